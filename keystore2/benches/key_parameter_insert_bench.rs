@@ -0,0 +1,89 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compares inserting a set of key parameters with `insert_key_parameters` against inserting
+//! them one at a time, each in its own implicit transaction.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use keystore2::database::insert_key_parameters;
+use keystore2::key_parameter::{KeyParameter, KeyParameterValue, SecurityLevel};
+use rusqlite::{params, Connection};
+
+const PARAMETER_COUNT: i64 = 100;
+
+fn new_connection() -> Connection {
+    let conn = Connection::open_in_memory().expect("Failed to open database.");
+    conn.execute("ATTACH DATABASE 'file::memory:' as persistent;", [])
+        .expect("Failed to attach persistent database.");
+    conn.execute(
+        "CREATE TABLE persistent.keyparameter (
+            keyentryid INTEGER,
+            tag INTEGER,
+            data ANY,
+            security_level INTEGER);",
+        [],
+    )
+    .expect("Failed to create keyparameter table.");
+    conn
+}
+
+/// Returns PARAMETER_COUNT parameters with distinct, repeatable tags.
+fn make_params() -> Vec<KeyParameter> {
+    (0..PARAMETER_COUNT)
+        .map(|i| {
+            KeyParameter::new(
+                KeyParameterValue::UserSecureID(i),
+                SecurityLevel::TRUSTED_ENVIRONMENT,
+            )
+        })
+        .collect()
+}
+
+fn insert_one_by_one(conn: &Connection, key_id: i64, params: &[KeyParameter]) {
+    for p in params {
+        conn.execute(
+            "INSERT into persistent.keyparameter (keyentryid, tag, data, security_level)
+            VALUES (?, ?, ?, ?);",
+            params![key_id, p.get_tag().0, p.key_parameter_value(), p.security_level().0],
+        )
+        .expect("Failed to insert key parameter.");
+    }
+}
+
+fn bench_insert_key_parameters(c: &mut Criterion) {
+    let params = make_params();
+    let mut group = c.benchmark_group("insert_key_parameters");
+    group.bench_function("single_inserts", |b| {
+        b.iter_batched(
+            new_connection,
+            |conn| insert_one_by_one(&conn, 1, &params),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("batch_insert", |b| {
+        b.iter_batched(
+            new_connection,
+            |mut conn| {
+                let tx = conn.transaction().expect("Failed to begin transaction.");
+                insert_key_parameters(&tx, 1, &params).expect("Failed to insert.");
+                tx.commit().expect("Failed to commit transaction.");
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_insert_key_parameters);
+criterion_main!(benches);
@@ -0,0 +1,209 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compares the peak heap usage of loading the parameters of a key with
+//! `KeystoreDB::load_key_parameters`, which collects all of them, against processing them one
+//! at a time with `KeystoreDB::load_key_parameters_iter`. Heap usage is measured by a counting
+//! global allocator and reported in bytes per iteration.
+
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use keystore2::database::{insert_key_parameters, KeystoreDB};
+use keystore2::key_parameter::{KeyParameter, KeyParameterValue, SecurityLevel};
+use rusqlite::Connection;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const PARAMETER_COUNT: i64 = 1000;
+
+const KEY_ID: i64 = 1;
+
+/// Wraps the system allocator and keeps track of the current and the peak number of allocated
+/// bytes.
+struct CountingAllocator {
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl CountingAllocator {
+    /// Resets the peak to the current number of allocated bytes and returns it.
+    fn reset_peak(&self) -> usize {
+        let current = self.current.load(Ordering::SeqCst);
+        self.peak.store(current, Ordering::SeqCst);
+        current
+    }
+
+    fn peak(&self) -> usize {
+        self.peak.load(Ordering::SeqCst)
+    }
+}
+
+// SAFETY: All allocations are forwarded to the system allocator unchanged.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: The caller upholds the contract of GlobalAlloc::alloc.
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let current = self.current.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            self.peak.fetch_max(current, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: The caller upholds the contract of GlobalAlloc::dealloc.
+        unsafe { System.dealloc(ptr, layout) };
+        self.current.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator =
+    CountingAllocator { current: AtomicUsize::new(0), peak: AtomicUsize::new(0) };
+
+/// Measures the peak number of bytes allocated above the level at the start of the
+/// measurement.
+struct PeakHeapUsage;
+
+struct BytesFormatter;
+
+impl ValueFormatter for BytesFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        "B"
+    }
+
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        _throughput: &Throughput,
+        _values: &mut [f64],
+    ) -> &'static str {
+        "B"
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "B"
+    }
+}
+
+impl Measurement for PeakHeapUsage {
+    type Intermediate = usize;
+    type Value = usize;
+
+    fn start(&self) -> Self::Intermediate {
+        ALLOCATOR.reset_peak()
+    }
+
+    fn end(&self, start: Self::Intermediate) -> Self::Value {
+        ALLOCATOR.peak() - start
+    }
+
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+
+    fn zero(&self) -> Self::Value {
+        0
+    }
+
+    fn to_f64(&self, value: &Self::Value) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &BytesFormatter
+    }
+}
+
+/// Returns a connection holding a key with PARAMETER_COUNT parameters.
+fn new_connection() -> Connection {
+    let mut conn = Connection::open_in_memory().expect("Failed to open database.");
+    conn.execute("ATTACH DATABASE 'file::memory:' as persistent;", [])
+        .expect("Failed to attach persistent database.");
+    conn.execute(
+        "CREATE TABLE persistent.keyparameter (
+            keyentryid INTEGER,
+            tag INTEGER,
+            data ANY,
+            security_level INTEGER);",
+        [],
+    )
+    .expect("Failed to create keyparameter table.");
+    let params: Vec<KeyParameter> = (0..PARAMETER_COUNT)
+        .map(|i| {
+            KeyParameter::new(
+                KeyParameterValue::UserSecureID(i),
+                SecurityLevel::TRUSTED_ENVIRONMENT,
+            )
+        })
+        .collect();
+    let tx = conn.transaction().expect("Failed to begin transaction.");
+    insert_key_parameters(&tx, KEY_ID, &params).expect("Failed to insert.");
+    tx.commit().expect("Failed to commit transaction.");
+    conn
+}
+
+/// Mirrors what enforcement does with the loaded parameters: it only keeps the user secure ids.
+fn user_secure_id(p: &KeyParameter) -> Option<i64> {
+    match p.key_parameter_value() {
+        KeyParameterValue::UserSecureID(sid) => Some(*sid),
+        _ => None,
+    }
+}
+
+fn bench_load_key_parameters(c: &mut Criterion<PeakHeapUsage>) {
+    let mut conn = new_connection();
+    let mut group = c.benchmark_group("load_key_parameters_peak_heap");
+    group.bench_function("eager", |b| {
+        b.iter_custom(|iters| {
+            let mut total = 0;
+            for _ in 0..iters {
+                let tx = conn.transaction().expect("Failed to begin transaction.");
+                let start = PeakHeapUsage.start();
+                let params = KeystoreDB::load_key_parameters(KEY_ID, &tx).expect("Failed to load.");
+                let sids: Vec<i64> = params.iter().filter_map(user_secure_id).collect();
+                total += PeakHeapUsage.end(start);
+                assert_eq!(sids.len() as i64, PARAMETER_COUNT);
+            }
+            total
+        })
+    });
+    group.bench_function("lazy", |b| {
+        b.iter_custom(|iters| {
+            let mut total = 0;
+            for _ in 0..iters {
+                let tx = conn.transaction().expect("Failed to begin transaction.");
+                let start = PeakHeapUsage.start();
+                let mut stmt =
+                    KeystoreDB::prepare_load_key_parameters(&tx).expect("Failed to prepare.");
+                let sids: Vec<i64> = KeystoreDB::load_key_parameters_iter(&mut stmt, KEY_ID)
+                    .expect("Failed to query.")
+                    .filter_map(|p| user_secure_id(&p.expect("Failed to load.")))
+                    .collect();
+                total += PeakHeapUsage.end(start);
+                assert_eq!(sids.len() as i64, PARAMETER_COUNT);
+            }
+            total
+        })
+    });
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    // The peak heap usage does not vary between iterations, which the plots cannot handle.
+    config = Criterion::default().with_measurement(PeakHeapUsage).without_plots();
+    targets = bench_load_key_parameters
+}
+criterion_main!(benches);
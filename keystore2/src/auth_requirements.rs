@@ -0,0 +1,70 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module derives the user authentication requirements of a key from its key
+//! parameters, i.e., whether the user must authenticate before the key can be used, with
+//! which kind of authenticator, and for how long an authentication remains valid.
+
+use crate::key_parameter::{HardwareAuthenticatorType, KeyParameter, KeyParameterValue};
+
+#[cfg(test)]
+mod tests;
+
+/// The user authentication requirements of a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthenticationRequirements {
+    /// True if the user must authenticate before the key can be used.
+    pub requires_auth: bool,
+    /// The number of seconds an authentication remains valid. None if the user must
+    /// authenticate for every operation or if no authentication is required.
+    pub timeout_seconds: Option<i32>,
+    /// Bit mask of the authenticator types that can be used to authenticate, e.g.,
+    /// `PASSWORD | FINGERPRINT`. NONE if no authentication is required.
+    pub authenticator_types: HardwareAuthenticatorType,
+}
+
+impl AuthenticationRequirements {
+    /// Derives the authentication requirements from the NoAuthRequired, UserSecureID,
+    /// AuthTimeout, and HardwareAuthenticatorType parameters of a key. Authentication is
+    /// required if the key is bound to at least one secure user id and NoAuthRequired is
+    /// absent. Multiple HardwareAuthenticatorType parameters are combined.
+    pub fn from_key_parameters(params: &[KeyParameter]) -> Self {
+        let mut no_auth_required = false;
+        let mut has_secure_id = false;
+        let mut timeout_seconds = None;
+        let mut authenticator_types = 0;
+        for kp in params {
+            match kp.key_parameter_value() {
+                KeyParameterValue::NoAuthRequired => no_auth_required = true,
+                KeyParameterValue::UserSecureID(_) => has_secure_id = true,
+                KeyParameterValue::AuthTimeout(t) => timeout_seconds = Some(*t),
+                KeyParameterValue::HardwareAuthenticatorType(t) => authenticator_types |= t.0,
+                _ => {}
+            }
+        }
+
+        if no_auth_required || !has_secure_id {
+            return Self {
+                requires_auth: false,
+                timeout_seconds: None,
+                authenticator_types: HardwareAuthenticatorType::NONE,
+            };
+        }
+        Self {
+            requires_auth: true,
+            timeout_seconds,
+            authenticator_types: HardwareAuthenticatorType(authenticator_types),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Authentication requirements tests.
+
+use super::*;
+use crate::key_parameter::{Algorithm, SecurityLevel};
+
+fn requirements(values: Vec<KeyParameterValue>) -> AuthenticationRequirements {
+    let params: Vec<KeyParameter> = values
+        .into_iter()
+        .map(|v| KeyParameter::new(v, SecurityLevel::TRUSTED_ENVIRONMENT))
+        .collect();
+    AuthenticationRequirements::from_key_parameters(&params)
+}
+
+#[test]
+fn no_auth() {
+    let expected = AuthenticationRequirements {
+        requires_auth: false,
+        timeout_seconds: None,
+        authenticator_types: HardwareAuthenticatorType::NONE,
+    };
+    assert_eq!(
+        requirements(vec![
+            KeyParameterValue::Algorithm(Algorithm::EC),
+            KeyParameterValue::NoAuthRequired
+        ]),
+        expected
+    );
+    assert_eq!(requirements(vec![KeyParameterValue::Algorithm(Algorithm::EC)]), expected);
+}
+
+#[test]
+fn auth_per_use() {
+    assert_eq!(
+        requirements(vec![
+            KeyParameterValue::UserSecureID(42),
+            KeyParameterValue::HardwareAuthenticatorType(HardwareAuthenticatorType::PASSWORD),
+        ]),
+        AuthenticationRequirements {
+            requires_auth: true,
+            timeout_seconds: None,
+            authenticator_types: HardwareAuthenticatorType::PASSWORD,
+        }
+    );
+}
+
+#[test]
+fn auth_timeout_60s() {
+    assert_eq!(
+        requirements(vec![
+            KeyParameterValue::UserSecureID(42),
+            KeyParameterValue::HardwareAuthenticatorType(HardwareAuthenticatorType::PASSWORD),
+            KeyParameterValue::AuthTimeout(60),
+        ]),
+        AuthenticationRequirements {
+            requires_auth: true,
+            timeout_seconds: Some(60),
+            authenticator_types: HardwareAuthenticatorType::PASSWORD,
+        }
+    );
+}
+
+#[test]
+fn biometric_only() {
+    assert_eq!(
+        requirements(vec![
+            KeyParameterValue::UserSecureID(7),
+            KeyParameterValue::HardwareAuthenticatorType(HardwareAuthenticatorType::FINGERPRINT),
+        ]),
+        AuthenticationRequirements {
+            requires_auth: true,
+            timeout_seconds: None,
+            authenticator_types: HardwareAuthenticatorType::FINGERPRINT,
+        }
+    );
+}
+
+#[test]
+fn fingerprint_and_password() {
+    let expected = AuthenticationRequirements {
+        requires_auth: true,
+        timeout_seconds: Some(30),
+        authenticator_types: HardwareAuthenticatorType(
+            HardwareAuthenticatorType::FINGERPRINT.0 | HardwareAuthenticatorType::PASSWORD.0,
+        ),
+    };
+    assert_eq!(
+        requirements(vec![
+            KeyParameterValue::UserSecureID(7),
+            KeyParameterValue::UserSecureID(8),
+            KeyParameterValue::HardwareAuthenticatorType(HardwareAuthenticatorType(
+                HardwareAuthenticatorType::FINGERPRINT.0 | HardwareAuthenticatorType::PASSWORD.0,
+            )),
+            KeyParameterValue::AuthTimeout(30),
+        ]),
+        expected
+    );
+    // The same requirements expressed as separate parameters.
+    assert_eq!(
+        requirements(vec![
+            KeyParameterValue::UserSecureID(7),
+            KeyParameterValue::HardwareAuthenticatorType(HardwareAuthenticatorType::FINGERPRINT),
+            KeyParameterValue::HardwareAuthenticatorType(HardwareAuthenticatorType::PASSWORD),
+            KeyParameterValue::AuthTimeout(30),
+        ]),
+        expected
+    );
+}
@@ -28,6 +28,7 @@
 #include <openssl/hkdf.h>
 #include <openssl/hmac.h>
 #include <openssl/rand.h>
+#include <openssl/sha.h>
 #include <openssl/x509.h>
 
 #include <vector>
@@ -76,6 +77,10 @@ bool hmacSha256(const uint8_t* key, size_t key_size, const uint8_t* msg, size_t
     return (p != nullptr);
 }
 
+bool sha256Digest(const uint8_t* data, size_t data_size, uint8_t* out) {
+    return SHA256(data, data_size, out) != nullptr;
+}
+
 bool randomBytes(uint8_t* out, size_t len) {
     return RAND_bytes(out, len);
 }
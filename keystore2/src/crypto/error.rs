@@ -99,6 +99,10 @@ pub enum Error {
     #[error("Failed to calculate HMAC-SHA256.")]
     HmacSha256Failed,
 
+    /// This is returned if the C implementation of sha256Digest failed.
+    #[error("Failed to calculate SHA-256.")]
+    Sha256Failed,
+
     /// Zvec error.
     #[error(transparent)]
     ZVec(#[from] zvec::Error),
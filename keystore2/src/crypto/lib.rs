@@ -19,10 +19,11 @@ mod error;
 pub mod zvec;
 pub use error::Error;
 use keystore2_crypto_bindgen::{
-    extractSubjectFromCertificate, hmacSha256, randomBytes, AES_gcm_decrypt, AES_gcm_encrypt,
-    ECDHComputeKey, ECKEYGenerateKey, ECKEYMarshalPrivateKey, ECKEYParsePrivateKey,
-    ECPOINTOct2Point, ECPOINTPoint2Oct, EC_KEY_free, EC_KEY_get0_public_key, EC_POINT_free,
-    HKDFExpand, HKDFExtract, EC_KEY, EC_MAX_BYTES, EC_POINT, EVP_MAX_MD_SIZE, PBKDF2,
+    extractSubjectFromCertificate, hmacSha256, randomBytes, sha256Digest, AES_gcm_decrypt,
+    AES_gcm_encrypt, ECDHComputeKey, ECKEYGenerateKey, ECKEYMarshalPrivateKey,
+    ECKEYParsePrivateKey, ECPOINTOct2Point, ECPOINTPoint2Oct, EC_KEY_free, EC_KEY_get0_public_key,
+    EC_POINT_free, HKDFExpand, HKDFExtract, EC_KEY, EC_MAX_BYTES, EC_POINT, EVP_MAX_MD_SIZE,
+    PBKDF2,
 };
 use std::convert::TryFrom;
 use std::convert::TryInto;
@@ -41,6 +42,8 @@ pub const AES_128_KEY_LENGTH: usize = 16;
 pub const SALT_LENGTH: usize = 16;
 /// Length of an HMAC-SHA256 tag in bytes.
 pub const HMAC_SHA256_LEN: usize = 32;
+/// Length of a SHA-256 digest in bytes.
+pub const SHA256_LEN: usize = 32;
 
 /// Older versions of keystore produced IVs with four extra
 /// ignored zero bytes at the end; recognise and trim those.
@@ -89,6 +92,18 @@ pub fn hmac_sha256(key: &[u8], msg: &[u8]) -> Result<Vec<u8>, Error> {
     }
 }
 
+/// Calculate the SHA-256 digest of the given data.
+pub fn sha256(data: &[u8]) -> Result<[u8; SHA256_LEN], Error> {
+    let mut digest = [0; SHA256_LEN];
+    // Safety: The first pair of arguments must point to a const buffer with size given by the
+    // second arg of the pair. The last argument must point to an output buffer of SHA256_LEN
+    // bytes.
+    match unsafe { sha256Digest(data.as_ptr(), data.len(), digest.as_mut_ptr()) } {
+        true => Ok(digest),
+        false => Err(Error::Sha256Failed),
+    }
+}
+
 /// Uses AES GCM to decipher a message given an initialization vector, aead tag, and key.
 /// This function accepts 128 and 256-bit keys and uses AES128 and AES256 respectively based
 /// on the key length.
@@ -604,4 +619,16 @@ mod tests {
         assert_eq!(tag2.len(), HMAC_SHA256_LEN);
         assert_ne!(tag1a, tag2);
     }
+
+    #[test]
+    fn test_sha256() {
+        // The SHA-256 digest of "abc" from FIPS 180-2, appendix B.1.
+        let expected = [
+            0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
+            0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
+            0xf2, 0x00, 0x15, 0xad,
+        ];
+        assert_eq!(sha256(b"abc").unwrap(), expected);
+        assert_eq!(sha256(b"").unwrap().len(), SHA256_LEN);
+    }
 }
@@ -84,7 +84,7 @@ use rusqlite::{
     types::FromSqlResult,
     types::ToSqlOutput,
     types::{FromSqlError, Value, ValueRef},
-    Connection, OptionalExtension, ToSql, Transaction,
+    Connection, OptionalExtension, Statement, ToSql, Transaction,
 };
 
 use std::{
@@ -766,6 +766,37 @@ impl<T> DoGc<T> for Result<T> {
     }
 }
 
+/// Inserts all of the given key parameters into the `persistent.keyparameter` table and
+/// associates them with `key_id`. All rows are inserted within the given transaction using a
+/// single prepared statement, so either all or none of the parameters are stored.
+pub fn insert_key_parameters(tx: &Transaction, key_id: i64, params: &[KeyParameter]) -> Result<()> {
+    let mut stmt = tx
+        .prepare_cached(
+            "INSERT into persistent.keyparameter (keyentryid, tag, data, security_level)
+            VALUES (?, ?, ?, ?);",
+        )
+        .context(ks_err!("Failed to prepare statement."))?;
+
+    for p in params.iter() {
+        stmt.insert(params![key_id, p.get_tag().0, p.key_parameter_value(), p.security_level().0])
+            .with_context(|| ks_err!("Failed to insert {:?}", p))?;
+    }
+    Ok(())
+}
+
+/// Creates the index `kp_tag_idx` on the `keyentryid` and `tag` columns of the
+/// `persistent.keyparameter` table if it does not exist yet. With this index, looking up a
+/// single tag of a key, e.g., USAGE_COUNT_LIMIT, no longer scans all parameters of the key.
+pub fn ensure_tag_index(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS persistent.kp_tag_idx
+        ON keyparameter(keyentryid, tag);",
+        [],
+    )
+    .context(ks_err!("Failed to create index kp_tag_idx."))?;
+    Ok(())
+}
+
 /// KeystoreDB wraps a connection to an SQLite database and tracks its
 /// ownership. It also implements all of Keystore 2.0's database functionality.
 pub struct KeystoreDB {
@@ -870,8 +901,9 @@ pub struct SupersededBlob {
 
 impl KeystoreDB {
     const UNASSIGNED_KEY_ID: i64 = -1i64;
-    const CURRENT_DB_VERSION: u32 = 1;
-    const UPGRADERS: &'static [fn(&Transaction) -> Result<u32>] = &[Self::from_0_to_1];
+    const CURRENT_DB_VERSION: u32 = 2;
+    const UPGRADERS: &'static [fn(&Transaction) -> Result<u32>] =
+        &[Self::from_0_to_1, Self::from_1_to_2];
 
     /// Name of the file that holds the cross-boot persistent database.
     pub const PERSISTENT_DB_FILENAME: &'static str = "persistent.sqlite";
@@ -916,6 +948,13 @@ impl KeystoreDB {
         Ok(1)
     }
 
+    // This upgrade function adds the index kp_tag_idx on the keyentryid and tag columns of the
+    // keyparameter table. The index keyparameter_keyentryid_index is kept.
+    fn from_1_to_2(tx: &Transaction) -> Result<u32> {
+        ensure_tag_index(tx).context(ks_err!())?;
+        Ok(2)
+    }
+
     fn init_tables(tx: &Transaction) -> Result<()> {
         tx.execute(
             "CREATE TABLE IF NOT EXISTS persistent.keyentry (
@@ -996,6 +1035,8 @@ impl KeystoreDB {
         )
         .context("Failed to create index keyparameter_keyentryid_index.")?;
 
+        ensure_tag_index(tx)?;
+
         tx.execute(
             "CREATE TABLE IF NOT EXISTS persistent.keymetadata (
                      keyentryid INTEGER,
@@ -1568,35 +1609,11 @@ impl KeystoreDB {
     #[cfg(test)]
     fn insert_keyparameter(&mut self, key_id: &KeyIdGuard, params: &[KeyParameter]) -> Result<()> {
         self.with_transaction(Immediate("TX_insert_keyparameter"), |tx| {
-            Self::insert_keyparameter_internal(tx, key_id, params).no_gc()
+            insert_key_parameters(tx, key_id.0, params).no_gc()
         })
         .context(ks_err!())
     }
 
-    fn insert_keyparameter_internal(
-        tx: &Transaction,
-        key_id: &KeyIdGuard,
-        params: &[KeyParameter],
-    ) -> Result<()> {
-        let mut stmt = tx
-            .prepare(
-                "INSERT into persistent.keyparameter (keyentryid, tag, data, security_level)
-                VALUES (?, ?, ?, ?);",
-            )
-            .context(ks_err!("Failed to prepare statement."))?;
-
-        for p in params.iter() {
-            stmt.insert(params![
-                key_id.0,
-                p.get_tag().0,
-                p.key_parameter_value(),
-                p.security_level().0
-            ])
-            .with_context(|| ks_err!("Failed to insert {:?}", p))?;
-        }
-        Ok(())
-    }
-
     /// Insert a set of key entry specific metadata into the database.
     #[cfg(test)]
     fn insert_key_metadata(&mut self, key_id: &KeyIdGuard, metadata: &KeyMetaData) -> Result<()> {
@@ -1725,7 +1742,9 @@ impl KeystoreDB {
 
     /// Store a new key in a single transaction.
     /// The function creates a new key entry, populates the blob, key parameter, and metadata
-    /// fields, and rebinds the given alias to the new key.
+    /// fields, and rebinds the given alias to the new key. Fails with SYSTEM_ERROR if one of the
+    /// key parameters must not be stored, e.g., NONCE or the ATTESTATION_ID_* parameters. Callers
+    /// drop those beforehand, see `retain_storable`.
     /// The boolean returned is a hint for the garbage collector. If true, a key was replaced,
     /// is now unreferenced and needs to be collected.
     #[allow(clippy::too_many_arguments)]
@@ -1751,6 +1770,10 @@ impl KeystoreDB {
                     .context(ks_err!("Need alias and domain must be APP or SELINUX."));
             }
         };
+        if let Some(p) = params.iter().find(|p| !p.is_storable()) {
+            return Err(KsError::sys())
+                .context(ks_err!("Key parameter {:?} must not be stored.", p.get_tag()));
+        }
         self.with_transaction(Immediate("TX_store_new_key"), |tx| {
             let key_id = Self::create_key_entry_internal(tx, &domain, namespace, key_type, km_uuid)
                 .context("Trying to create new key entry.")?;
@@ -1797,7 +1820,7 @@ impl KeystoreDB {
                 )
                 .context("Trying to insert the certificate chain.")?;
             }
-            Self::insert_keyparameter_internal(tx, &key_id, params)
+            insert_key_parameters(tx, key_id.id(), params)
                 .context("Trying to insert key parameters.")?;
             metadata.store_in_db(key_id.id(), tx).context("Trying to insert key metadata.")?;
             let need_gc = Self::rebind_alias(tx, &key_id, alias, &domain, namespace, key_type)
@@ -2068,30 +2091,37 @@ impl KeystoreDB {
         Ok((has_km_blob, blob_info, cert_blob, cert_chain_blob))
     }
 
-    fn load_key_parameters(key_id: i64, tx: &Transaction) -> Result<Vec<KeyParameter>> {
-        let mut stmt = tx
-            .prepare(
-                "SELECT tag, data, security_level from persistent.keyparameter
-                    WHERE keyentryid = ?;",
-            )
-            .context("In load_key_parameters: prepare statement failed.")?;
-
-        let mut parameters: Vec<KeyParameter> = Vec::new();
+    /// Loads all key parameters of the given key by collecting `load_key_parameters_iter`.
+    pub fn load_key_parameters(key_id: i64, tx: &Transaction) -> Result<Vec<KeyParameter>> {
+        let mut stmt = Self::prepare_load_key_parameters(tx)?;
+        let parameters = Self::load_key_parameters_iter(&mut stmt, key_id)?
+            .collect::<Result<Vec<_>>>()
+            .context(ks_err!())?;
+        Ok(parameters)
+    }
 
-        let mut rows =
-            stmt.query(params![key_id]).context("In load_key_parameters: query failed.")?;
-        db_utils::with_rows_extract_all(&mut rows, |row| {
-            let tag = Tag(row.get(0).context("Failed to read tag.")?);
-            let sec_level = SecurityLevel(row.get(2).context("Failed to read sec_level.")?);
-            parameters.push(
-                KeyParameter::new_from_sql(tag, &SqlField::new(1, row), sec_level)
-                    .context("Failed to read KeyParameter.")?,
-            );
-            Ok(())
-        })
-        .context(ks_err!())?;
+    /// Prepares the statement used by `load_key_parameters_iter`. The statement borrows the
+    /// connection, and the iterator borrows the statement, so the statement has to be kept
+    /// alive by the caller while iterating.
+    pub fn prepare_load_key_parameters(conn: &Connection) -> Result<Statement<'_>> {
+        conn.prepare(
+            "SELECT tag, data, security_level from persistent.keyparameter
+                WHERE keyentryid = ?;",
+        )
+        .context("In prepare_load_key_parameters: prepare statement failed.")
+    }
 
-        Ok(parameters)
+    /// Returns a lazy iterator over the key parameters of the given key. Unlike
+    /// `load_key_parameters`, the rows are decoded one at a time as the iterator is advanced,
+    /// so that keys with many parameters can be processed without collecting all of them.
+    pub fn load_key_parameters_iter<'stmt>(
+        stmt: &'stmt mut Statement,
+        key_id: i64,
+    ) -> Result<impl Iterator<Item = Result<KeyParameter>> + 'stmt> {
+        let rows = stmt
+            .query_map(params![key_id], |row| Ok(KeyParameter::from_sql_row(row)))
+            .context("In load_key_parameters_iter: query failed.")?;
+        Ok(rows.map(|r| r.context("In load_key_parameters_iter: Failed to read row.")?))
     }
 
     /// Decrements the usage count of a limited use key. This function first checks whether the
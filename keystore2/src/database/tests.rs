@@ -16,8 +16,9 @@
 
 use super::*;
 use crate::key_parameter::{
-    Algorithm, BlockMode, Digest, EcCurve, HardwareAuthenticatorType, KeyOrigin, KeyParameter,
-    KeyParameterValue, KeyPurpose, PaddingMode, SecurityLevel,
+    Algorithm, BlockMode, Digest, EcCurve, HardwareAuthenticatorType, KeyOrigin,
+    KeyParameter, KeyParameterBuilder, KeyParameterValue, KeyPurpose, PaddingMode, SecurityLevel,
+    retain_storable,
 };
 use crate::key_perm_set;
 use crate::permission::{KeyPerm, KeyPermSet};
@@ -38,7 +39,6 @@ use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, SystemTime};
 use crate::utils::AesGcm;
-#[cfg(disabled)]
 use std::time::Instant;
 
 pub fn new_test_db() -> Result<KeystoreDB> {
@@ -555,6 +555,75 @@ fn test_insert_and_load_full_keyentry_domain_app() -> Result<()> {
     Ok(())
 }
 
+fn store_test_key_with_params(db: &mut KeystoreDB, params: &[KeyParameter]) -> Result<KeyEntry> {
+    let key = KeyDescriptor {
+        domain: Domain::APP,
+        nspace: 1,
+        alias: Some(TEST_ALIAS.to_string()),
+        blob: None,
+    };
+    let blob_metadata = BlobMetaData::new();
+    db.store_new_key(
+        &key,
+        KeyType::Client,
+        params,
+        &BlobInfo::new(TEST_KEY_BLOB, &blob_metadata),
+        &CertificateInfo::new(None, None),
+        &KeyMetaData::new(),
+        &KEYSTORE_UUID,
+    )?;
+    let (_key_guard, key_entry) =
+        db.load_key_entry(&key, KeyType::Client, KeyEntryLoadBits::NONE, 1, |_k, _av| Ok(()))?;
+    Ok(key_entry)
+}
+
+#[test]
+fn test_store_new_key_rejects_unstorable_params() -> Result<()> {
+    let mut db = new_test_db()?;
+    let params = vec![
+        KeyParameter::new(KeyParameterValue::KeySize(256), SecurityLevel::TRUSTED_ENVIRONMENT),
+        KeyParameter::new(KeyParameterValue::Nonce(vec![1, 2, 3]), SecurityLevel::KEYSTORE),
+    ];
+    let err = store_test_key_with_params(&mut db, &params).unwrap_err();
+    assert_eq!(Some(&KsError::sys()), err.root_cause().downcast_ref::<KsError>());
+    // The caller is expected to drop the parameters that must not be stored.
+    let key_entry = store_test_key_with_params(&mut db, &retain_storable(params.clone()))?;
+    assert_eq!(key_entry.into_key_parameters(), params[..1]);
+    Ok(())
+}
+
+#[test]
+fn test_store_new_key_keeps_sensitive_storable_params() -> Result<()> {
+    let mut db = new_test_db()?;
+    // APPLICATION_ID must not be logged, but it is stored like any other key parameter.
+    let params = vec![
+        KeyParameter::new(KeyParameterValue::KeySize(256), SecurityLevel::TRUSTED_ENVIRONMENT),
+        KeyParameter::new(
+            KeyParameterValue::ApplicationID(vec![4, 5]),
+            SecurityLevel::TRUSTED_ENVIRONMENT,
+        ),
+    ];
+    assert!(params[1].key_parameter_value().is_sensitive());
+    assert!(params[1].is_storable());
+    let key_entry = store_test_key_with_params(&mut db, &params)?;
+    assert_eq!(key_entry.into_key_parameters(), params);
+    Ok(())
+}
+
+#[test]
+fn test_store_new_key_accepts_identical_duplicates() -> Result<()> {
+    let mut db = new_test_db()?;
+    // KeyMint or a legacy blob may repeat a single valued tag with the same value.
+    let params = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .algorithm(Algorithm::EC)
+        .key_size(256)
+        .key_size(256)
+        .build();
+    let key_entry = store_test_key_with_params(&mut db, &params)?;
+    assert_eq!(key_entry.into_key_parameters(), params);
+    Ok(())
+}
+
 #[test]
 fn test_insert_and_load_certificate_entry_domain_app() -> Result<()> {
     let mut db = new_test_db()?;
@@ -1569,6 +1638,154 @@ fn get_keyentry(db: &KeystoreDB) -> Result<Vec<KeyEntryRow>> {
         .collect::<Result<Vec<_>>>()
 }
 
+fn insert_test_key_parameters(
+    db: &mut KeystoreDB,
+    key_id: i64,
+    params: &[KeyParameter],
+) -> Result<()> {
+    db.with_transaction(Immediate("TX_insert_test_key_parameters"), |tx| {
+        insert_key_parameters(tx, key_id, params).no_gc()
+    })
+}
+
+fn load_test_key_parameters(db: &mut KeystoreDB, key_id: i64) -> Result<Vec<KeyParameter>> {
+    let mut params = db.with_transaction(Immediate("TX_load_test_key_parameters"), |tx| {
+        KeystoreDB::load_key_parameters(key_id, tx).no_gc()
+    })?;
+    params.sort();
+    Ok(params)
+}
+
+#[test]
+fn test_insert_key_parameters() -> Result<()> {
+    let mut db = new_test_db()?;
+    let mut params = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .algorithm(Algorithm::EC)
+        .ec_curve(EcCurve::P_256)
+        .purpose(KeyPurpose::SIGN)
+        .purpose(KeyPurpose::VERIFY)
+        .user_secure_id(42)
+        .user_secure_id(43)
+        .at_level(SecurityLevel::KEYSTORE)
+        .creation_datetime(1704067200000)
+        .build();
+    insert_test_key_parameters(&mut db, 1, &params)?;
+    params.sort();
+    assert_eq!(load_test_key_parameters(&mut db, 1)?, params);
+    Ok(())
+}
+
+#[test]
+fn test_load_key_parameters_iter_matches_eager_load() -> Result<()> {
+    let mut db = new_test_db()?;
+    let mut builder = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT);
+    builder.algorithm(Algorithm::EC);
+    for i in 0..1000 {
+        builder.user_secure_id(i);
+    }
+    insert_test_key_parameters(&mut db, 1, &builder.build())?;
+
+    let (eager, lazy) = db.with_transaction(Immediate("TX_test_load_iter"), |tx| {
+        let eager = KeystoreDB::load_key_parameters(1, tx)?;
+        let mut stmt = KeystoreDB::prepare_load_key_parameters(tx)?;
+        let lazy: Result<Vec<_>> = KeystoreDB::load_key_parameters_iter(&mut stmt, 1)?.collect();
+        lazy.map(|lazy| (eager, lazy)).no_gc()
+    })?;
+    assert_eq!(eager.len(), 1001);
+    assert_eq!(eager, lazy);
+    Ok(())
+}
+
+#[test]
+fn test_tag_index() -> Result<()> {
+    let mut db = new_test_db()?;
+    let mut builder = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT);
+    for i in 0..499 {
+        builder.user_secure_id(i);
+    }
+    builder.algorithm(Algorithm::EC);
+    insert_test_key_parameters(&mut db, 1, &builder.build())?;
+
+    let plan: String = db.conn.query_row(
+        "EXPLAIN QUERY PLAN
+        SELECT data FROM persistent.keyparameter WHERE keyentryid = 1 AND tag = ?;",
+        params![Tag::ALGORITHM.0],
+        |row| row.get(3),
+    )?;
+    assert!(plan.contains("kp_tag_idx"), "Unexpected query plan: {plan}");
+
+    // Creating the index is idempotent.
+    ensure_tag_index(&db.conn)?;
+    ensure_tag_index(&db.conn)?;
+    Ok(())
+}
+
+fn keyparameter_indices(db: &KeystoreDB) -> Result<Vec<String>> {
+    let mut stmt = db.conn.prepare(
+        "SELECT name FROM persistent.sqlite_master
+        WHERE type = 'index' AND tbl_name = 'keyparameter' ORDER BY name;",
+    )?;
+    let names = stmt.query_map([], |row| row.get(0))?.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(names)
+}
+
+#[test]
+fn test_upgrade_1_to_2() -> Result<()> {
+    const ALIAS: &str = "test_upgrade_1_to_2";
+    const UID: u32 = 33;
+    let temp_dir = TempDir::new("test_upgrade_1_to_2")?;
+    let key_id = {
+        let mut db = KeystoreDB::new(temp_dir.path(), None)?;
+        let key_id = make_test_key_entry(&mut db, Domain::APP, UID as i64, ALIAS, None)?.id();
+
+        // Turn the database into one at version 1, which did not have the index kp_tag_idx.
+        db.conn.execute("DROP INDEX persistent.kp_tag_idx;", [])?;
+        db.conn.execute("UPDATE persistent.version SET version = 1 WHERE id = 0;", [])?;
+        assert_eq!(keyparameter_indices(&db)?, vec!["keyparameter_keyentryid_index".to_string()]);
+        key_id
+    };
+
+    let mut db = KeystoreDB::new(temp_dir.path(), None)?;
+    let version: u32 =
+        db.conn.query_row("SELECT version FROM persistent.version WHERE id = 0;", [], |row| {
+            row.get(0)
+        })?;
+    assert_eq!(version, 2);
+    assert_eq!(
+        keyparameter_indices(&db)?,
+        vec!["keyparameter_keyentryid_index".to_string(), "kp_tag_idx".to_string()]
+    );
+
+    let (_, key_entry) = db.load_key_entry(
+        &KeyDescriptor {
+            domain: Domain::APP,
+            nspace: -1,
+            alias: Some(ALIAS.to_string()),
+            blob: None,
+        },
+        KeyType::Client,
+        KeyEntryLoadBits::BOTH,
+        UID,
+        |_, _| Ok(()),
+    )?;
+    assert_eq!(key_entry, make_test_key_entry_test_vector(key_id, None));
+    Ok(())
+}
+
+#[test]
+fn test_insert_key_parameters_same_tag_at_different_levels() -> Result<()> {
+    let mut db = new_test_db()?;
+    let mut params = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .key_size(256)
+        .at_level(SecurityLevel::KEYSTORE)
+        .key_size(256)
+        .build();
+    insert_test_key_parameters(&mut db, 1, &params)?;
+    params.sort();
+    assert_eq!(load_test_key_parameters(&mut db, 1)?, params);
+    Ok(())
+}
+
 fn make_test_params(max_usage_count: Option<i32>) -> Vec<KeyParameter> {
     make_test_params_with_sids(max_usage_count, &[42])
 }
@@ -1576,12 +1793,15 @@ fn make_test_params(max_usage_count: Option<i32>) -> Vec<KeyParameter> {
 // Note: The parameters and SecurityLevel associations are nonsensical. This
 // collection is only used to check if the parameters are preserved as expected by the
 // database.
+// The collection includes deprecated parameters such as AllowWhileOnBody, which existing
+// databases may still hold.
+#[allow(deprecated)]
 fn make_test_params_with_sids(
     max_usage_count: Option<i32>,
     user_secure_ids: &[i64],
 ) -> Vec<KeyParameter> {
     let mut params = vec![
-        KeyParameter::new(KeyParameterValue::Invalid, SecurityLevel::TRUSTED_ENVIRONMENT),
+        KeyParameter::new(KeyParameterValue::Invalid(0), SecurityLevel::TRUSTED_ENVIRONMENT),
         KeyParameter::new(
             KeyParameterValue::KeyPurpose(KeyPurpose::SIGN),
             SecurityLevel::TRUSTED_ENVIRONMENT,
@@ -1636,14 +1856,8 @@ fn make_test_params_with_sids(
             SecurityLevel::TRUSTED_ENVIRONMENT,
         ),
         KeyParameter::new(KeyParameterValue::EcCurve(EcCurve::P_256), SecurityLevel::STRONGBOX),
-        KeyParameter::new(
-            KeyParameterValue::EcCurve(EcCurve::P_384),
-            SecurityLevel::TRUSTED_ENVIRONMENT,
-        ),
-        KeyParameter::new(
-            KeyParameterValue::EcCurve(EcCurve::P_521),
-            SecurityLevel::TRUSTED_ENVIRONMENT,
-        ),
+        KeyParameter::new(KeyParameterValue::EcCurve(EcCurve::P_384), SecurityLevel::KEYSTORE),
+        KeyParameter::new(KeyParameterValue::EcCurve(EcCurve::P_521), SecurityLevel::SOFTWARE),
         KeyParameter::new(
             KeyParameterValue::RSAPublicExponent(3),
             SecurityLevel::TRUSTED_ENVIRONMENT,
@@ -14,7 +14,10 @@
 
 use crate::error::Error as KsError;
 use anyhow::{Context, Result};
-use rusqlite::{types::FromSql, Row, Rows};
+use rusqlite::{
+    types::{FromSql, Type},
+    Row, Rows,
+};
 
 // Takes Rows as returned by a query call on prepared statement.
 // Extracts exactly one row with the `row_extractor` and fails if more
@@ -65,6 +68,10 @@ impl<'a> SqlField<'a> {
     pub fn get<T: FromSql>(&self) -> rusqlite::Result<T> {
         self.1.get(self.0)
     }
+    /// Returns the type of the column value, e.g., for error messages.
+    pub fn data_type(&self) -> rusqlite::Result<Type> {
+        self.1.get_ref(self.0).map(|v| v.data_type())
+    }
 }
 
 /// This macro implements two types to aid in the implementation of a type safe metadata
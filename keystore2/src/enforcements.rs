@@ -17,7 +17,9 @@
 use crate::ks_err;
 use crate::error::{map_binder_status, Error, ErrorCode};
 use crate::globals::{get_timestamp_service, ASYNC_TASK, DB, ENFORCEMENTS};
-use crate::key_parameter::{KeyParameter, KeyParameterValue};
+use crate::key_parameter::{
+    is_expired_for_origination, is_expired_for_usage, KeyParameter, KeyParameterValue,
+};
 use crate::{authorization::Error as AuthzError, super_key::SuperEncryptionType};
 use crate::{
     database::{AuthTokenEntry, BootTime},
@@ -456,6 +458,19 @@ impl Enforcements {
         let mut confirmation_token_receiver: Option<Arc<Mutex<Option<Receiver<Vec<u8>>>>>> = None;
         let mut max_boot_level: Option<i32> = None;
 
+        if let Some(now) = Enforcements::millis_since_epoch() {
+            let expired = match purpose {
+                KeyPurpose::ENCRYPT | KeyPurpose::SIGN => {
+                    is_expired_for_origination(key_params, now)
+                }
+                KeyPurpose::DECRYPT | KeyPurpose::VERIFY => is_expired_for_usage(key_params, now),
+                _ => false,
+            };
+            if expired {
+                return Err(Error::Km(Ec::KEY_EXPIRED)).context(ks_err!("key is expired."));
+            }
+        }
+
         // iterate through key parameters, recording information we need for authorization
         // enforcements later, or enforcing authorizations in place, where applicable
         for key_param in key_params.iter() {
@@ -484,20 +499,6 @@ impl Enforcements {
                             .context(ks_err!("key is not yet active."));
                     }
                 }
-                KeyParameterValue::OriginationExpireDateTime(o) => {
-                    if (purpose == KeyPurpose::ENCRYPT || purpose == KeyPurpose::SIGN)
-                        && Enforcements::is_given_time_passed(*o, false)
-                    {
-                        return Err(Error::Km(Ec::KEY_EXPIRED)).context(ks_err!("key is expired."));
-                    }
-                }
-                KeyParameterValue::UsageExpireDateTime(u) => {
-                    if (purpose == KeyPurpose::DECRYPT || purpose == KeyPurpose::VERIFY)
-                        && Enforcements::is_given_time_passed(*u, false)
-                    {
-                        return Err(Error::Km(Ec::KEY_EXPIRED)).context(ks_err!("key is expired."));
-                    }
-                }
                 KeyParameterValue::UserSecureID(s) => {
                     user_secure_ids.push(*s);
                 }
@@ -613,6 +614,12 @@ impl Enforcements {
         DB.with(|db| db.borrow().find_auth_token_entry(p))
     }
 
+    /// Returns the time since epoch in milliseconds, or None if the clock is set before the epoch.
+    fn millis_since_epoch() -> Option<i64> {
+        let duration = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).ok()?;
+        duration.as_millis().try_into().ok()
+    }
+
     /// Checks if the time now since epoch is greater than (or equal, if is_given_time_inclusive is
     /// set) the given time (in milliseconds)
     fn is_given_time_passed(given_time: i64, is_given_time_inclusive: bool) -> bool {
@@ -0,0 +1,32 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fuzzes the protobuf decoding of key parameter values, and checks that every decoded value
+//! survives an encode/decode round trip unchanged.
+
+#![no_main]
+
+use keystore2::key_parameter::{KeyParameterValue, Tag};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (i32, &[u8])| {
+    let (tag, bytes) = input;
+    if let Ok(value) = KeyParameterValue::new_from_protobuf(Tag(tag), bytes) {
+        // Whatever was decoded must be encoded and decoded again to an equal value.
+        let encoded = value.to_protobuf();
+        let decoded = KeyParameterValue::new_from_protobuf(value.get_tag(), &encoded)
+            .expect("Failed to decode an encoded value.");
+        assert_eq!(value, decoded);
+    }
+});
@@ -0,0 +1,130 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module pairs the hardware enforced and the software enforced key parameters of a key,
+//! so that the characteristics of the key can be queried without consulting both lists
+//! separately. Hardware enforced parameters take precedence over software enforced ones.
+
+use crate::key_parameter::{
+    find_all_tags, find_tag, Algorithm, Digest, EcCurve, KeyOrigin, KeyParameter,
+    KeyParameterValue, KeyPurpose, Tag,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The key parameters of a key, split by whether they are enforced by hardware, i.e., by a
+/// TRUSTED_ENVIRONMENT or STRONGBOX KeyMint instance, or by software.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyCharacteristics {
+    /// Key parameters enforced by hardware.
+    pub hw_enforced: Vec<KeyParameter>,
+    /// Key parameters enforced by software, i.e., by a software KeyMint instance or by
+    /// Keystore itself.
+    pub sw_enforced: Vec<KeyParameter>,
+}
+
+impl KeyCharacteristics {
+    /// Returns the first key parameter with the given tag. The hardware enforced parameters
+    /// are searched first, so a hardware enforced parameter shadows a software enforced
+    /// parameter with the same tag.
+    pub fn find(&self, tag: Tag) -> Option<&KeyParameter> {
+        find_tag(&self.hw_enforced, tag).or_else(|| find_tag(&self.sw_enforced, tag))
+    }
+
+    /// Returns all key parameters with the given tag, the hardware enforced parameters
+    /// first. This is useful for repeatable tags such as PURPOSE or DIGEST.
+    pub fn find_all(&self, tag: Tag) -> Vec<&KeyParameter> {
+        find_all_tags(&self.hw_enforced, tag).chain(find_all_tags(&self.sw_enforced, tag)).collect()
+    }
+
+    /// Returns true if a key parameter with the given tag is present in either list.
+    pub fn contains(&self, tag: Tag) -> bool {
+        self.find(tag).is_some()
+    }
+
+    fn find_value(&self, tag: Tag) -> Option<&KeyParameterValue> {
+        self.find(tag).map(|p| p.key_parameter_value())
+    }
+
+    /// Returns the algorithm of the key.
+    pub fn algorithm(&self) -> Option<Algorithm> {
+        match self.find_value(Tag::ALGORITHM)? {
+            KeyParameterValue::Algorithm(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the key size in bits.
+    pub fn key_size(&self) -> Option<i32> {
+        match self.find_value(Tag::KEY_SIZE)? {
+            KeyParameterValue::KeySize(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the curve of an EC key.
+    pub fn ec_curve(&self) -> Option<EcCurve> {
+        match self.find_value(Tag::EC_CURVE)? {
+            KeyParameterValue::EcCurve(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the public exponent of an RSA key.
+    pub fn rsa_public_exponent(&self) -> Option<i64> {
+        match self.find_value(Tag::RSA_PUBLIC_EXPONENT)? {
+            KeyParameterValue::RSAPublicExponent(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns where the key was created.
+    pub fn origin(&self) -> Option<KeyOrigin> {
+        match self.find_value(Tag::ORIGIN)? {
+            KeyParameterValue::KeyOrigin(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the creation date time of the key in milliseconds since the epoch.
+    pub fn creation_date_time(&self) -> Option<i64> {
+        match self.find_value(Tag::CREATION_DATETIME)? {
+            KeyParameterValue::CreationDateTime(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns all purposes of the key, the hardware enforced purposes first.
+    pub fn purposes(&self) -> Vec<KeyPurpose> {
+        self.find_all(Tag::PURPOSE)
+            .into_iter()
+            .filter_map(|p| match p.key_parameter_value() {
+                KeyParameterValue::KeyPurpose(v) => Some(*v),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns all digests of the key, the hardware enforced digests first.
+    pub fn digests(&self) -> Vec<Digest> {
+        self.find_all(Tag::DIGEST)
+            .into_iter()
+            .filter_map(|p| match p.key_parameter_value() {
+                KeyParameterValue::Digest(v) => Some(*v),
+                _ => None,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Key characteristics tests.
+
+use super::*;
+use crate::key_parameter::SecurityLevel;
+
+fn hw(value: KeyParameterValue) -> KeyParameter {
+    KeyParameter::new(value, SecurityLevel::TRUSTED_ENVIRONMENT)
+}
+
+fn sw(value: KeyParameterValue) -> KeyParameter {
+    KeyParameter::new(value, SecurityLevel::KEYSTORE)
+}
+
+fn overlapping() -> KeyCharacteristics {
+    KeyCharacteristics {
+        hw_enforced: vec![
+            hw(KeyParameterValue::Algorithm(Algorithm::EC)),
+            hw(KeyParameterValue::KeySize(256)),
+            hw(KeyParameterValue::EcCurve(EcCurve::P_256)),
+            hw(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+            hw(KeyParameterValue::Digest(Digest::SHA_2_256)),
+        ],
+        sw_enforced: vec![
+            sw(KeyParameterValue::Algorithm(Algorithm::RSA)),
+            sw(KeyParameterValue::KeySize(2048)),
+            sw(KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY)),
+            sw(KeyParameterValue::CreationDateTime(1704067200000)),
+        ],
+    }
+}
+
+#[test]
+fn find_prefers_hw_enforced() {
+    let chars = overlapping();
+    assert_eq!(chars.find(Tag::ALGORITHM), Some(&hw(KeyParameterValue::Algorithm(Algorithm::EC))));
+    assert_eq!(chars.algorithm(), Some(Algorithm::EC));
+    assert_eq!(chars.key_size(), Some(256));
+    assert_eq!(chars.ec_curve(), Some(EcCurve::P_256));
+}
+
+#[test]
+fn find_falls_back_to_sw_enforced() {
+    let chars = overlapping();
+    assert_eq!(
+        chars.find(Tag::CREATION_DATETIME),
+        Some(&sw(KeyParameterValue::CreationDateTime(1704067200000)))
+    );
+    assert_eq!(chars.creation_date_time(), Some(1704067200000));
+    assert!(chars.contains(Tag::CREATION_DATETIME));
+}
+
+#[test]
+fn find_missing_tag() {
+    let chars = overlapping();
+    assert_eq!(chars.find(Tag::RSA_PUBLIC_EXPONENT), None);
+    assert_eq!(chars.rsa_public_exponent(), None);
+    assert_eq!(chars.origin(), None);
+    assert!(!chars.contains(Tag::ORIGIN));
+
+    let empty = KeyCharacteristics::default();
+    assert_eq!(empty.algorithm(), None);
+    assert!(empty.find_all(Tag::PURPOSE).is_empty());
+    assert!(empty.purposes().is_empty());
+}
+
+#[test]
+fn find_all_lists_hw_enforced_first() {
+    let chars = overlapping();
+    assert_eq!(
+        chars.find_all(Tag::PURPOSE),
+        vec![
+            &hw(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+            &sw(KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY)),
+        ]
+    );
+    assert_eq!(chars.purposes(), vec![KeyPurpose::SIGN, KeyPurpose::VERIFY]);
+    assert_eq!(chars.digests(), vec![Digest::SHA_2_256]);
+}
@@ -21,26 +21,39 @@
 //! implements traits to convert it from and into KeyMint KeyParameters and store it in
 //! the SQLite database.
 //!
+//! The SQLite conversions (`ToSql`, `new_from_sql`, `from_sql_row`) are only built with the
+//! `sql` feature, and the serde implementations only with the `serde` feature. Without them,
+//! what remains is the enum itself and its KeyMint conversions. Keystore enables both.
+//!
 //! ## Synopsis
 //!
 //! enum KeyParameterValue {
-//!     Invalid,
+//!     Invalid(i32),
 //!     Algorithm(Algorithm),
 //!     ...
 //! }
 //!
 //! impl KeyParameterValue {
 //!     pub fn get_tag(&self) -> Tag;
+//!     pub fn get_primitive(&self) -> Option<Primitive>;
 //!     pub fn new_from_sql(tag: Tag, data: &SqlField) -> Result<Self>;
 //!     pub fn new_from_tag_primitive_pair<T: Into<Primitive>>(tag: Tag, v: T)
-//!        -> Result<Self, PrimitiveError>;
+//!         -> Result<Self, KeyParameterError>;
 //!     fn to_sql(&self) -> SqlResult<ToSqlOutput>
 //! }
 //!
 //! use ...::keymint::KeyParameter as KmKeyParameter;
-//! impl Into<KmKeyParameter> for KeyParameterValue {}
+//! impl From<KeyParameterValue> for KmKeyParameter {}
 //! impl From<KmKeyParameter> for KeyParameterValue {}
 //!
+//! struct KeyParameter { value: KeyParameterValue, security_level: SecurityLevel }
+//!
+//! The other representations live in submodules: `blob` (the compact wire format stored in a
+//! single database column, and canonical digest input), `parcel` (Binder `Parcelable`), and,
+//! behind features of the same name, `cbor`, `json`, `protobuf` and `attestation` (decoding of
+//! the key attestation certificate extension). Their tests sit next to them in
+//! `key_parameter/*_tests.rs`.
+//!
 //! ## Implementation
 //! Each of the six functions is implemented as match statement over each key parameter variant.
 //! We bootstrap these function as well as the KeyParameterValue enum itself from a single list
@@ -48,7 +61,7 @@
 //!
 //! The list resembles an enum declaration with a few extra fields.
 //! enum KeyParameterValue {
-//!    Invalid with tag INVALID and field Invalid,
+//!    Invalid(i32) with tag INVALID and field Invalid,
 //!    Algorithm(Algorithm) with tag ALGORITHM and field Algorithm,
 //!    ...
 //! }
@@ -64,7 +77,7 @@
 //!    $vname $(($vtype ))? with tag $tag_name and field $field_name,
 //!
 //! Further, KeyParameterValue appears in the macro as $enum_name.
-//! Note that $vtype is optional to accommodate variants like Invalid which don't wrap a value.
+//! Note that $vtype is optional to accommodate variants like CallerNonce which don't wrap a value.
 //!
 //! In some cases $vtype is not part of the expansion, but we still have to modify the expansion
 //! depending on the presence of $vtype. In these cases we recurse through the list following the
@@ -90,26 +103,57 @@
 //!  * The termination condition which has an empty in list.
 //!  * The public interface, which does not have @marker and calls itself with an empty out list.
 
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::TryInto;
+use std::hash::{Hash, Hasher};
+use std::sync::LazyLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+#[cfg(feature = "sql")]
 use crate::database::utils::SqlField;
 use crate::error::Error as KeystoreError;
 use crate::error::ResponseCode;
+use crate::key_parameter_set::ValidationError;
 
 pub use android_hardware_security_keymint::aidl::android::hardware::security::keymint::{
     Algorithm::Algorithm, BlockMode::BlockMode, Digest::Digest, EcCurve::EcCurve,
     HardwareAuthenticatorType::HardwareAuthenticatorType, KeyOrigin::KeyOrigin,
     KeyParameter::KeyParameter as KmKeyParameter,
     KeyParameterValue::KeyParameterValue as KmKeyParameterValue, KeyPurpose::KeyPurpose,
-    PaddingMode::PaddingMode, SecurityLevel::SecurityLevel, Tag::Tag,
+    PaddingMode::PaddingMode, SecurityLevel::SecurityLevel, Tag::Tag, TagType::TagType,
 };
 use android_system_keystore2::aidl::android::system::keystore2::Authorization::Authorization;
 use anyhow::{Context, Result};
-use rusqlite::types::{Null, ToSql, ToSqlOutput};
-use rusqlite::Result as SqlResult;
-use serde::de::Deserializer;
-use serde::ser::Serializer;
-use serde::{Deserialize, Serialize};
+#[cfg(feature = "sql")]
+use rusqlite::{
+    types::{Null, ToSql, ToSqlOutput},
+    Result as SqlResult, Row,
+};
+#[cfg(feature = "serde")]
+use serde::{de::Deserializer, ser::Serializer, Deserialize, Serialize};
+
+#[cfg(all(feature = "cbor", not(feature = "serde")))]
+compile_error!("The cbor feature requires the serde feature.");
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+#[cfg(feature = "attestation")]
+mod attestation;
+mod blob;
+#[cfg(feature = "cbor")]
+mod cbor;
+#[cfg(feature = "json")]
+mod json;
+mod parcel;
+#[cfg(feature = "protobuf")]
+mod protobuf;
+
+#[cfg(feature = "attestation")]
+pub use attestation::key_parameters_from_attestation_extension;
+pub use blob::{canonical_digest_input, decode_blob, encode_blob, key_parameter_set_fingerprint};
+pub use parcel::MAX_PARCEL_BLOB_SIZE;
 
 #[cfg(test)]
 mod generated_key_parameter_tests;
@@ -117,12 +161,30 @@ mod generated_key_parameter_tests;
 #[cfg(test)]
 mod basic_tests;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "sql"))]
 mod storage_tests;
 
 #[cfg(test)]
 mod wire_tests;
 
+#[cfg(test)]
+mod parcel_tests;
+
+#[cfg(all(test, feature = "cbor"))]
+mod cbor_tests;
+
+#[cfg(all(test, feature = "protobuf"))]
+mod protobuf_tests;
+
+#[cfg(all(test, feature = "json"))]
+mod json_tests;
+
+#[cfg(all(test, feature = "attestation"))]
+mod attestation_tests;
+
+#[cfg(all(test, feature = "arbitrary"))]
+mod arbitrary_tests;
+
 /// This trait is used to associate a primitive to any type that can be stored inside a
 /// KeyParameterValue, especially the AIDL enum types, e.g., keymint::{Algorithm, Digest, ...}.
 /// This allows for simplifying the macro rules, e.g., for reading from the SQL database.
@@ -140,12 +202,19 @@ trait AssociatePrimitive {
 
     fn from_primitive(v: Self::Primitive) -> Self;
     fn to_primitive(&self) -> Self::Primitive;
+
+    /// Returns false if the type is an AIDL enum and the value is not one of its constants.
+    fn is_defined(&self) -> bool {
+        true
+    }
 }
 
 /// Associates the given type with i32. The macro assumes that the given type is actually a
-/// tuple struct wrapping i32, such as AIDL enum types.
+/// tuple struct wrapping i32, such as AIDL enum types. A value is defined if it is one of the
+/// constants of the enum, or, for enums marked as `bitmask`, a combination of the positive
+/// constants. Negative constants of bit masks, such as `ANY = -1`, are only accepted as is.
 macro_rules! implement_associate_primitive_for_aidl_enum {
-    ($t:ty) => {
+    (@impl $t:ty, $v:ident => $is_defined:expr) => {
         impl AssociatePrimitive for $t {
             type Primitive = i32;
 
@@ -155,6 +224,22 @@ macro_rules! implement_associate_primitive_for_aidl_enum {
             fn to_primitive(&self) -> Self::Primitive {
                 self.0
             }
+            fn is_defined(&self) -> bool {
+                let $v = self;
+                $is_defined
+            }
+        }
+    };
+    ($t:ty, bitmask) => {
+        implement_associate_primitive_for_aidl_enum! {@impl $t, v =>
+            <$t>::enum_values().contains(v)
+                || v.0 & !<$t>::enum_values().iter().filter(|c| c.0 > 0).fold(0, |m, c| m | c.0)
+                    == 0
+        }
+    };
+    ($t:ty) => {
+        implement_associate_primitive_for_aidl_enum! {@impl $t, v =>
+            <$t>::enum_values().contains(v)
         }
     };
 }
@@ -179,20 +264,148 @@ implement_associate_primitive_for_aidl_enum! {Algorithm}
 implement_associate_primitive_for_aidl_enum! {BlockMode}
 implement_associate_primitive_for_aidl_enum! {Digest}
 implement_associate_primitive_for_aidl_enum! {EcCurve}
-implement_associate_primitive_for_aidl_enum! {HardwareAuthenticatorType}
+implement_associate_primitive_for_aidl_enum! {HardwareAuthenticatorType, bitmask}
 implement_associate_primitive_for_aidl_enum! {KeyOrigin}
 implement_associate_primitive_for_aidl_enum! {KeyPurpose}
 implement_associate_primitive_for_aidl_enum! {PaddingMode}
 implement_associate_primitive_for_aidl_enum! {SecurityLevel}
 
-implement_associate_primitive_identity! {Vec<u8>}
 implement_associate_primitive_identity! {i64}
 implement_associate_primitive_identity! {i32}
 
+impl AssociatePrimitive for Vec<u8> {
+    type Primitive = Vec<u8>;
+
+    fn from_primitive(v: Self::Primitive) -> Self {
+        v
+    }
+    fn to_primitive(&self) -> Self::Primitive {
+        self.clone()
+    }
+}
+
+/// This trait associates the form used by `KeyParameterValueRef` with each type that can be
+/// stored inside a KeyParameterValue. Byte vectors are borrowed as slices. All other types
+/// are Copy and are used as is.
+pub trait BorrowField<'a> {
+    /// The borrowed form of the type.
+    type Borrowed: 'a;
+
+    /// Returns the borrowed form of the value.
+    fn borrow_field(&'a self) -> Self::Borrowed;
+    /// Creates an owned value from the borrowed form, copying the data if necessary.
+    fn to_owned_field(v: Self::Borrowed) -> Self;
+}
+
+/// Uses the given Copy type as its own borrowed form.
+macro_rules! implement_borrow_field_copy {
+    ($t:ty) => {
+        impl<'a> BorrowField<'a> for $t {
+            type Borrowed = $t;
+
+            fn borrow_field(&'a self) -> Self::Borrowed {
+                *self
+            }
+            fn to_owned_field(v: Self::Borrowed) -> Self {
+                v
+            }
+        }
+    };
+}
+
+implement_borrow_field_copy! {Algorithm}
+implement_borrow_field_copy! {BlockMode}
+implement_borrow_field_copy! {Digest}
+implement_borrow_field_copy! {EcCurve}
+implement_borrow_field_copy! {HardwareAuthenticatorType}
+implement_borrow_field_copy! {KeyOrigin}
+implement_borrow_field_copy! {KeyPurpose}
+implement_borrow_field_copy! {PaddingMode}
+implement_borrow_field_copy! {SecurityLevel}
+implement_borrow_field_copy! {i64}
+implement_borrow_field_copy! {i32}
+
+impl<'a> BorrowField<'a> for Vec<u8> {
+    type Borrowed = &'a [u8];
+
+    fn borrow_field(&'a self) -> Self::Borrowed {
+        self
+    }
+    fn to_owned_field(v: Self::Borrowed) -> Self {
+        v.to_vec()
+    }
+}
+
+/// Upper bound for the length of blobs generated by the Arbitrary implementation of
+/// KeyParameterValue.
+#[cfg(feature = "arbitrary")]
+const MAX_ARBITRARY_BLOB_LEN: usize = 256;
+
+/// Generates a random value for a field wrapped by a KeyParameterValue variant. Used by the
+/// Arbitrary implementation of KeyParameterValue.
+#[cfg(feature = "arbitrary")]
+trait ArbitraryField: Sized {
+    fn arbitrary_field(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self>;
+}
+
+/// AIDL enums only produce the values that are defined by the AIDL spec.
+#[cfg(feature = "arbitrary")]
+macro_rules! implement_arbitrary_field_for_aidl_enum {
+    ($t:ty) => {
+        impl ArbitraryField for $t {
+            fn arbitrary_field(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self> {
+                u.choose(&<$t>::enum_values()).copied()
+            }
+        }
+    };
+}
+
+#[cfg(feature = "arbitrary")]
+implement_arbitrary_field_for_aidl_enum! {Algorithm}
+#[cfg(feature = "arbitrary")]
+implement_arbitrary_field_for_aidl_enum! {BlockMode}
+#[cfg(feature = "arbitrary")]
+implement_arbitrary_field_for_aidl_enum! {Digest}
+#[cfg(feature = "arbitrary")]
+implement_arbitrary_field_for_aidl_enum! {EcCurve}
+#[cfg(feature = "arbitrary")]
+implement_arbitrary_field_for_aidl_enum! {HardwareAuthenticatorType}
+#[cfg(feature = "arbitrary")]
+implement_arbitrary_field_for_aidl_enum! {KeyOrigin}
+#[cfg(feature = "arbitrary")]
+implement_arbitrary_field_for_aidl_enum! {KeyPurpose}
+#[cfg(feature = "arbitrary")]
+implement_arbitrary_field_for_aidl_enum! {PaddingMode}
+#[cfg(feature = "arbitrary")]
+implement_arbitrary_field_for_aidl_enum! {SecurityLevel}
+
+#[cfg(feature = "arbitrary")]
+impl ArbitraryField for i32 {
+    fn arbitrary_field(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self> {
+        u.arbitrary()
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl ArbitraryField for i64 {
+    fn arbitrary_field(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self> {
+        u.arbitrary()
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl ArbitraryField for Vec<u8> {
+    fn arbitrary_field(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self> {
+        let len = u.arbitrary_len::<u8>()?.min(MAX_ARBITRARY_BLOB_LEN);
+        Ok(u.bytes(len)?.to_vec())
+    }
+}
+
 /// This enum allows passing a primitive value to `KeyParameterValue::new_from_tag_primitive_pair`
 /// Usually, it is not necessary to use this type directly because the function uses
 /// `Into<Primitive>` as a trait bound.
-#[derive(Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum Primitive {
     /// Wraps an i64.
     I64(i64),
@@ -218,15 +431,394 @@ impl From<Vec<u8>> for Primitive {
     }
 }
 
+impl Hash for Primitive {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Self::I64(v) => {
+                0u8.hash(state);
+                v.hash(state);
+            }
+            Self::I32(v) => {
+                1u8.hash(state);
+                v.hash(state);
+            }
+            Self::Vec(v) => {
+                2u8.hash(state);
+                v.hash(state);
+            }
+        }
+    }
+}
+
+impl Primitive {
+    /// Returns the name of the wrapped type, e.g., "i32".
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::I64(_) => "i64",
+            Self::I32(_) => "i32",
+            Self::Vec(_) => "Vec<u8>",
+        }
+    }
+}
+
+/// Returns the name of the primitive type that stores values of the given tag type, or "none"
+/// for tag types without value.
+fn primitive_name_of_tag_type(tag_type: TagType) -> &'static str {
+    match tag_type {
+        TagType::INVALID
+        | TagType::ENUM
+        | TagType::ENUM_REP
+        | TagType::UINT
+        | TagType::UINT_REP => "i32",
+        TagType::ULONG | TagType::ULONG_REP | TagType::DATE => "i64",
+        TagType::BIGNUM | TagType::BYTES => "Vec<u8>",
+        _ => "none",
+    }
+}
+
+/// The union field of keymint::KeyParameterValue that carries the value of a tag. This is
+/// generated from the `field = ...` attribute of the KeyParameterValue variants and can be
+/// looked up with `field_type_of`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FieldType {
+    /// The field of Tag::INVALID.
+    Invalid,
+    /// Holds an Algorithm.
+    Algorithm,
+    /// Holds a BlockMode.
+    BlockMode,
+    /// Holds a PaddingMode.
+    PaddingMode,
+    /// Holds a Digest.
+    Digest,
+    /// Holds an EcCurve.
+    EcCurve,
+    /// Holds a KeyOrigin.
+    Origin,
+    /// Holds a KeyPurpose.
+    KeyPurpose,
+    /// Holds a HardwareAuthenticatorType.
+    HardwareAuthenticatorType,
+    /// Holds a SecurityLevel.
+    SecurityLevel,
+    /// Holds an i32.
+    Integer,
+    /// Holds an i64.
+    LongInteger,
+    /// Holds an i64 with milliseconds since the epoch.
+    DateTime,
+    /// Holds a Vec<u8>.
+    Blob,
+    /// Boolean tags carry no value. They are implicitly true if present.
+    BoolValue,
+}
+
+impl FieldType {
+    /// Returns the field that holds the given KeyMint value.
+    pub fn of_km_value(v: &KmKeyParameterValue) -> Self {
+        match v {
+            KmKeyParameterValue::Invalid(_) => Self::Invalid,
+            KmKeyParameterValue::Algorithm(_) => Self::Algorithm,
+            KmKeyParameterValue::BlockMode(_) => Self::BlockMode,
+            KmKeyParameterValue::PaddingMode(_) => Self::PaddingMode,
+            KmKeyParameterValue::Digest(_) => Self::Digest,
+            KmKeyParameterValue::EcCurve(_) => Self::EcCurve,
+            KmKeyParameterValue::Origin(_) => Self::Origin,
+            KmKeyParameterValue::KeyPurpose(_) => Self::KeyPurpose,
+            KmKeyParameterValue::HardwareAuthenticatorType(_) => Self::HardwareAuthenticatorType,
+            KmKeyParameterValue::SecurityLevel(_) => Self::SecurityLevel,
+            KmKeyParameterValue::Integer(_) => Self::Integer,
+            KmKeyParameterValue::LongInteger(_) => Self::LongInteger,
+            KmKeyParameterValue::DateTime(_) => Self::DateTime,
+            KmKeyParameterValue::Blob(_) => Self::Blob,
+            KmKeyParameterValue::BoolValue(_) => Self::BoolValue,
+        }
+    }
+
+    /// Returns true if the field holds one of the AIDL enum types.
+    pub fn is_enum(&self) -> bool {
+        matches!(
+            self,
+            Self::Algorithm
+                | Self::BlockMode
+                | Self::PaddingMode
+                | Self::Digest
+                | Self::EcCurve
+                | Self::Origin
+                | Self::KeyPurpose
+                | Self::HardwareAuthenticatorType
+                | Self::SecurityLevel
+        )
+    }
+
+    /// Returns the name of the primitive type that `KeyParameterValue::new_from_tag_primitive_pair`
+    /// expects for this field, or "none" if the field carries no value.
+    pub fn primitive_name(&self) -> &'static str {
+        match self {
+            Self::Integer => "i32",
+            Self::LongInteger | Self::DateTime => "i64",
+            Self::Blob => "Vec<u8>",
+            Self::BoolValue => "none",
+            _ => "i32",
+        }
+    }
+
+    /// Returns true if `KeyParameterValue::new_from_tag_primitive_pair` accepts the given
+    /// primitive for a tag with this field. Fields without value accept, and ignore, any
+    /// primitive.
+    pub fn accepts(&self, p: &Primitive) -> bool {
+        match self.primitive_name() {
+            "none" => true,
+            name => name == p.type_name(),
+        }
+    }
+}
+
+/// Groups tags by their purpose, e.g., to show the parameters of a key grouped by category in
+/// dumps. The category of a tag is given by `tag_category`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TagCategory {
+    /// Tags that describe the key material and how it is used in cryptographic operations,
+    /// e.g., ALGORITHM, KEY_SIZE, or DIGEST.
+    CryptoParams,
+    /// Tags that restrict who may use the key and when, e.g., USER_SECURE_ID or
+    /// UNLOCKED_DEVICE_REQUIRED.
+    AuthAndAccess,
+    /// Tags that only take part in key attestation, e.g., ATTESTATION_CHALLENGE or the
+    /// ATTESTATION_ID_* tags.
+    Attestation,
+    /// Tags that describe the state of the device, e.g., OS_VERSION or ROOT_OF_TRUST.
+    DeviceInfo,
+    /// Tags that govern when the key was created and how long it can be used, e.g.,
+    /// CREATION_DATETIME or ACTIVE_DATETIME.
+    Lifecycle,
+    /// All other tags, including tags unknown to Keystore.
+    Other,
+}
+
+/// Describes one of the tags known to KeyParameterValue. See `all_known_tags`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TagInfo {
+    /// The KeyMint tag.
+    pub tag: Tag,
+    /// The symbolic name of the tag, e.g., "ALGORITHM".
+    pub name: &'static str,
+    /// The name of the KeyParameterValue variant, e.g., "Algorithm".
+    pub variant: &'static str,
+    /// The field of keymint::KeyParameterValue that carries the value of the tag.
+    pub field_type: FieldType,
+    /// True if the variant wraps a value. Boolean parameters do not.
+    pub has_value: bool,
+}
+
+/// Expands to true if a value type is given and false otherwise.
+macro_rules! key_param_has_value {
+    () => {
+        false
+    };
+    ($vtype:ty) => {
+        true
+    };
+}
+
 /// This error is returned by `KeyParameterValue::new_from_tag_primitive_pair`.
 #[derive(thiserror::Error, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum PrimitiveError {
     /// Returned if this primitive is unsuitable for the given tag type.
-    #[error("Primitive does not match the expected tag type.")]
-    TypeMismatch,
+    #[error("tag {tag:?} expects {expected} but got {got}")]
+    TypeMismatch {
+        /// The tag of the parameter being decoded.
+        tag: Tag,
+        /// The name of the primitive type expected by the tag, or "none" if the tag has no
+        /// value.
+        expected: &'static str,
+        /// The name of the primitive type that was given, or "none" if no value was given.
+        got: &'static str,
+    },
     /// Return if the tag type is unknown.
     #[error("Unknown tag.")]
     UnknownTag,
+    /// Returned by the `TryFrom<Primitive>` conversions if the primitive holds a different
+    /// type. The primitive is handed back to the caller.
+    #[error("unexpected {} primitive", .0.type_name())]
+    UnexpectedPrimitive(Primitive),
+}
+
+impl PrimitiveError {
+    /// Creates a TypeMismatch error for the given tag and the given primitive, if any.
+    fn type_mismatch(tag: Tag, got: Option<&Primitive>) -> Self {
+        Self::TypeMismatch {
+            tag,
+            expected: primitive_name_of_tag_type(tag_type_of(tag)),
+            got: got.map_or("none", Primitive::type_name),
+        }
+    }
+
+    /// Turns an UnexpectedPrimitive error into a TypeMismatch error for the given tag.
+    /// Other errors are returned unchanged.
+    fn for_tag(self, tag: Tag) -> Self {
+        match self {
+            Self::UnexpectedPrimitive(p) => Self::type_mismatch(tag, Some(&p)),
+            e => e,
+        }
+    }
+}
+
+/// This error is returned when constructing a KeyParameterValue from a database cell, from
+/// a tag and primitive pair, or from a tag and KeyMint value.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum KeyParameterError {
+    /// Returned if the database cell holding the value of the parameter cannot be read as the
+    /// type expected by the tag.
+    #[error("Failed to read sql data for tag: {0:?}")]
+    SqlCorrupted(Tag),
+    /// Returned if the tag is not supported by Keystore.
+    #[error("Unsupported tag {0:?}")]
+    UnsupportedTag(Tag),
+    /// Returned if the union field of a KeyMint value does not match the field of the tag.
+    #[error("Field does not match the expected field for tag {0:?}")]
+    TypeMismatch(Tag),
+    /// Returned if a blob exceeds the maximum size allowed for its tag.
+    #[error(transparent)]
+    BlobTooLarge(#[from] BlobTooLargeError),
+    /// Returned by `KeyParameterValue::new_from_tag_primitive_pair` if the value is rejected by
+    /// `KeyParameterValue::validate_value_range`.
+    #[error(transparent)]
+    OutOfRange(#[from] ValidationError),
+    /// Returned if the primitive does not match the tag type or is out of range.
+    #[error(transparent)]
+    InvalidPrimitive(#[from] PrimitiveError),
+    /// Returned if CERTIFICATE_SUBJECT does not hold a DER encoded X.509 distinguished name.
+    #[error("Malformed certificate subject")]
+    InvalidCertificateSubject,
+}
+
+/// This error is returned by `KeyParameterValue::try_from_km_parameter`. It tells apart the
+/// failure modes that `KeyParameterValue::from_km_parameter_lossy` cannot distinguish, i.e.,
+/// unknown tags, values in the wrong field, and values that are not valid for their tag.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum KeyParameterConversionError {
+    /// Returned if the tag is not known to Keystore.
+    #[error("Unknown tag {tag:?}.")]
+    UnknownTag {
+        /// The unknown tag.
+        tag: Tag,
+    },
+    /// Returned if the union field does not match the field associated with the tag.
+    #[error("Field does not match the expected field for tag {tag:?}.")]
+    FieldTypeMismatch {
+        /// The tag whose value was stored in the wrong field.
+        tag: Tag,
+    },
+    /// Returned if the value is stored in the right field, but it is not a constant defined
+    /// for the enum of the tag, see `KeyParameterValue::validate_enum_ranges`.
+    #[error("Value {value} is not defined for tag {tag:?}.")]
+    InvalidValue {
+        /// The tag of the parameter.
+        tag: Tag,
+        /// The undefined value.
+        value: i32,
+    },
+    /// Returned by `KeyParameterValue::from_km_param_checked` if a blob exceeds the limit
+    /// configured for its tag.
+    #[error(transparent)]
+    BlobTooLarge(#[from] BlobTooLargeError),
+}
+
+/// This error is returned by `KeyParameterValue::check_blob_size` and `BlobLimits::check` if a
+/// blob exceeds the limit for its tag.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Blob of {size} bytes for tag {tag:?} exceeds the maximum of {max} bytes.")]
+pub struct BlobTooLargeError {
+    /// The tag of the parameter.
+    pub tag: Tag,
+    /// The size of the blob.
+    pub size: usize,
+    /// The limit for the tag.
+    pub max: usize,
+}
+
+impl KeyParameterValue {
+    /// Maximum length of an application id as documented by KeyMint.
+    pub const APPLICATION_ID_MAX_SIZE: usize = 128;
+    /// Maximum length of application data as documented by KeyMint.
+    pub const APPLICATION_DATA_MAX_SIZE: usize = 32768;
+
+    /// Returns the maximum blob length that `new_from_sql` and `new_from_tag_primitive_pair`
+    /// accept for the given tag, if it is bounded.
+    pub fn max_blob_size(tag: Tag) -> Option<usize> {
+        match tag {
+            Tag::APPLICATION_ID => Some(Self::APPLICATION_ID_MAX_SIZE),
+            Tag::APPLICATION_DATA => Some(Self::APPLICATION_DATA_MAX_SIZE),
+            _ => None,
+        }
+    }
+
+    /// Returns the value unchanged, or BlobTooLargeError if it holds a blob that is longer
+    /// than `max_blob_size` of its tag.
+    fn check_blob_size(self) -> Result<Self, BlobTooLargeError> {
+        let tag = self.get_tag();
+        match (self.blob_value(), Self::max_blob_size(tag)) {
+            (Some(blob), Some(max)) if blob.len() > max => {
+                Err(BlobTooLargeError { tag, size: blob.len(), max })
+            }
+            _ => Ok(self),
+        }
+    }
+}
+
+/// Per-tag upper bounds for the length of blob parameters received from KeyMint, see
+/// `KeyParameterValue::from_km_param_checked`. Tags without a limit are not bounded.
+/// The default limits are 128 bytes for APPLICATION_ID and ATTESTATION_CHALLENGE and 32768
+/// bytes for APPLICATION_DATA as documented by KeyMint, and 1024 bytes for
+/// ATTESTATION_APPLICATION_ID. The latter is Keystore's own bound for the encoded
+/// attestation application id; KeyMint does not document a maximum for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobLimits {
+    limits: HashMap<Tag, usize>,
+}
+
+impl BlobLimits {
+    /// Maximum length of an attestation challenge as documented by KeyMint.
+    pub const ATTESTATION_CHALLENGE_MAX_SIZE: usize = 128;
+    /// Maximum length of an attestation application id as accepted by Keystore.
+    pub const ATTESTATION_APPLICATION_ID_MAX_SIZE: usize = 1024;
+
+    /// Creates limits that do not bound any tag.
+    pub fn unlimited() -> Self {
+        Self { limits: HashMap::new() }
+    }
+
+    /// Sets the maximum blob length for the given tag, replacing any previous limit.
+    pub fn set_limit(&mut self, tag: Tag, max: usize) -> &mut Self {
+        self.limits.insert(tag, max);
+        self
+    }
+
+    /// Returns the maximum blob length for the given tag, if it is bounded.
+    pub fn limit(&self, tag: Tag) -> Option<usize> {
+        self.limits.get(&tag).copied()
+    }
+
+    /// Fails with BlobTooLargeError if the blob is longer than the limit for the given tag.
+    pub fn check(&self, tag: Tag, blob: &[u8]) -> Result<(), BlobTooLargeError> {
+        match self.limit(tag) {
+            Some(max) if blob.len() > max => Err(BlobTooLargeError { tag, size: blob.len(), max }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for BlobLimits {
+    fn default() -> Self {
+        let mut limits = Self::unlimited();
+        limits
+            .set_limit(Tag::APPLICATION_ID, KeyParameterValue::APPLICATION_ID_MAX_SIZE)
+            .set_limit(Tag::APPLICATION_DATA, KeyParameterValue::APPLICATION_DATA_MAX_SIZE)
+            .set_limit(Tag::ATTESTATION_CHALLENGE, Self::ATTESTATION_CHALLENGE_MAX_SIZE)
+            .set_limit(Tag::ATTESTATION_APPLICATION_ID, Self::ATTESTATION_APPLICATION_ID_MAX_SIZE);
+        limits
+    }
 }
 
 impl TryFrom<Primitive> for i64 {
@@ -235,7 +827,7 @@ impl TryFrom<Primitive> for i64 {
     fn try_from(p: Primitive) -> Result<i64, Self::Error> {
         match p {
             Primitive::I64(v) => Ok(v),
-            _ => Err(Self::Error::TypeMismatch),
+            p => Err(PrimitiveError::UnexpectedPrimitive(p)),
         }
     }
 }
@@ -245,7 +837,7 @@ impl TryFrom<Primitive> for i32 {
     fn try_from(p: Primitive) -> Result<i32, Self::Error> {
         match p {
             Primitive::I32(v) => Ok(v),
-            _ => Err(Self::Error::TypeMismatch),
+            p => Err(PrimitiveError::UnexpectedPrimitive(p)),
         }
     }
 }
@@ -255,11 +847,12 @@ impl TryFrom<Primitive> for Vec<u8> {
     fn try_from(p: Primitive) -> Result<Vec<u8>, Self::Error> {
         match p {
             Primitive::Vec(v) => Ok(v),
-            _ => Err(Self::Error::TypeMismatch),
+            p => Err(PrimitiveError::UnexpectedPrimitive(p)),
         }
     }
 }
 
+#[cfg(feature = "serde")]
 fn serialize_primitive<S, P>(v: &P, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -269,6 +862,7 @@ where
     primitive.serialize(serializer)
 }
 
+#[cfg(feature = "serde")]
 fn deserialize_primitive<'de, D, T>(deserializer: D) -> Result<T, D::Error>
 where
     D: Deserializer<'de>,
@@ -280,10 +874,82 @@ where
     ))
 }
 
+/// Returns the type encoded in the upper four bits of the given tag.
+fn tag_type_of(tag: Tag) -> TagType {
+    TagType((tag.0 as u32 & 0xF0000000) as i32)
+}
+
+/// Returns the primitive wrapped by the given keymint::KeyParameterValue. Boolean values have
+/// no primitive, because boolean parameters are implicitly true if present.
+fn km_value_to_primitive(value: &KmKeyParameterValue) -> Option<Primitive> {
+    Some(match value {
+        KmKeyParameterValue::Invalid(v) | KmKeyParameterValue::Integer(v) => Primitive::I32(*v),
+        KmKeyParameterValue::Algorithm(v) => Primitive::I32(v.0),
+        KmKeyParameterValue::BlockMode(v) => Primitive::I32(v.0),
+        KmKeyParameterValue::PaddingMode(v) => Primitive::I32(v.0),
+        KmKeyParameterValue::Digest(v) => Primitive::I32(v.0),
+        KmKeyParameterValue::EcCurve(v) => Primitive::I32(v.0),
+        KmKeyParameterValue::Origin(v) => Primitive::I32(v.0),
+        KmKeyParameterValue::KeyPurpose(v) => Primitive::I32(v.0),
+        KmKeyParameterValue::HardwareAuthenticatorType(v) => Primitive::I32(v.0),
+        KmKeyParameterValue::SecurityLevel(v) => Primitive::I32(v.0),
+        KmKeyParameterValue::BoolValue(_) => return None,
+        KmKeyParameterValue::LongInteger(v) | KmKeyParameterValue::DateTime(v) => {
+            Primitive::I64(*v)
+        }
+        KmKeyParameterValue::Blob(v) => Primitive::Vec(v.clone()),
+    })
+}
+
+/// Reconstructs the keymint::KeyParameterValue of a tag unknown to Keystore from its primitive.
+/// The union field is inferred from the type encoded in the tag. Enum tags are mapped to the
+/// Integer field, because the tag does not reveal which enum type the value belongs to.
+/// Returns None if the tag type is invalid or if it does not match the primitive.
+fn km_value_from_primitive(tag: Tag, p: Option<Primitive>) -> Option<KmKeyParameterValue> {
+    Some(match (tag_type_of(tag), p) {
+        (TagType::BOOL, None) => KmKeyParameterValue::BoolValue(true),
+        (
+            TagType::ENUM | TagType::ENUM_REP | TagType::UINT | TagType::UINT_REP,
+            Some(Primitive::I32(v)),
+        ) => KmKeyParameterValue::Integer(v),
+        (TagType::ULONG | TagType::ULONG_REP, Some(Primitive::I64(v))) => {
+            KmKeyParameterValue::LongInteger(v)
+        }
+        (TagType::DATE, Some(Primitive::I64(v))) => KmKeyParameterValue::DateTime(v),
+        (TagType::BIGNUM | TagType::BYTES, Some(Primitive::Vec(v))) => KmKeyParameterValue::Blob(v),
+        _ => return None,
+    })
+}
+
+#[cfg(feature = "serde")]
+fn serialize_unknown<S>(
+    tag: &Tag,
+    value: &KmKeyParameterValue,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    (tag.0, km_value_to_primitive(value)).serialize(serializer)
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_unknown<'de, D>(deserializer: D) -> Result<(Tag, KmKeyParameterValue), D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let (tag, primitive): (i32, Option<Primitive>) =
+        serde::de::Deserialize::deserialize(deserializer)?;
+    let tag = Tag(tag);
+    let value = km_value_from_primitive(tag, primitive)
+        .ok_or_else(|| serde::de::Error::custom("Type Mismatch"))?;
+    Ok((tag, value))
+}
+
 /// Expands the list of KeyParameterValue variants as follows:
 ///
 /// Input:
-/// Invalid with tag INVALID and field Invalid,
+/// CallerNonce with tag CALLER_NONCE and field BoolValue,
 /// Algorithm(Algorithm) with tag ALGORITHM and field Algorithm,
 ///
 /// Output:
@@ -291,12 +957,35 @@ where
 /// pub fn new_from_tag_primitive_pair<T: Into<Primitive>>(
 ///     tag: Tag,
 ///     v: T
+/// ) -> Result<KeyParameterValue, KeyParameterError> {
+///     let result = Self::new_from_tag_primitive_pair_without_range_check(tag, v)?;
+///     result.validate_value_range()?;
+///     Ok(result)
+/// }
+///
+/// pub fn new_from_tag_primitive_pair_without_range_check<T: Into<Primitive>>(
+///     tag: Tag,
+///     v: T
+/// ) -> Result<KeyParameterValue, KeyParameterError> {
+///     let result = Self::new_from_tag_primitive_pair_unchecked(tag, v.into()).map_err(|e| {
+///         match e {
+///             PrimitiveError::UnknownTag => KeyParameterError::UnsupportedTag(tag),
+///             e => e.into(),
+///         }
+///     })?;
+///     Ok(result.check_blob_size()?)
+/// }
+///
+/// fn new_from_tag_primitive_pair_unchecked(
+///     tag: Tag,
+///     p: Primitive
 /// ) -> Result<KeyParameterValue, PrimitiveError> {
-///     let p: Primitive = v.into();
 ///     Ok(match tag {
-///         Tag::INVALID => KeyParameterValue::Invalid,
+///         Tag::CALLER_NONCE => KeyParameterValue::CallerNonce,
 ///         Tag::ALGORITHM => KeyParameterValue::Algorithm(
-///             <Algorithm>::from_primitive(p.try_into()?)
+///             <Algorithm>::from_primitive(
+///                 p.try_into().map_err(|e: PrimitiveError| e.for_tag(tag))?
+///             )
 ///         ),
 ///         _ => return Err(PrimitiveError::UnknownTag),
 ///     })
@@ -305,15 +994,44 @@ where
 macro_rules! implement_from_tag_primitive_pair {
     ($enum_name:ident; $($vname:ident$(($vtype:ty))? $tag_name:ident),*) => {
         /// Returns the an instance of $enum_name or an error if the given primitive does not match
-        /// the tag type or the tag is unknown.
+        /// the tag type, exceeds the maximum blob size of the tag, or the tag is unknown, or
+        /// KeyParameterError::OutOfRange if the value is rejected by `validate_value_range`.
         pub fn new_from_tag_primitive_pair<T: Into<Primitive>>(
             tag: Tag,
             v: T
+        ) -> Result<$enum_name, KeyParameterError> {
+            let result = Self::new_from_tag_primitive_pair_without_range_check(tag, v)?;
+            result.validate_value_range()?;
+            Ok(result)
+        }
+
+        /// Like `new_from_tag_primitive_pair`, but does not check the range of the value. This
+        /// is used for parameters read from storage, e.g., legacy key characteristics, which
+        /// must be preserved unchanged.
+        pub fn new_from_tag_primitive_pair_without_range_check<T: Into<Primitive>>(
+            tag: Tag,
+            v: T
+        ) -> Result<$enum_name, KeyParameterError> {
+            let result = Self::new_from_tag_primitive_pair_unchecked(tag, v.into()).map_err(|e| {
+                match e {
+                    PrimitiveError::UnknownTag => KeyParameterError::UnsupportedTag(tag),
+                    e => e.into(),
+                }
+            })?;
+            Ok(result.check_blob_size()?)
+        }
+
+        /// Like `new_from_tag_primitive_pair`, but does not check the size of blobs.
+        /// This is used when decoding parameters, which must round trip unchanged.
+        fn new_from_tag_primitive_pair_unchecked(
+            tag: Tag,
+            p: Primitive
         ) -> Result<$enum_name, PrimitiveError> {
-            let p: Primitive = v.into();
             Ok(match tag {
                 $(Tag::$tag_name => $enum_name::$vname$((
-                    <$vtype>::from_primitive(p.try_into()?)
+                    <$vtype>::from_primitive(
+                        p.try_into().map_err(|e: PrimitiveError| e.for_tag(tag))?
+                    )
                 ))?,)*
                 _ => return Err(PrimitiveError::UnknownTag),
             })
@@ -325,15 +1043,16 @@ macro_rules! implement_from_tag_primitive_pair {
 ///
 /// Input:
 /// pub enum KeyParameterValue {
-///     Invalid with tag INVALID and field Invalid,
+///     CallerNonce with tag CALLER_NONCE and field BoolValue,
 ///     Algorithm(Algorithm) with tag ALGORITHM and field Algorithm,
 /// }
 ///
 /// Output:
 /// ```
 /// pub enum KeyParameterValue {
-///     Invalid,
+///     CallerNonce,
 ///     Algorithm(Algorithm),
+///     Unknown { tag: Tag, value: KmKeyParameterValue },
 /// }
 /// ```
 macro_rules! implement_enum {
@@ -348,7 +1067,17 @@ macro_rules! implement_enum {
             $(
                 $(#[$emeta])*
                 $vname$(($vtype))?
-            ),*
+            ),*,
+            /// A parameter with a tag that is not known to this version of Keystore. It is
+            /// preserved as is, so that it can be passed back to KeyMint unaltered.
+            #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_unknown"))]
+            #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_unknown"))]
+            Unknown {
+                /// The original tag.
+                tag: Tag,
+                /// The original value.
+                value: KmKeyParameterValue,
+            },
         }
     };
 }
@@ -356,17 +1085,26 @@ macro_rules! implement_enum {
 /// Expands the list of KeyParameterValue variants as follows:
 ///
 /// Input:
-/// Invalid with tag INVALID and field Invalid,
+/// CallerNonce with tag CALLER_NONCE and field BoolValue,
 /// Algorithm(Algorithm) with tag ALGORITHM and field Algorithm,
 ///
 /// Output:
 /// ```
-/// pub fn get_tag(&self) -> Tag {
+/// pub const fn static_tag(&self) -> Tag {
 ///     match self {
-///         KeyParameterValue::Invalid => Tag::INVALID,
+///         KeyParameterValue::CallerNonce => Tag::CALLER_NONCE,
 ///         KeyParameterValue::Algorithm(_) => Tag::ALGORITHM,
+///         KeyParameterValue::Unknown { tag, .. } => *tag,
 ///     }
 /// }
+///
+/// pub fn get_tag(&self) -> Tag {
+///     self.static_tag()
+/// }
+///
+/// pub fn tag_type(&self) -> TagType {
+///     tag_type_of(self.static_tag())
+/// }
 /// ```
 macro_rules! implement_get_tag {
     (
@@ -390,12 +1128,25 @@ macro_rules! implement_get_tag {
         ], [$($in)*]}
     };
     (@replace_type_spec $enum_name:ident, [$($out:tt)*], []) => {
-        /// Returns the tag of the given instance.
-        pub fn get_tag(&self) -> Tag {
+        /// Returns the tag of the given instance. Unlike `get_tag`, this can be evaluated at
+        /// compile time, e.g., to build static tables keyed by tag.
+        pub const fn static_tag(&self) -> Tag {
             match self {
                 $($out)*
+                $enum_name::Unknown { tag, .. } => *tag,
             }
         }
+
+        /// Returns the tag of the given instance.
+        pub fn get_tag(&self) -> Tag {
+            self.static_tag()
+        }
+
+        /// Returns the type encoded in the tag of the given instance, i.e., the KeyMint
+        /// representation of its value.
+        pub fn tag_type(&self) -> TagType {
+            tag_type_of(self.static_tag())
+        }
     };
 
     ($enum_name:ident; $($vname:ident$(($vtype:ty))? $tag_name:ident),*) => {
@@ -406,27 +1157,28 @@ macro_rules! implement_get_tag {
 /// Expands the list of KeyParameterValue variants as follows:
 ///
 /// Input:
-/// Invalid with tag INVALID and field Invalid,
+/// CallerNonce with tag CALLER_NONCE and field BoolValue,
 /// Algorithm(Algorithm) with tag ALGORITHM and field Algorithm,
 ///
 /// Output:
 /// ```
-/// fn to_sql(&self) -> SqlResult<ToSqlOutput> {
+/// pub fn get_primitive(&self) -> Option<Primitive> {
 ///     match self {
-///         KeyParameterValue::Invalid => Ok(ToSqlOutput::from(Null)),
-///         KeyParameterValue::Algorithm(v) => Ok(ToSqlOutput::from(v.to_primitive())),
+///         KeyParameterValue::CallerNonce => None,
+///         KeyParameterValue::Algorithm(v) => Some(v.to_primitive().into()),
+///         KeyParameterValue::Unknown { value, .. } => km_value_to_primitive(value),
 ///     }
 /// }
 /// ```
-macro_rules! implement_to_sql {
+macro_rules! implement_get_primitive {
     (
         @replace_type_spec
         $enum_name:ident,
         [$($out:tt)*],
         [$vname:ident($vtype:ty), $($in:tt)*]
     ) => {
-        implement_to_sql!{@replace_type_spec $enum_name, [ $($out)*
-            $enum_name::$vname(v) => Ok(ToSqlOutput::from(v.to_primitive())),
+        implement_get_primitive!{@replace_type_spec $enum_name, [$($out)*
+            $enum_name::$vname(v) => Some(v.to_primitive().into()),
         ], [$($in)*]}
     };
     (
@@ -435,98 +1187,637 @@ macro_rules! implement_to_sql {
         [$($out:tt)*],
         [$vname:ident, $($in:tt)*]
     ) => {
-        implement_to_sql!{@replace_type_spec $enum_name, [ $($out)*
-            $enum_name::$vname => Ok(ToSqlOutput::from(Null)),
+        implement_get_primitive!{@replace_type_spec $enum_name, [$($out)*
+            $enum_name::$vname => None,
         ], [$($in)*]}
     };
     (@replace_type_spec $enum_name:ident, [$($out:tt)*], []) => {
-        /// Converts $enum_name to be stored in a rusqlite database.
-        fn to_sql(&self) -> SqlResult<ToSqlOutput> {
+        /// Returns the primitive representation of the wrapped value, or None if the variant
+        /// does not wrap a value, i.e., for boolean parameters.
+        pub fn get_primitive(&self) -> Option<Primitive> {
             match self {
                 $($out)*
+                $enum_name::Unknown { value, .. } => km_value_to_primitive(value),
             }
         }
     };
 
-
     ($enum_name:ident; $($vname:ident$(($vtype:ty))?),*) => {
-        impl ToSql for $enum_name {
-            implement_to_sql!{@replace_type_spec $enum_name, [], [$($vname$(($vtype))?,)*]}
-        }
-
-    }
+        implement_get_primitive!{@replace_type_spec $enum_name, [], [$($vname$(($vtype))?,)*]}
+    };
 }
 
 /// Expands the list of KeyParameterValue variants as follows:
 ///
 /// Input:
-/// Invalid with tag INVALID and field Invalid,
-/// Algorithm(Algorithm) with tag ALGORITHM and field Algorithm,
+/// KeySize(i32) with field Integer,
+/// RSAPublicExponent(i64) with field LongInteger,
+/// ApplicationID(Vec<u8>) with field Blob,
+/// CallerNonce with field BoolValue,
+/// Algorithm(Algorithm) with field Algorithm,
 ///
 /// Output:
 /// ```
-/// pub fn new_from_sql(
-///     tag: Tag,
-///     data: &SqlField,
-/// ) -> Result<Self> {
-///     Ok(match self {
-///         Tag::Invalid => KeyParameterValue::Invalid,
-///         Tag::ALGORITHM => {
-///             KeyParameterValue::Algorithm(<Algorithm>::from_primitive(data
-///                 .get()
-///                 .map_err(|_| KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
-///                 .context(concat!("Failed to read sql data for tag: ", "ALGORITHM", "."))?
-///             ))
-///         },
-///     })
+/// pub fn integer_value(&self) -> Option<i32> {
+///     match self {
+///         KeyParameterValue::KeySize(v) => Some(*v),
+///         KeyParameterValue::Unknown { value: KmKeyParameterValue::Integer(v), .. } => Some(*v),
+///         _ => None,
+///     }
+/// }
+///
+/// pub fn long_integer_value(&self) -> Option<i64> {
+///     match self {
+///         KeyParameterValue::RSAPublicExponent(v) => Some(*v),
+///         ...
+///     }
+/// }
+///
+/// pub fn blob_value(&self) -> Option<&[u8]> {
+///     match self {
+///         KeyParameterValue::ApplicationID(v) => Some(v),
+///         ...
+///     }
+/// }
+///
+/// pub fn bool_value(&self) -> Option<bool> {
+///     match self {
+///         KeyParameterValue::CallerNonce => Some(true),
+///         ...
+///     }
 /// }
 /// ```
-macro_rules! implement_new_from_sql {
-    ($enum_name:ident; $($vname:ident$(($vtype:ty))? $tag_name:ident),*) => {
-        /// Takes a tag and an SqlField and attempts to construct a KeyParameter value.
-        /// This function may fail if the parameter value cannot be extracted from the
-        /// database cell.
-        pub fn new_from_sql(
-            tag: Tag,
-            data: &SqlField,
-        ) -> Result<Self> {
-            Ok(match tag {
-                $(
-                    Tag::$tag_name => {
-                        $enum_name::$vname$((<$vtype>::from_primitive(data
-                            .get()
-                            .map_err(|_| KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
-                            .context(concat!(
-                                "Failed to read sql data for tag: ",
-                                stringify!($tag_name),
-                                "."
-                            ))?
-                        )))?
-                    },
-                )*
-                _ => $enum_name::Invalid,
-            })
-        }
+/// Variants of all other fields are not listed and fall through to `_ => None`.
+macro_rules! implement_field_accessors {
+    (
+        @group
+        $enum_name:ident,
+        [$($int_out:tt)*], [$($long_out:tt)*], [$($blob_out:tt)*], [$($bool_out:tt)*],
+        [$vname:ident($vtype:ty) Integer, $($in:tt)*]
+    ) => {
+        implement_field_accessors!{@group $enum_name, [$($int_out)*
+            $enum_name::$vname(v) => Some(*v),
+        ], [$($long_out)*], [$($blob_out)*], [$($bool_out)*], [$($in)*]}
     };
-}
-
+    (
+        @group
+        $enum_name:ident,
+        [$($int_out:tt)*], [$($long_out:tt)*], [$($blob_out:tt)*], [$($bool_out:tt)*],
+        [$vname:ident($vtype:ty) LongInteger, $($in:tt)*]
+    ) => {
+        implement_field_accessors!{@group $enum_name, [$($int_out)*], [$($long_out)*
+            $enum_name::$vname(v) => Some(*v),
+        ], [$($blob_out)*], [$($bool_out)*], [$($in)*]}
+    };
+    (
+        @group
+        $enum_name:ident,
+        [$($int_out:tt)*], [$($long_out:tt)*], [$($blob_out:tt)*], [$($bool_out:tt)*],
+        [$vname:ident($vtype:ty) Blob, $($in:tt)*]
+    ) => {
+        implement_field_accessors!{@group $enum_name, [$($int_out)*], [$($long_out)*], [
+            $($blob_out)*
+            $enum_name::$vname(v) => Some(v),
+        ], [$($bool_out)*], [$($in)*]}
+    };
+    (
+        @group
+        $enum_name:ident,
+        [$($int_out:tt)*], [$($long_out:tt)*], [$($blob_out:tt)*], [$($bool_out:tt)*],
+        [$vname:ident BoolValue, $($in:tt)*]
+    ) => {
+        implement_field_accessors!{@group $enum_name, [$($int_out)*], [$($long_out)*], [
+            $($blob_out)*
+        ], [$($bool_out)*
+            $enum_name::$vname => Some(true),
+        ], [$($in)*]}
+    };
+    (
+        @group
+        $enum_name:ident,
+        [$($int_out:tt)*], [$($long_out:tt)*], [$($blob_out:tt)*], [$($bool_out:tt)*],
+        [$vname:ident$(($vtype:ty))? $field_name:ident, $($in:tt)*]
+    ) => {
+        implement_field_accessors!{@group $enum_name, [$($int_out)*], [$($long_out)*], [
+            $($blob_out)*
+        ], [$($bool_out)*], [$($in)*]}
+    };
+    (
+        @group
+        $enum_name:ident,
+        [$($int_out:tt)*], [$($long_out:tt)*], [$($blob_out:tt)*], [$($bool_out:tt)*],
+        []
+    ) => {
+        /// Returns the wrapped value if the variant is stored in the Integer field. Unlike
+        /// `i32::try_from`, this returns None for enum values.
+        pub fn integer_value(&self) -> Option<i32> {
+            match self {
+                $($int_out)*
+                $enum_name::Unknown { value: KmKeyParameterValue::Integer(v), .. } => Some(*v),
+                _ => None,
+            }
+        }
+
+        /// Returns the wrapped value if the variant is stored in the LongInteger field. Unlike
+        /// `i64::try_from`, this returns None for DateTime values.
+        pub fn long_integer_value(&self) -> Option<i64> {
+            match self {
+                $($long_out)*
+                $enum_name::Unknown { value: KmKeyParameterValue::LongInteger(v), .. } => {
+                    Some(*v)
+                }
+                _ => None,
+            }
+        }
+
+        /// Returns the wrapped bytes if the variant is stored in the Blob field.
+        pub fn blob_value(&self) -> Option<&[u8]> {
+            match self {
+                $($blob_out)*
+                $enum_name::Unknown { value: KmKeyParameterValue::Blob(v), .. } => Some(v),
+                _ => None,
+            }
+        }
+
+        /// Returns Some(true) if the variant is a boolean parameter. Boolean parameters are
+        /// implicitly true if present.
+        pub fn bool_value(&self) -> Option<bool> {
+            match self {
+                $($bool_out)*
+                $enum_name::Unknown { value: KmKeyParameterValue::BoolValue(_), .. } => Some(true),
+                _ => None,
+            }
+        }
+    };
+
+    ($enum_name:ident; $($vname:ident$(($vtype:ty))? $field_name:ident),*) => {
+        implement_field_accessors!{
+            @group $enum_name, [], [], [], [], [$($vname$(($vtype))? $field_name,)*]
+        }
+    };
+}
+
+/// Expands the list of KeyParameterValue variants as follows:
+///
+/// Input:
+/// KeySize(i32) with field Integer,
+/// CallerNonce with field BoolValue,
+/// NoAuthRequired with field BoolValue,
+///
+/// Output:
+/// ```
+/// fn bool_flag_bit(&self) -> Option<u32> {
+///     match self {
+///         KeyParameterValue::CallerNonce => Some(0),
+///         KeyParameterValue::NoAuthRequired => Some(0 + 1),
+///         _ => None,
+///     }
+/// }
+/// ```
+/// The bits are assigned in the order of the BoolValue variants in the list. Variants of all
+/// other fields fall through to `_ => None`.
+macro_rules! implement_bool_flag_bit {
+    (@bits $enum_name:ident, $bit:expr, [$($out:tt)*], [$vname:ident BoolValue, $($in:tt)*]) => {
+        implement_bool_flag_bit!{@bits $enum_name, $bit + 1, [$($out)*
+            $enum_name::$vname => Some($bit),
+        ], [$($in)*]}
+    };
+    (
+        @bits $enum_name:ident, $bit:expr, [$($out:tt)*],
+        [$vname:ident$(($vtype:ty))? $field_name:ident, $($in:tt)*]
+    ) => {
+        implement_bool_flag_bit!{@bits $enum_name, $bit, [$($out)*], [$($in)*]}
+    };
+    (@bits $enum_name:ident, $bit:expr, [$($out:tt)*], []) => {
+        /// Returns the position of the variant in a BoolTagSet if it is a boolean parameter.
+        fn bool_flag_bit(&self) -> Option<u32> {
+            match self {
+                $($out)*
+                _ => None,
+            }
+        }
+    };
+    ($enum_name:ident; $($vname:ident$(($vtype:ty))? $field_name:ident),*) => {
+        implement_bool_flag_bit!{@bits $enum_name, 0, [], [$($vname$(($vtype))? $field_name,)*]}
+    };
+}
+
+/// Expands one KeyParameterValue variant as follows:
+///
+/// Input:
+/// Algorithm(Algorithm) with tag ALGORITHM, field Algorithm, and no markers,
+///
+/// Output:
+/// ```
+/// impl TryFrom<KeyParameterValue> for Algorithm {
+///     type Error = KeyParameterValueError;
+///
+///     fn try_from(v: KeyParameterValue) -> Result<Self, Self::Error> {
+///         match v {
+///             KeyParameterValue::Algorithm(v) => Ok(v),
+///             v => Err(KeyParameterValueError::WrongVariant {
+///                 expected_tag: Tag::ALGORITHM,
+///                 actual_tag: v.get_tag(),
+///             }),
+///         }
+///     }
+/// }
+/// ```
+/// Only variants wrapping one of the AIDL enum types, i.e., variants whose field is neither
+/// Invalid, Integer, LongInteger, DateTime, Blob, nor BoolValue, are expanded. Some types are
+/// wrapped by more than one variant, e.g., Digest is also wrapped by RsaOaepMgfDigest. The
+/// additional variants are marked with `#[key_param(shared_type)]` and expand to nothing.
+macro_rules! implement_try_from_key_parameter_value {
+    (
+        @impl $enum_name:ident, $vname:ident($vtype:ty) $tag_name:ident
+        [shared_type $($tail:ident)*]
+    ) => {};
+    (
+        @impl $enum_name:ident, $vname:ident($vtype:ty) $tag_name:ident
+        [$head:ident $($tail:ident)*]
+    ) => {
+        implement_try_from_key_parameter_value!{
+            @impl $enum_name, $vname($vtype) $tag_name [$($tail)*]
+        }
+    };
+    (@impl $enum_name:ident, $vname:ident($vtype:ty) $tag_name:ident []) => {
+        impl TryFrom<$enum_name> for $vtype {
+            type Error = KeyParameterValueError;
+
+            fn try_from(v: $enum_name) -> Result<Self, Self::Error> {
+                match v {
+                    $enum_name::$vname(v) => Ok(v),
+                    v => Err(KeyParameterValueError::WrongVariant {
+                        expected_tag: Tag::$tag_name,
+                        actual_tag: v.get_tag(),
+                    }),
+                }
+            }
+        }
+    };
+    ($enum_name:ident; $vname:ident $tag_name:ident BoolValue [$($flags:ident)*]) => {};
+    ($enum_name:ident; $vname:ident($vtype:ty) $tag_name:ident Invalid [$($flags:ident)*]) => {};
+    ($enum_name:ident; $vname:ident($vtype:ty) $tag_name:ident Integer [$($flags:ident)*]) => {};
+    (
+        $enum_name:ident; $vname:ident($vtype:ty) $tag_name:ident LongInteger
+        [$($flags:ident)*]
+    ) => {};
+    ($enum_name:ident; $vname:ident($vtype:ty) $tag_name:ident DateTime [$($flags:ident)*]) => {};
+    ($enum_name:ident; $vname:ident($vtype:ty) $tag_name:ident Blob [$($flags:ident)*]) => {};
+    (
+        $enum_name:ident; $vname:ident($vtype:ty) $tag_name:ident $field_name:ident
+        [$($flags:ident)*]
+    ) => {
+        implement_try_from_key_parameter_value!{
+            @impl $enum_name, $vname($vtype) $tag_name [$($flags)*]
+        }
+    };
+}
+
+/// Expands the list of KeyParameterValue variants as follows:
+///
+/// Input:
+/// CallerNonce with tag CALLER_NONCE and field BoolValue,
+/// Algorithm(Algorithm) with tag ALGORITHM and field Algorithm,
+///
+/// Output:
+/// ```
+/// fn to_sql(&self) -> SqlResult<ToSqlOutput> {
+///     match self {
+///         KeyParameterValue::CallerNonce => Ok(ToSqlOutput::from(Null)),
+///         KeyParameterValue::Algorithm(v) => Ok(ToSqlOutput::from(v.to_primitive())),
+///         KeyParameterValue::Unknown { value, .. } => Ok(match km_value_to_primitive(value) {
+///             Some(Primitive::I32(v)) => ToSqlOutput::from(v),
+///             ...
+///             None => ToSqlOutput::from(Null),
+///         }),
+///     }
+/// }
+/// ```
+macro_rules! implement_to_sql {
+    (
+        @replace_type_spec
+        $enum_name:ident,
+        [$($out:tt)*],
+        [$vname:ident($vtype:ty), $($in:tt)*]
+    ) => {
+        implement_to_sql!{@replace_type_spec $enum_name, [ $($out)*
+            $enum_name::$vname(v) => Ok(ToSqlOutput::from(v.to_primitive())),
+        ], [$($in)*]}
+    };
+    (
+        @replace_type_spec
+        $enum_name:ident,
+        [$($out:tt)*],
+        [$vname:ident, $($in:tt)*]
+    ) => {
+        implement_to_sql!{@replace_type_spec $enum_name, [ $($out)*
+            $enum_name::$vname => Ok(ToSqlOutput::from(Null)),
+        ], [$($in)*]}
+    };
+    (@replace_type_spec $enum_name:ident, [$($out:tt)*], []) => {
+        /// Converts $enum_name to be stored in a rusqlite database.
+        fn to_sql(&self) -> SqlResult<ToSqlOutput> {
+            match self {
+                $($out)*
+                $enum_name::Unknown { value, .. } => Ok(match km_value_to_primitive(value) {
+                    Some(Primitive::I32(v)) => ToSqlOutput::from(v),
+                    Some(Primitive::I64(v)) => ToSqlOutput::from(v),
+                    Some(Primitive::Vec(v)) => ToSqlOutput::from(v),
+                    None => ToSqlOutput::from(Null),
+                }),
+            }
+        }
+    };
+
+
+    ($enum_name:ident; $($vname:ident$(($vtype:ty))?),*) => {
+        #[cfg(feature = "sql")]
+        #[allow(deprecated)]
+        impl ToSql for $enum_name {
+            implement_to_sql!{@replace_type_spec $enum_name, [], [$($vname$(($vtype))?,)*]}
+        }
+
+    }
+}
+
+/// Returns the type of the column value of the given SqlField, e.g., "Null" or "Blob", so that
+/// errors reading a corrupted key parameter say what was found in the database.
+#[cfg(feature = "sql")]
+fn sql_column_type(data: &SqlField) -> String {
+    data.data_type().map_or_else(|_| "unknown".to_string(), |t| t.to_string())
+}
+
+/// Expands the list of KeyParameterValue variants as follows:
+///
+/// Input:
+/// CallerNonce with tag CALLER_NONCE and field BoolValue,
+/// Algorithm(Algorithm) with tag ALGORITHM and field Algorithm,
+///
+/// Output:
+/// ```
+/// pub fn new_from_sql(
+///     tag: Tag,
+///     data: &SqlField,
+/// ) -> Result<Self, KeyParameterError> {
+///     let value = match self {
+///         Tag::CALLER_NONCE => KeyParameterValue::CallerNonce,
+///         Tag::ALGORITHM => {
+///             KeyParameterValue::Algorithm(<Algorithm>::from_primitive(data
+///                 .get()
+///                 .map_err(|_| KeyParameterError::SqlCorrupted(tag))?
+///             ))
+///         },
+///         _ => KeyParameterValue::new_unknown_from_sql(tag, data)?,
+///     };
+///     Ok(value.check_blob_size()?)
+/// }
+/// ```
+macro_rules! implement_new_from_sql {
+    ($enum_name:ident; $($vname:ident$(($vtype:ty))? $tag_name:ident),*) => {
+        /// Takes a tag and an SqlField and attempts to construct a KeyParameter value.
+        /// This function fails with KeyParameterError::SqlCorrupted if the parameter value
+        /// cannot be extracted from the database cell, and with KeyParameterError::BlobTooLarge
+        /// if the blob exceeds the maximum size of the tag. Invalid parameters written before
+        /// Invalid carried a payload are stored as NULL and read back as `Invalid(0)`.
+        #[cfg(feature = "sql")]
+        pub fn new_from_sql(
+            tag: Tag,
+            data: &SqlField,
+        ) -> Result<Self, KeyParameterError> {
+            if tag == Tag::INVALID {
+                let v: Option<i32> =
+                    data.get().map_err(|_| KeyParameterError::SqlCorrupted(tag))?;
+                return Ok($enum_name::Invalid(v.unwrap_or(0)));
+            }
+            let value = match tag {
+                $(
+                    Tag::$tag_name => {
+                        $enum_name::$vname$((<$vtype>::from_primitive(data
+                            .get()
+                            .map_err(|_| KeyParameterError::SqlCorrupted(tag))?
+                        )))?
+                    },
+                )*
+                _ => $enum_name::new_unknown_from_sql(tag, data)?,
+            };
+            Ok(value.check_blob_size()?)
+        }
+    };
+}
+
+/// Expands the list of KeyParameterValue variants as follows:
+///
+/// Input:
+/// CallerNonce with tag CALLER_NONCE and field BoolValue,
+/// Algorithm(Algorithm) with tag ALGORITHM and field Algorithm,
+///
+/// Output:
+/// ```
+/// pub fn validate_enum_ranges(&self) -> Result<(), EnumRangeError> {
+///     let defined = match self {
+///         KeyParameterValue::CallerNonce => true,
+///         KeyParameterValue::Algorithm(v) => v.is_defined(),
+///         KeyParameterValue::Unknown { .. } => true,
+///     };
+///     ...
+/// }
+/// ```
+macro_rules! implement_validate_enum_ranges {
+    (
+        @replace_type_spec
+        $enum_name:ident,
+        [$($out:tt)*],
+        [$vname:ident($vtype:ty), $($in:tt)*]
+    ) => {
+        implement_validate_enum_ranges!{@replace_type_spec $enum_name, [$($out)*
+            $enum_name::$vname(v) => v.is_defined(),
+        ], [$($in)*]}
+    };
+    (
+        @replace_type_spec
+        $enum_name:ident,
+        [$($out:tt)*],
+        [$vname:ident, $($in:tt)*]
+    ) => {
+        implement_validate_enum_ranges!{@replace_type_spec $enum_name, [$($out)*
+            $enum_name::$vname => true,
+        ], [$($in)*]}
+    };
+    (@replace_type_spec $enum_name:ident, [$($out:tt)*], []) => {
+        /// Checks that a wrapped AIDL enum value is one of the constants defined for the enum,
+        /// e.g., that an Algorithm is not `Algorithm(9999)`. HardwareAuthenticatorType is a bit
+        /// mask and may be any combination of its constants. Values that are not enums and
+        /// values with tags unknown to Keystore always pass.
+        pub fn validate_enum_ranges(&self) -> Result<(), EnumRangeError> {
+            let defined = match self {
+                $($out)*
+                $enum_name::Unknown { .. } => true,
+            };
+            match (defined, self.get_primitive()) {
+                (false, Some(Primitive::I32(value))) => {
+                    Err(EnumRangeError::UndefinedValue { tag: self.get_tag(), value })
+                }
+                _ => Ok(()),
+            }
+        }
+    };
+
+    ($enum_name:ident; $($vname:ident$(($vtype:ty))?),*) => {
+        implement_validate_enum_ranges!{
+            @replace_type_spec $enum_name, [], [$($vname$(($vtype))?,)*]
+        }
+    };
+}
+
+/// Writes the wrapped value of a KeyParameterValue variant in human-readable form.
+/// The rule is selected by the keymint::KeyParameterValue field: enum types are printed by
+/// their symbolic AIDL name, date times as ISO-8601, and blobs only by their length, so that no
+/// key material ends up in logs.
+macro_rules! display_field_value {
+    (Integer $v:ident $f:ident) => {
+        write!($f, "{}", $v)
+    };
+    (LongInteger $v:ident $f:ident) => {
+        write!($f, "{}", $v)
+    };
+    (DateTime $v:ident $f:ident) => {
+        write_iso8601_millis($f, *$v)
+    };
+    (Blob $v:ident $f:ident) => {
+        write!($f, "{} bytes", $v.len())
+    };
+    ($field_name:ident $v:ident $f:ident) => {
+        write!($f, "{:?}", $v)
+    };
+}
+
+/// Writes the given milliseconds since the epoch as ISO-8601 UTC date time, e.g.,
+/// `2024-01-01T00:00:00Z`. Fractional seconds are only written if present.
+fn write_iso8601_millis(f: &mut std::fmt::Formatter, millis: i64) -> std::fmt::Result {
+    let secs = millis.div_euclid(1000);
+    let ms = millis.rem_euclid(1000);
+    let days = secs.div_euclid(86400);
+    let secs_of_day = secs.rem_euclid(86400);
+
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    write!(
+        f,
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )?;
+    if ms != 0 {
+        write!(f, ".{:03}", ms)?;
+    }
+    write!(f, "Z")
+}
+
+/// Expands the list of KeyParameterValue variants as follows:
+///
+/// Input:
+/// CallerNonce with tag CALLER_NONCE and field BoolValue,
+/// Algorithm(Algorithm) with tag ALGORITHM and field Algorithm,
+///
+/// Output:
+/// ```
+/// impl std::fmt::Display for KeyParameterValue {
+///     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+///         if self.is_sensitive() && self.tag_type() != TagType::BOOL {
+///             return write!(f, "{:?}=<redacted>", self.get_tag());
+///         }
+///         match self {
+///             KeyParameterValue::CallerNonce => f.write_str("CALLER_NONCE=true"),
+///             KeyParameterValue::Algorithm(v) => {
+///                 f.write_str("ALGORITHM=")?;
+///                 display_field_value!(Algorithm v f)
+///             }
+///             KeyParameterValue::Unknown { tag, value } => {
+///                 write!(f, "{:?}=", tag)?;
+///                 ...
+///             }
+///         }
+///     }
+/// }
+/// ```
+macro_rules! implement_display {
+    (
+        @replace_type_spec
+        $enum_name:ident $f:ident,
+        [$($out:tt)*],
+        [$vname:ident($vtype:ty) $tag_name:ident $field_name:ident, $($in:tt)*]
+    ) => {
+        implement_display!{@replace_type_spec $enum_name $f, [$($out)*
+            $enum_name::$vname(v) => {
+                $f.write_str(concat!(stringify!($tag_name), "="))?;
+                display_field_value!($field_name v $f)
+            }
+        ], [$($in)*]}
+    };
+    (
+        @replace_type_spec
+        $enum_name:ident $f:ident,
+        [$($out:tt)*],
+        [$vname:ident $tag_name:ident BoolValue, $($in:tt)*]
+    ) => {
+        implement_display!{@replace_type_spec $enum_name $f, [$($out)*
+            $enum_name::$vname => $f.write_str(concat!(stringify!($tag_name), "=true")),
+        ], [$($in)*]}
+    };
+    (@replace_type_spec $enum_name:ident $f:ident, [$($out:tt)*], []) => {
+        #[allow(deprecated)]
+        impl std::fmt::Display for $enum_name {
+            fn fmt(&self, $f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                // Boolean parameters carry no value that could be leaked.
+                if self.is_sensitive() && self.tag_type() != TagType::BOOL {
+                    return write!($f, "{:?}=<redacted>", self.get_tag());
+                }
+                match self {
+                    $($out)*
+                    $enum_name::Unknown { tag, value } => {
+                        write!($f, "{:?}=", tag)?;
+                        match km_value_to_primitive(value) {
+                            Some(Primitive::I32(v)) => write!($f, "{}", v),
+                            Some(Primitive::I64(v)) => write!($f, "{}", v),
+                            Some(Primitive::Vec(v)) => write!($f, "{} bytes", v.len()),
+                            None => $f.write_str("true"),
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    ($enum_name:ident; $($vname:ident$(($vtype:ty))? $tag_name:ident $field_name:ident),*) => {
+        implement_display!{
+            @replace_type_spec $enum_name f, [], [$($vname$(($vtype))? $tag_name $field_name,)*]
+        }
+    };
+}
+
 /// This key parameter default is used during the conversion from KeyParameterValue
 /// to keymint::KeyParameterValue. Keystore's version does not have wrapped types
-/// for boolean tags and the tag Invalid. The AIDL version uses bool and integer
-/// variants respectively. This default function is invoked in these cases to
-/// homogenize the rules for boolean and invalid tags.
-/// The bool variant returns true because boolean parameters are implicitly true
-/// if present.
+/// for boolean tags. The AIDL version uses a bool variant. This default function is
+/// invoked in this case and returns true because boolean parameters are implicitly
+/// true if present. Invalid wraps the integer payload of its field and needs no default.
 trait KpDefault {
     fn default() -> Self;
 }
 
-impl KpDefault for i32 {
-    fn default() -> Self {
-        0
-    }
-}
-
 impl KpDefault for bool {
     fn default() -> Self {
         true
@@ -536,19 +1827,37 @@ impl KpDefault for bool {
 /// Expands the list of KeyParameterValue variants as follows:
 ///
 /// Input:
-/// Invalid with tag INVALID and field Invalid,
+/// CallerNonce with tag CALLER_NONCE and field BoolValue,
 /// Algorithm(Algorithm) with tag ALGORITHM and field Algorithm,
 ///
 /// Output:
 /// ```
-/// impl From<KmKeyParameter> for KeyParameterValue {
-///     fn from(kp: KmKeyParameter) -> Self {
+/// impl KeyParameterValue {
+///     pub fn from_km_parameter_lossy(kp: KmKeyParameter) -> Self {
 ///         match kp {
-///             KmKeyParameter { tag: Tag::INVALID, value: KmKeyParameterValue::Invalid(_) }
-///                 => $enum_name::$vname,
+///             KmKeyParameter { tag: Tag::CALLER_NONCE, value: KmKeyParameterValue::BoolValue(_) }
+///                 => KeyParameterValue::CallerNonce,
 ///             KmKeyParameter { tag: Tag::Algorithm, value: KmKeyParameterValue::Algorithm(v) }
 ///                 => $enum_name::Algorithm(v),
-///             _ => $enum_name::Invalid,
+///             KmKeyParameter { tag, value } => match tag {
+///                 Tag::CALLER_NONCE | Tag::ALGORITHM => KeyParameterValue::Invalid(0),
+///                 _ => KeyParameterValue::Unknown { tag, value },
+///             },
+///         }
+///     }
+/// }
+///
+/// impl<'a> KeyParameterValueRef<'a> {
+///     pub fn from_km_parameter_lossy(kp: &'a KmKeyParameter) -> Self {
+///         match kp {
+///             KmKeyParameter { tag: Tag::CALLER_NONCE, value: KmKeyParameterValue::BoolValue(_) }
+///                 => KeyParameterValueRef::CallerNonce,
+///             KmKeyParameter { tag: Tag::Algorithm, value: KmKeyParameterValue::Algorithm(v) }
+///                 => KeyParameterValueRef::Algorithm(BorrowField::borrow_field(v)),
+///             KmKeyParameter { tag, value } => match *tag {
+///                 Tag::CALLER_NONCE | Tag::ALGORITHM => KeyParameterValueRef::Invalid(0),
+///                 tag => KeyParameterValueRef::Unknown { tag, value },
+///             },
 ///         }
 ///     }
 /// }
@@ -556,52 +1865,89 @@ impl KpDefault for bool {
 /// impl Into<KmKeyParameter> for KeyParameterValue {
 ///     fn into(self) -> KmKeyParameter {
 ///         match self {
-///             KeyParameterValue::Invalid => KmKeyParameter {
+///             KeyParameterValue::CallerNonce => KmKeyParameter {
 ///                 tag: Tag::INVALID,
-///                 value: KmKeyParameterValue::Invalid(KpDefault::default())
+///                 value: KmKeyParameterValue::BoolValue(KpDefault::default())
 ///             },
 ///             KeyParameterValue::Algorithm(v) => KmKeyParameter {
 ///                 tag: Tag::ALGORITHM,
 ///                 value: KmKeyParameterValue::Algorithm(v)
 ///             },
+///             KeyParameterValue::Unknown { tag, value } => KmKeyParameter { tag, value },
 ///         }
 ///     }
 /// }
 /// ```
 macro_rules! implement_try_from_to_km_parameter {
-    // The first three rules expand From<KmKeyParameter>.
+    // The first three rules expand the lossy conversions from KmKeyParameter and
+    // &KmKeyParameter.
     (
         @from
-        $enum_name:ident,
+        $enum_name:ident [$($tags:ident)*],
         [$($out:tt)*],
+        [$($ref_out:tt)*],
         [$vname:ident($vtype:ty) $tag_name:ident $field_name:ident, $($in:tt)*]
     ) => {
-        implement_try_from_to_km_parameter!{@from $enum_name, [$($out)*
+        implement_try_from_to_km_parameter!{@from $enum_name [$($tags)*], [$($out)*
             KmKeyParameter {
                 tag: Tag::$tag_name,
                 value: KmKeyParameterValue::$field_name(v)
             } => $enum_name::$vname(v),
+        ], [$($ref_out)*
+            KmKeyParameter {
+                tag: Tag::$tag_name,
+                value: KmKeyParameterValue::$field_name(v)
+            } => KeyParameterValueRef::$vname(BorrowField::borrow_field(v)),
         ], [$($in)*]
     }};
     (
         @from
-        $enum_name:ident,
+        $enum_name:ident [$($tags:ident)*],
         [$($out:tt)*],
+        [$($ref_out:tt)*],
         [$vname:ident $tag_name:ident $field_name:ident, $($in:tt)*]
     ) => {
-        implement_try_from_to_km_parameter!{@from $enum_name, [$($out)*
+        implement_try_from_to_km_parameter!{@from $enum_name [$($tags)*], [$($out)*
             KmKeyParameter {
                 tag: Tag::$tag_name,
                 value: KmKeyParameterValue::$field_name(_)
             } => $enum_name::$vname,
+        ], [$($ref_out)*
+            KmKeyParameter {
+                tag: Tag::$tag_name,
+                value: KmKeyParameterValue::$field_name(_)
+            } => KeyParameterValueRef::$vname,
         ], [$($in)*]
     }};
-    (@from $enum_name:ident, [$($out:tt)*], []) => {
-        impl From<KmKeyParameter> for $enum_name {
-            fn from(kp: KmKeyParameter) -> Self {
+    (@from $enum_name:ident [$($tags:ident)*], [$($out:tt)*], [$($ref_out:tt)*], []) => {
+        #[allow(deprecated)]
+        impl $enum_name {
+            /// Converts the KeyMint parameter without failing. Parameters whose union field
+            /// does not match the field of the tag become `Invalid(0)`, and parameters with tags
+            /// unknown to Keystore become `Unknown`. Use `try_from_km_parameter` where these
+            /// cases must be told apart.
+            pub fn from_km_parameter_lossy(kp: KmKeyParameter) -> Self {
                 match kp {
                     $($out)*
-                    _ => $enum_name::Invalid,
+                    KmKeyParameter { tag, value } => match tag {
+                        $(Tag::$tags)|* => $enum_name::Invalid(0),
+                        _ => $enum_name::Unknown { tag, value },
+                    },
+                }
+            }
+        }
+
+        #[allow(deprecated)]
+        impl<'a> KeyParameterValueRef<'a> {
+            /// Borrowing counterpart of `KeyParameterValue::from_km_parameter_lossy`. Blob
+            /// payloads are borrowed from the KeyMint parameter rather than copied.
+            pub fn from_km_parameter_lossy(kp: &'a KmKeyParameter) -> Self {
+                match kp {
+                    $($ref_out)*
+                    KmKeyParameter { tag, value } => match *tag {
+                        $(Tag::$tags)|* => KeyParameterValueRef::Invalid(0),
+                        tag => KeyParameterValueRef::Unknown { tag, value },
+                    },
                 }
             }
         }
@@ -635,10 +1981,12 @@ macro_rules! implement_try_from_to_km_parameter {
         ], [$($in)*]
     }};
     (@into $enum_name:ident, [$($out:tt)*], []) => {
+        #[allow(deprecated)]
         impl From<$enum_name> for KmKeyParameter {
             fn from(x: $enum_name) -> Self {
                 match x {
                     $($out)*
+                    $enum_name::Unknown { tag, value } => KmKeyParameter { tag, value },
                 }
             }
         }
@@ -647,7 +1995,8 @@ macro_rules! implement_try_from_to_km_parameter {
 
     ($enum_name:ident; $($vname:ident$(($vtype:ty))? $tag_name:ident $field_name:ident),*) => {
         implement_try_from_to_km_parameter!(
-            @from $enum_name,
+            @from $enum_name [$($tag_name)*],
+            [],
             [],
             [$($vname$(($vtype))? $tag_name $field_name,)*]
         );
@@ -657,31 +2006,354 @@ macro_rules! implement_try_from_to_km_parameter {
             [$($vname$(($vtype))? $tag_name $field_name,)*]
         );
     };
-}
+}
+
+/// Expands the list of KeyParameterValue variants as follows:
+///
+/// Input:
+/// CallerNonce, Algorithm(Algorithm), ApplicationID(Vec<u8>)
+///
+/// Output:
+/// ```
+/// pub enum KeyParameterValueRef<'a> {
+///     CallerNonce,
+///     Algorithm(<Algorithm as BorrowField<'a>>::Borrowed),
+///     ApplicationID(<Vec<u8> as BorrowField<'a>>::Borrowed),
+///     Unknown { tag: Tag, value: &'a KmKeyParameterValue },
+/// }
+///
+/// impl KeyParameterValue {
+///     pub fn as_ref(&self) -> KeyParameterValueRef<'_> {
+///         match self {
+///             KeyParameterValue::CallerNonce => KeyParameterValueRef::CallerNonce,
+///             KeyParameterValue::Algorithm(v) => {
+///                 KeyParameterValueRef::Algorithm(BorrowField::borrow_field(v))
+///             }
+///             ...
+///             KeyParameterValue::Unknown { tag, value } => {
+///                 KeyParameterValueRef::Unknown { tag: *tag, value }
+///             }
+///         }
+///     }
+/// }
+///
+/// impl From<KeyParameterValueRef<'_>> for KeyParameterValue {
+///     fn from(v: KeyParameterValueRef<'_>) -> Self {
+///         match v {
+///             KeyParameterValueRef::CallerNonce => KeyParameterValue::CallerNonce,
+///             KeyParameterValueRef::Algorithm(v) => {
+///                 KeyParameterValue::Algorithm(BorrowField::to_owned_field(v))
+///             }
+///             ...
+///         }
+///     }
+/// }
+/// ```
+macro_rules! implement_value_ref {
+    // The next three rules expand as_ref.
+    (
+        @as_ref
+        $enum_name:ident,
+        [$($out:tt)*],
+        [$vname:ident($vtype:ty), $($in:tt)*]
+    ) => {
+        implement_value_ref!{@as_ref $enum_name, [$($out)*
+            $enum_name::$vname(v) => KeyParameterValueRef::$vname(BorrowField::borrow_field(v)),
+        ], [$($in)*]}
+    };
+    (
+        @as_ref
+        $enum_name:ident,
+        [$($out:tt)*],
+        [$vname:ident, $($in:tt)*]
+    ) => {
+        implement_value_ref!{@as_ref $enum_name, [$($out)*
+            $enum_name::$vname => KeyParameterValueRef::$vname,
+        ], [$($in)*]}
+    };
+    (@as_ref $enum_name:ident, [$($out:tt)*], []) => {
+        #[allow(deprecated)]
+        impl $enum_name {
+            /// Returns a borrowed view of the value. Blob payloads are not copied.
+            pub fn as_ref(&self) -> KeyParameterValueRef<'_> {
+                match self {
+                    $($out)*
+                    $enum_name::Unknown { tag, value } => {
+                        KeyParameterValueRef::Unknown { tag: *tag, value }
+                    }
+                }
+            }
+        }
+    };
+
+    // The next three rules expand From<KeyParameterValueRef>.
+    (
+        @to_owned
+        $enum_name:ident,
+        [$($out:tt)*],
+        [$vname:ident($vtype:ty), $($in:tt)*]
+    ) => {
+        implement_value_ref!{@to_owned $enum_name, [$($out)*
+            KeyParameterValueRef::$vname(v) => $enum_name::$vname(BorrowField::to_owned_field(v)),
+        ], [$($in)*]}
+    };
+    (
+        @to_owned
+        $enum_name:ident,
+        [$($out:tt)*],
+        [$vname:ident, $($in:tt)*]
+    ) => {
+        implement_value_ref!{@to_owned $enum_name, [$($out)*
+            KeyParameterValueRef::$vname => $enum_name::$vname,
+        ], [$($in)*]}
+    };
+    (@to_owned $enum_name:ident, [$($out:tt)*], []) => {
+        #[allow(deprecated)]
+        impl From<KeyParameterValueRef<'_>> for $enum_name {
+            fn from(v: KeyParameterValueRef<'_>) -> Self {
+                match v {
+                    $($out)*
+                    KeyParameterValueRef::Unknown { tag, value } => {
+                        $enum_name::Unknown { tag, value: value.clone() }
+                    }
+                }
+            }
+        }
+    };
+
+    ($enum_name:ident; $($vname:ident$(($vtype:ty))?),*) => {
+        /// Borrowed view of a KeyParameterValue as returned by `KeyParameterValue::as_ref`.
+        /// Blob payloads are borrowed, all other payloads are copied.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum KeyParameterValueRef<'a> {
+            $(
+                #[doc = concat!("Borrowed form of `KeyParameterValue::", stringify!($vname), "`.")]
+                $vname$((<$vtype as BorrowField<'a>>::Borrowed))?,
+            )*
+            /// Borrowed form of `KeyParameterValue::Unknown`.
+            Unknown {
+                /// The original tag.
+                tag: Tag,
+                /// The original value.
+                value: &'a KmKeyParameterValue,
+            },
+        }
+
+        implement_value_ref!{@as_ref $enum_name, [], [$($vname$(($vtype))?,)*]}
+        implement_value_ref!{@to_owned $enum_name, [], [$($vname$(($vtype))?,)*]}
+    };
+}
+
+/// Expands to `true` if the marker given as first argument is present in the list of markers
+/// attached to a variant with `#[key_param(<marker>)]`, and to `false` otherwise.
+///
+/// Input:
+/// key_param_has_flag!(sensitive, [sensitive])
+///
+/// Output:
+/// true
+macro_rules! key_param_has_flag {
+    (sensitive, [sensitive $($tail:ident)*]) => {
+        true
+    };
+    (not_stored, [not_stored $($tail:ident)*]) => {
+        true
+    };
+    (repeatable, [repeatable $($tail:ident)*]) => {
+        true
+    };
+    (volatile, [volatile $($tail:ident)*]) => {
+        true
+    };
+    (not_characteristic, [not_characteristic $($tail:ident)*]) => {
+        true
+    };
+    ($flag:ident, [$head:ident $($tail:ident)*]) => {
+        key_param_has_flag!($flag, [$($tail)*])
+    };
+    ($flag:ident, []) => {
+        false
+    };
+}
+
+/// Expands to the TagCategory given with `#[key_param(category = ...)]`, or to
+/// TagCategory::Other if the variant has no category.
+///
+/// Input:
+/// key_param_category!(Lifecycle)
+///
+/// Output:
+/// TagCategory::Lifecycle
+macro_rules! key_param_category {
+    () => {
+        TagCategory::Other
+    };
+    ($category:ident) => {
+        TagCategory::$category
+    };
+}
+
+/// This is the top level macro. While the other macros do most of the heavy lifting, this takes
+/// the key parameter list and passes it on to the other macros to generate all of the conversion
+/// functions. In addition, it generates an important test vector for verifying that tag type of the
+/// keymint tag matches the associated keymint KeyParameterValue field.
+///
+/// Besides `#[key_param(tag = ..., field = ...)]`, which is mandatory for every variant, a variant
+/// may carry marker attributes of the form `#[key_param(<marker>)]`. The markers are stripped from
+/// the enum declaration and evaluated with `key_param_has_flag!`. Supported markers:
+///  * `sensitive`: The variant carries secret or per-use material that must not be logged. See
+///    `KeyParameterValue::is_sensitive`.
+///  * `not_stored`: The variant is an operation or attestation input that must not be stored
+///    in the database. See `KeyParameterValue::is_storable`.
+///  * `repeatable`: The tag may occur more than once in a key's characteristics. See
+///    `KeyParameterValue::is_repeated_allowed`.
+///  * `volatile`: The value legitimately differs between characteristics snapshots of the same
+///    key. See `KeyParameterValue::is_volatile`.
+///  * `not_characteristic`: The tag never appears in key characteristics, because it is an
+///    operation, generation, or attestation parameter. See
+///    `KeyParameterValue::is_characteristic`.
+///  * `shared_type`: The variant wraps the same AIDL enum type as an earlier variant, so no
+///    `TryFrom<KeyParameterValue>` implementation is generated for it. See
+///    `implement_try_from_key_parameter_value`.
+///  * `deprecated`: The tag is no longer enforced by KeyMint. The variant is declared
+///    `#[deprecated]`, so that new code using it gets a warning. The generated code allows the
+///    use of deprecated variants.
+///
+/// In addition, `#[key_param(category = <TagCategory variant>)]` assigns the tag to a category,
+/// see `tag_category`. Variants without a category are in TagCategory::Other.
+macro_rules! implement_key_parameter_value {
+    (
+        $(#[$enum_meta:meta])*
+        $enum_vis:vis enum $enum_name:ident {
+            $(
+                $(#[$($emeta:tt)+])*
+                $vname:ident$(($vtype:ty))?
+            ),* $(,)?
+        }
+    ) => {
+        implement_key_parameter_value!{
+            @extract_attr
+            $(#[$enum_meta])*
+            $enum_vis enum $enum_name {
+                []
+                [$(
+                    [] [] [] [] [$(#[$($emeta)+])*]
+                    $vname$(($vtype))?,
+                )*]
+            }
+        }
+    };
+
+    (
+        @extract_attr
+        $(#[$enum_meta:meta])*
+        $enum_vis:vis enum $enum_name:ident {
+            [$($out:tt)*]
+            [
+                [$(#[$mout:meta])*]
+                []
+                [$($flags:ident)*]
+                [$($category:ident)*]
+                [
+                    #[key_param(tag = $tag_name:ident, field = $field_name:ident)]
+                    $(#[$($mtail:tt)+])*
+                ]
+                $vname:ident$(($vtype:ty))?,
+                $($tail:tt)*
+            ]
+        }
+    ) => {
+        implement_key_parameter_value!{
+            @extract_attr
+            $(#[$enum_meta])*
+            $enum_vis enum $enum_name {
+                [$($out)*]
+                [
+                    [$(#[$mout])*]
+                    [$tag_name $field_name]
+                    [$($flags)*]
+                    [$($category)*]
+                    [$(#[$($mtail)+])*]
+                    $vname$(($vtype))?,
+                    $($tail)*
+                ]
+            }
+        }
+    };
+
+    (
+        @extract_attr
+        $(#[$enum_meta:meta])*
+        $enum_vis:vis enum $enum_name:ident {
+            [$($out:tt)*]
+            [
+                [$(#[$mout:meta])*]
+                [$($tag_field:ident)*]
+                [$($flags:ident)*]
+                [$($category:ident)*]
+                [
+                    #[key_param(deprecated)]
+                    $(#[$($mtail:tt)+])*
+                ]
+                $vname:ident$(($vtype:ty))?,
+                $($tail:tt)*
+            ]
+        }
+    ) => {
+        implement_key_parameter_value!{
+            @extract_attr
+            $(#[$enum_meta])*
+            $enum_vis enum $enum_name {
+                [$($out)*]
+                [
+                    [
+                        $(#[$mout])*
+                        #[deprecated(note = "The tag is no longer enforced by KeyMint.")]
+                    ]
+                    [$($tag_field)*]
+                    [$($flags)*]
+                    [$($category)*]
+                    [$(#[$($mtail)+])*]
+                    $vname$(($vtype))?,
+                    $($tail)*
+                ]
+            }
+        }
+    };
 
-/// This is the top level macro. While the other macros do most of the heavy lifting, this takes
-/// the key parameter list and passes it on to the other macros to generate all of the conversion
-/// functions. In addition, it generates an important test vector for verifying that tag type of the
-/// keymint tag matches the associated keymint KeyParameterValue field.
-macro_rules! implement_key_parameter_value {
     (
+        @extract_attr
         $(#[$enum_meta:meta])*
         $enum_vis:vis enum $enum_name:ident {
-            $(
-                $(#[$($emeta:tt)+])*
-                $vname:ident$(($vtype:ty))?
-            ),* $(,)?
+            [$($out:tt)*]
+            [
+                [$(#[$mout:meta])*]
+                [$($tag_field:ident)*]
+                [$($flags:ident)*]
+                []
+                [
+                    #[key_param(category = $category:ident)]
+                    $(#[$($mtail:tt)+])*
+                ]
+                $vname:ident$(($vtype:ty))?,
+                $($tail:tt)*
+            ]
         }
     ) => {
         implement_key_parameter_value!{
             @extract_attr
             $(#[$enum_meta])*
             $enum_vis enum $enum_name {
-                []
-                [$(
-                    [] [$(#[$($emeta)+])*]
+                [$($out)*]
+                [
+                    [$(#[$mout])*]
+                    [$($tag_field)*]
+                    [$($flags)*]
+                    [$category]
+                    [$(#[$($mtail)+])*]
                     $vname$(($vtype))?,
-                )*]
+                    $($tail)*
+                ]
             }
         }
     };
@@ -693,8 +2365,11 @@ macro_rules! implement_key_parameter_value {
             [$($out:tt)*]
             [
                 [$(#[$mout:meta])*]
+                [$($tag_field:ident)*]
+                [$($flags:ident)*]
+                [$($category:ident)*]
                 [
-                    #[key_param(tag = $tag_name:ident, field = $field_name:ident)]
+                    #[key_param($flag:ident)]
                     $(#[$($mtail:tt)+])*
                 ]
                 $vname:ident$(($vtype:ty))?,
@@ -706,13 +2381,16 @@ macro_rules! implement_key_parameter_value {
             @extract_attr
             $(#[$enum_meta])*
             $enum_vis enum $enum_name {
+                [$($out)*]
                 [
-                    $($out)*
-                    $(#[$mout])*
-                    $(#[$($mtail)+])*
-                    $tag_name $field_name $vname$(($vtype))?,
+                    [$(#[$mout])*]
+                    [$($tag_field)*]
+                    [$($flags)* $flag]
+                    [$($category)*]
+                    [$(#[$($mtail)+])*]
+                    $vname$(($vtype))?,
+                    $($tail)*
                 ]
-                [$($tail)*]
             }
         }
     };
@@ -724,6 +2402,9 @@ macro_rules! implement_key_parameter_value {
             [$($out:tt)*]
             [
                 [$(#[$mout:meta])*]
+                [$($tag_field:ident)*]
+                [$($flags:ident)*]
+                [$($category:ident)*]
                 [
                     #[$front:meta]
                     $(#[$($mtail:tt)+])*
@@ -743,6 +2424,9 @@ macro_rules! implement_key_parameter_value {
                         $(#[$mout])*
                         #[$front]
                     ]
+                    [$($tag_field)*]
+                    [$($flags)*]
+                    [$($category)*]
                     [$(#[$($mtail)+])*]
                     $vname$(($vtype))?,
                     $($tail)*
@@ -751,6 +2435,36 @@ macro_rules! implement_key_parameter_value {
         }
     };
 
+    (
+        @extract_attr
+        $(#[$enum_meta:meta])*
+        $enum_vis:vis enum $enum_name:ident {
+            [$($out:tt)*]
+            [
+                [$(#[$mout:meta])*]
+                [$tag_name:ident $field_name:ident]
+                [$($flags:ident)*]
+                [$($category:ident)*]
+                []
+                $vname:ident$(($vtype:ty))?,
+                $($tail:tt)*
+            ]
+        }
+    ) => {
+        implement_key_parameter_value!{
+            @extract_attr
+            $(#[$enum_meta])*
+            $enum_vis enum $enum_name {
+                [
+                    $($out)*
+                    $(#[$mout])*
+                    $tag_name $field_name [$($flags)*] [$($category)*] $vname$(($vtype))?,
+                ]
+                [$($tail)*]
+            }
+        }
+    };
+
     (
         @extract_attr
         $(#[$enum_meta:meta])*
@@ -774,7 +2488,8 @@ macro_rules! implement_key_parameter_value {
         $enum_vis:vis enum $enum_name:ident {
             $(
                 $(#[$emeta:meta])*
-                $tag_name:ident $field_name:ident $vname:ident$(($vtype:ty))?,
+                $tag_name:ident $field_name:ident [$($flags:ident)*] [$($category:ident)*]
+                $vname:ident$(($vtype:ty))?,
             )*
         }
     ) => {
@@ -787,10 +2502,75 @@ macro_rules! implement_key_parameter_value {
             ),*
         });
 
+        #[allow(deprecated)]
         impl $enum_name {
             implement_new_from_sql!($enum_name; $($vname$(($vtype))? $tag_name),*);
             implement_get_tag!($enum_name; $($vname$(($vtype))? $tag_name),*);
             implement_from_tag_primitive_pair!($enum_name; $($vname$(($vtype))? $tag_name),*);
+            implement_get_primitive!($enum_name; $($vname$(($vtype))?),*);
+            implement_field_accessors!($enum_name; $($vname$(($vtype))? $field_name),*);
+            implement_bool_flag_bit!($enum_name; $($vname$(($vtype))? $field_name),*);
+            implement_validate_enum_ranges!($enum_name; $($vname$(($vtype))?),*);
+
+            /// Returns true if the variant carries secret or per-use material that must not end up
+            /// in logs. The Display representation of such variants replaces the value with
+            /// `<redacted>`.
+            pub fn is_sensitive(&self) -> bool {
+                match self {
+                    $($enum_name::$vname { .. } => key_param_has_flag!(sensitive, [$($flags)*]),)*
+                    $enum_name::Unknown { .. } => false,
+                }
+            }
+
+            /// Returns false if the variant must not be stored in the database, i.e., if it is
+            /// marked with `#[key_param(not_stored)]`. Unknown values are storable.
+            pub fn is_storable(&self) -> bool {
+                match self {
+                    $($enum_name::$vname { .. } => {
+                        !key_param_has_flag!(not_stored, [$($flags)*])
+                    })*
+                    $enum_name::Unknown { .. } => true,
+                }
+            }
+
+            /// Returns true if the tag may occur more than once in a key's characteristics,
+            /// e.g., PURPOSE or DIGEST. Returns false for singleton tags and for tags unknown to
+            /// Keystore.
+            pub fn is_repeated_allowed(tag: Tag) -> bool {
+                match tag {
+                    $(Tag::$tag_name => key_param_has_flag!(repeatable, [$($flags)*]),)*
+                    _ => false,
+                }
+            }
+
+            /// Returns true if the value of the tag legitimately differs between snapshots of
+            /// the characteristics of the same key, e.g., CREATION_DATETIME. Returns false for
+            /// tags unknown to Keystore.
+            pub fn is_volatile(tag: Tag) -> bool {
+                match tag {
+                    $(Tag::$tag_name => key_param_has_flag!(volatile, [$($flags)*]),)*
+                    _ => false,
+                }
+            }
+
+            /// Returns false if the tag never appears in key characteristics, i.e., for
+            /// operation parameters such as NONCE, for parameters only consumed during key
+            /// generation or attestation, and for INVALID. Returns true for tags unknown to
+            /// Keystore.
+            pub fn is_characteristic(tag: Tag) -> bool {
+                match tag {
+                    $(Tag::$tag_name => !key_param_has_flag!(not_characteristic, [$($flags)*]),)*
+                    _ => true,
+                }
+            }
+
+            /// Returns the name of the variant.
+            fn variant_name(&self) -> &'static str {
+                match self {
+                    $($enum_name::$vname { .. } => stringify!($vname),)*
+                    $enum_name::Unknown { .. } => "Unknown",
+                }
+            }
 
             #[cfg(test)]
             fn make_field_matches_tag_type_test_vector() -> Vec<KmKeyParameter> {
@@ -814,250 +2594,1090 @@ macro_rules! implement_key_parameter_value {
             $($vname$(($vtype))? $tag_name $field_name),*
         );
 
+        $(implement_try_from_key_parameter_value!(
+            $enum_name; $vname$(($vtype))? $tag_name $field_name [$($flags)*]
+        );)*
+
+        /// Returns the field of keymint::KeyParameterValue that carries the value of the given
+        /// tag or None if the tag is unknown.
+        pub fn field_type_of(tag: Tag) -> Option<FieldType> {
+            match tag {
+                $(Tag::$tag_name => Some(FieldType::$field_name),)*
+                _ => None,
+            }
+        }
+
+        /// Returns the category of the given tag, see `TagCategory`. Tags without a
+        /// `#[key_param(category = ...)]` marker and tags unknown to Keystore are in
+        /// TagCategory::Other.
+        pub fn tag_category(tag: Tag) -> TagCategory {
+            match tag {
+                $(Tag::$tag_name => key_param_category!($($category)*),)*
+                _ => TagCategory::Other,
+            }
+        }
+
+        /// Returns a description of every tag known to KeyParameterValue in the order in which
+        /// the variants are declared.
+        pub fn all_known_tags() -> impl Iterator<Item = TagInfo> {
+            [$(TagInfo {
+                tag: Tag::$tag_name,
+                name: stringify!($tag_name),
+                variant: stringify!($vname),
+                field_type: FieldType::$field_name,
+                has_value: key_param_has_value!($($vtype)?),
+            }),*]
+            .into_iter()
+        }
+
         implement_to_sql!($enum_name; $($vname$(($vtype))?),*);
+
+        implement_display!($enum_name; $($vname$(($vtype))? $tag_name $field_name),*);
+
+        implement_value_ref!($enum_name; $($vname$(($vtype))?),*);
+
+        /// Picks one of the known variants and fills it with a random value. Enum values are
+        /// always defined by the AIDL spec and blobs are at most MAX_ARBITRARY_BLOB_LEN bytes
+        /// long. The Unknown variant is never generated.
+        #[cfg(feature = "arbitrary")]
+        #[allow(deprecated)]
+        impl<'a> arbitrary::Arbitrary<'a> for $enum_name {
+            fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+                type Generator =
+                    fn(&mut arbitrary::Unstructured<'_>) -> arbitrary::Result<$enum_name>;
+                const GENERATORS: &[Generator] = &[$(
+                    |_u| Ok($enum_name::$vname$((
+                        <$vtype as ArbitraryField>::arbitrary_field(_u)?
+                    ))?),
+                )*];
+                u.choose(GENERATORS)?(u)
+            }
+        }
     };
 }
 
 implement_key_parameter_value! {
 /// KeyParameterValue holds a value corresponding to one of the Tags defined in
 /// the AIDL spec at hardware/interfaces/security/keymint
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Deserialize, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum KeyParameterValue {
-    /// Associated with Tag:INVALID
+    /// Associated with Tag:INVALID. The wrapped integer is the payload of the Invalid field
+    /// of keymint::KeyParameterValue, which is preserved for round trips.
     #[key_param(tag = INVALID, field = Invalid)]
-    Invalid,
+    #[key_param(not_characteristic)]
+    Invalid(i32),
     /// Set of purposes for which the key may be used
-    #[serde(deserialize_with = "deserialize_primitive")]
-    #[serde(serialize_with = "serialize_primitive")]
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_primitive"))]
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_primitive"))]
     #[key_param(tag = PURPOSE, field = KeyPurpose)]
+    #[key_param(category = CryptoParams)]
+    #[key_param(repeatable)]
     KeyPurpose(KeyPurpose),
     /// Cryptographic algorithm with which the key is used
-    #[serde(deserialize_with = "deserialize_primitive")]
-    #[serde(serialize_with = "serialize_primitive")]
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_primitive"))]
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_primitive"))]
     #[key_param(tag = ALGORITHM, field = Algorithm)]
+    #[key_param(category = CryptoParams)]
     Algorithm(Algorithm),
     /// Size of the key , in bits
     #[key_param(tag = KEY_SIZE, field = Integer)]
+    #[key_param(category = CryptoParams)]
     KeySize(i32),
     /// Block cipher mode(s) with which the key may be used
-    #[serde(deserialize_with = "deserialize_primitive")]
-    #[serde(serialize_with = "serialize_primitive")]
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_primitive"))]
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_primitive"))]
     #[key_param(tag = BLOCK_MODE, field = BlockMode)]
+    #[key_param(category = CryptoParams)]
+    #[key_param(repeatable)]
     BlockMode(BlockMode),
     /// Digest algorithms that may be used with the key to perform signing and verification
-    #[serde(deserialize_with = "deserialize_primitive")]
-    #[serde(serialize_with = "serialize_primitive")]
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_primitive"))]
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_primitive"))]
     #[key_param(tag = DIGEST, field = Digest)]
+    #[key_param(category = CryptoParams)]
+    #[key_param(repeatable)]
     Digest(Digest),
     /// Digest algorithms that can be used for MGF in RSA-OAEP.
-    #[serde(deserialize_with = "deserialize_primitive")]
-    #[serde(serialize_with = "serialize_primitive")]
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_primitive"))]
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_primitive"))]
     #[key_param(tag = RSA_OAEP_MGF_DIGEST, field = Digest)]
+    #[key_param(category = CryptoParams)]
+    #[key_param(repeatable)]
+    #[key_param(shared_type)]
     RsaOaepMgfDigest(Digest),
     /// Padding modes that may be used with the key.  Relevant to RSA, AES and 3DES keys.
-    #[serde(deserialize_with = "deserialize_primitive")]
-    #[serde(serialize_with = "serialize_primitive")]
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_primitive"))]
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_primitive"))]
     #[key_param(tag = PADDING, field = PaddingMode)]
+    #[key_param(category = CryptoParams)]
+    #[key_param(repeatable)]
     PaddingMode(PaddingMode),
     /// Can the caller provide a nonce for nonce-requiring operations
     #[key_param(tag = CALLER_NONCE, field = BoolValue)]
+    #[key_param(category = CryptoParams)]
     CallerNonce,
     /// Minimum length of MAC for HMAC keys and AES keys that support GCM mode
     #[key_param(tag = MIN_MAC_LENGTH, field = Integer)]
+    #[key_param(category = CryptoParams)]
     MinMacLength(i32),
     /// The elliptic curve
-    #[serde(deserialize_with = "deserialize_primitive")]
-    #[serde(serialize_with = "serialize_primitive")]
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_primitive"))]
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_primitive"))]
     #[key_param(tag = EC_CURVE, field = EcCurve)]
+    #[key_param(category = CryptoParams)]
     EcCurve(EcCurve),
     /// Value of the public exponent for an RSA key pair
     #[key_param(tag = RSA_PUBLIC_EXPONENT, field = LongInteger)]
+    #[key_param(category = CryptoParams)]
     RSAPublicExponent(i64),
     /// An attestation certificate for the generated key should contain an application-scoped
     /// and time-bounded device-unique ID
     #[key_param(tag = INCLUDE_UNIQUE_ID, field = BoolValue)]
+    #[key_param(category = Attestation)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
     IncludeUniqueID,
     //TODO: find out about this
     // /// Necessary system environment conditions for the generated key to be used
     // KeyBlobUsageRequirements(KeyBlobUsageRequirements),
     /// Only the boot loader can use the key
     #[key_param(tag = BOOTLOADER_ONLY, field = BoolValue)]
+    #[key_param(category = AuthAndAccess)]
     BootLoaderOnly,
     /// When deleted, the key is guaranteed to be permanently deleted and unusable
     #[key_param(tag = ROLLBACK_RESISTANCE, field = BoolValue)]
+    #[key_param(category = Lifecycle)]
     RollbackResistance,
     /// The Key shall only be used during the early boot stage
     #[key_param(tag = EARLY_BOOT_ONLY, field = BoolValue)]
+    #[key_param(category = AuthAndAccess)]
     EarlyBootOnly,
     /// The date and time at which the key becomes active
     #[key_param(tag = ACTIVE_DATETIME, field = DateTime)]
+    #[key_param(category = Lifecycle)]
     ActiveDateTime(i64),
     /// The date and time at which the key expires for signing and encryption
     #[key_param(tag = ORIGINATION_EXPIRE_DATETIME, field = DateTime)]
+    #[key_param(category = Lifecycle)]
     OriginationExpireDateTime(i64),
     /// The date and time at which the key expires for verification and decryption
     #[key_param(tag = USAGE_EXPIRE_DATETIME, field = DateTime)]
+    #[key_param(category = Lifecycle)]
     UsageExpireDateTime(i64),
     /// Minimum amount of time that elapses between allowed operations
     #[key_param(tag = MIN_SECONDS_BETWEEN_OPS, field = Integer)]
+    #[key_param(category = AuthAndAccess)]
     MinSecondsBetweenOps(i32),
     /// Maximum number of times that a key may be used between system reboots
     #[key_param(tag = MAX_USES_PER_BOOT, field = Integer)]
+    #[key_param(category = AuthAndAccess)]
     MaxUsesPerBoot(i32),
     /// The number of times that a limited use key can be used
     #[key_param(tag = USAGE_COUNT_LIMIT, field = Integer)]
+    #[key_param(category = Lifecycle)]
     UsageCountLimit(i32),
     /// ID of the Android user that is permitted to use the key
     #[key_param(tag = USER_ID, field = Integer)]
+    #[key_param(category = AuthAndAccess)]
     UserID(i32),
     /// A key may only be used under a particular secure user authentication state
     #[key_param(tag = USER_SECURE_ID, field = LongInteger)]
+    #[key_param(category = AuthAndAccess)]
+    #[key_param(repeatable)]
     UserSecureID(i64),
     /// No authentication is required to use this key
     #[key_param(tag = NO_AUTH_REQUIRED, field = BoolValue)]
+    #[key_param(category = AuthAndAccess)]
     NoAuthRequired,
     /// The types of user authenticators that may be used to authorize this key
-    #[serde(deserialize_with = "deserialize_primitive")]
-    #[serde(serialize_with = "serialize_primitive")]
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_primitive"))]
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_primitive"))]
     #[key_param(tag = USER_AUTH_TYPE, field = HardwareAuthenticatorType)]
+    #[key_param(category = AuthAndAccess)]
     HardwareAuthenticatorType(HardwareAuthenticatorType),
     /// The time in seconds for which the key is authorized for use, after user authentication
     #[key_param(tag = AUTH_TIMEOUT, field = Integer)]
+    #[key_param(category = AuthAndAccess)]
     AuthTimeout(i32),
     /// The key's authentication timeout, if it has one, is automatically expired when the device is
     /// removed from the user's body. No longer implemented; this tag is no longer enforced.
     #[key_param(tag = ALLOW_WHILE_ON_BODY, field = BoolValue)]
+    #[key_param(category = AuthAndAccess)]
+    #[key_param(deprecated)]
     AllowWhileOnBody,
     /// The key must be unusable except when the user has provided proof of physical presence
     #[key_param(tag = TRUSTED_USER_PRESENCE_REQUIRED, field = BoolValue)]
+    #[key_param(category = AuthAndAccess)]
     TrustedUserPresenceRequired,
     /// Applicable to keys with KeyPurpose SIGN, and specifies that this key must not be usable
     /// unless the user provides confirmation of the data to be signed
     #[key_param(tag = TRUSTED_CONFIRMATION_REQUIRED, field = BoolValue)]
+    #[key_param(category = AuthAndAccess)]
     TrustedConfirmationRequired,
     /// The key may only be used when the device is unlocked
     #[key_param(tag = UNLOCKED_DEVICE_REQUIRED, field = BoolValue)]
+    #[key_param(category = AuthAndAccess)]
     UnlockedDeviceRequired,
     /// When provided to generateKey or importKey, this tag specifies data
     /// that is necessary during all uses of the key
     #[key_param(tag = APPLICATION_ID, field = Blob)]
+    #[key_param(category = AuthAndAccess)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
     ApplicationID(Vec<u8>),
     /// When provided to generateKey or importKey, this tag specifies data
     /// that is necessary during all uses of the key
     #[key_param(tag = APPLICATION_DATA, field = Blob)]
+    #[key_param(category = AuthAndAccess)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
     ApplicationData(Vec<u8>),
     /// Specifies the date and time the key was created
     #[key_param(tag = CREATION_DATETIME, field = DateTime)]
+    #[key_param(category = Lifecycle)]
+    #[key_param(volatile)]
     CreationDateTime(i64),
     /// Specifies where the key was created, if known
-    #[serde(deserialize_with = "deserialize_primitive")]
-    #[serde(serialize_with = "serialize_primitive")]
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_primitive"))]
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_primitive"))]
     #[key_param(tag = ORIGIN, field = Origin)]
+    #[key_param(category = Lifecycle)]
     KeyOrigin(KeyOrigin),
     /// The key used by verified boot to validate the operating system booted
     #[key_param(tag = ROOT_OF_TRUST, field = Blob)]
+    #[key_param(category = DeviceInfo)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     RootOfTrust(Vec<u8>),
     /// System OS version with which the key may be used
     #[key_param(tag = OS_VERSION, field = Integer)]
+    #[key_param(category = DeviceInfo)]
     OSVersion(i32),
     /// Specifies the system security patch level with which the key may be used
     #[key_param(tag = OS_PATCHLEVEL, field = Integer)]
+    #[key_param(category = DeviceInfo)]
     OSPatchLevel(i32),
     /// Specifies a unique, time-based identifier
     #[key_param(tag = UNIQUE_ID, field = Blob)]
+    #[key_param(category = Attestation)]
+    #[key_param(sensitive)]
+    #[key_param(volatile)]
+    #[key_param(not_characteristic)]
     UniqueID(Vec<u8>),
     /// Used to deliver a "challenge" value to the attestKey() method
     #[key_param(tag = ATTESTATION_CHALLENGE, field = Blob)]
+    #[key_param(category = Attestation)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     AttestationChallenge(Vec<u8>),
     /// The set of applications which may use a key, used only with attestKey()
     #[key_param(tag = ATTESTATION_APPLICATION_ID, field = Blob)]
+    #[key_param(category = Attestation)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     AttestationApplicationID(Vec<u8>),
     /// Provides the device's brand name, to attestKey()
     #[key_param(tag = ATTESTATION_ID_BRAND, field = Blob)]
+    #[key_param(category = Attestation)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     AttestationIdBrand(Vec<u8>),
     /// Provides the device's device name, to attestKey()
     #[key_param(tag = ATTESTATION_ID_DEVICE, field = Blob)]
+    #[key_param(category = Attestation)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     AttestationIdDevice(Vec<u8>),
     /// Provides the device's product name, to attestKey()
     #[key_param(tag = ATTESTATION_ID_PRODUCT, field = Blob)]
+    #[key_param(category = Attestation)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     AttestationIdProduct(Vec<u8>),
     /// Provides the device's serial number, to attestKey()
     #[key_param(tag = ATTESTATION_ID_SERIAL, field = Blob)]
+    #[key_param(category = Attestation)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     AttestationIdSerial(Vec<u8>),
     /// Provides the primary IMEI for the device, to attestKey()
     #[key_param(tag = ATTESTATION_ID_IMEI, field = Blob)]
+    #[key_param(category = Attestation)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     AttestationIdIMEI(Vec<u8>),
     /// Provides a second IMEI for the device, to attestKey()
     #[key_param(tag = ATTESTATION_ID_SECOND_IMEI, field = Blob)]
+    #[key_param(category = Attestation)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     AttestationIdSecondIMEI(Vec<u8>),
     /// Provides the MEIDs for all radios on the device, to attestKey()
     #[key_param(tag = ATTESTATION_ID_MEID, field = Blob)]
+    #[key_param(category = Attestation)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     AttestationIdMEID(Vec<u8>),
     /// Provides the device's manufacturer name, to attestKey()
     #[key_param(tag = ATTESTATION_ID_MANUFACTURER, field = Blob)]
+    #[key_param(category = Attestation)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     AttestationIdManufacturer(Vec<u8>),
     /// Provides the device's model name, to attestKey()
     #[key_param(tag = ATTESTATION_ID_MODEL, field = Blob)]
+    #[key_param(category = Attestation)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     AttestationIdModel(Vec<u8>),
     /// Specifies the vendor image security patch level with which the key may be used
     #[key_param(tag = VENDOR_PATCHLEVEL, field = Integer)]
+    #[key_param(category = DeviceInfo)]
     VendorPatchLevel(i32),
     /// Specifies the boot image (kernel) security patch level with which the key may be used
     #[key_param(tag = BOOT_PATCHLEVEL, field = Integer)]
+    #[key_param(category = DeviceInfo)]
     BootPatchLevel(i32),
     /// Provides "associated data" for AES-GCM encryption or decryption
     #[key_param(tag = ASSOCIATED_DATA, field = Blob)]
+    #[key_param(category = CryptoParams)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     AssociatedData(Vec<u8>),
     /// Provides or returns a nonce or Initialization Vector (IV) for AES-GCM,
     /// AES-CBC, AES-CTR, or 3DES-CBC encryption or decryption
     #[key_param(tag = NONCE, field = Blob)]
+    #[key_param(category = CryptoParams)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     Nonce(Vec<u8>),
     /// Provides the requested length of a MAC or GCM authentication tag, in bits
     #[key_param(tag = MAC_LENGTH, field = Integer)]
+    #[key_param(category = CryptoParams)]
+    #[key_param(not_characteristic)]
     MacLength(i32),
     /// Specifies whether the device has been factory reset since the
     /// last unique ID rotation.  Used for key attestation
     #[key_param(tag = RESET_SINCE_ID_ROTATION, field = BoolValue)]
+    #[key_param(category = Attestation)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     ResetSinceIdRotation,
     /// Used to deliver a cryptographic token proving that the user
     /// confirmed a signing request
     #[key_param(tag = CONFIRMATION_TOKEN, field = Blob)]
+    #[key_param(category = AuthAndAccess)]
+    #[key_param(sensitive)]
+    #[key_param(not_characteristic)]
+    #[key_param(not_stored)]
     ConfirmationToken(Vec<u8>),
     /// Used to deliver the certificate serial number to the KeyMint instance
     /// certificate generation.
     #[key_param(tag = CERTIFICATE_SERIAL, field = Blob)]
+    #[key_param(category = Attestation)]
+    #[key_param(not_characteristic)]
     CertificateSerial(Vec<u8>),
     /// Used to deliver the certificate subject to the KeyMint instance
     /// certificate generation. This must be DER encoded X509 name.
     #[key_param(tag = CERTIFICATE_SUBJECT, field = Blob)]
+    #[key_param(category = Attestation)]
+    #[key_param(not_characteristic)]
     CertificateSubject(Vec<u8>),
     /// Used to deliver the not before date in milliseconds to KeyMint during key generation/import.
     #[key_param(tag = CERTIFICATE_NOT_BEFORE, field = DateTime)]
+    #[key_param(category = Attestation)]
+    #[key_param(not_characteristic)]
     CertificateNotBefore(i64),
     /// Used to deliver the not after date in milliseconds to KeyMint during key generation/import.
     #[key_param(tag = CERTIFICATE_NOT_AFTER, field = DateTime)]
+    #[key_param(category = Attestation)]
+    #[key_param(not_characteristic)]
     CertificateNotAfter(i64),
     /// Specifies a maximum boot level at which a key should function
     #[key_param(tag = MAX_BOOT_LEVEL, field = Integer)]
+    #[key_param(category = AuthAndAccess)]
     MaxBootLevel(i32),
 }
 }
 
-impl From<&KmKeyParameter> for KeyParameterValue {
-    fn from(kp: &KmKeyParameter) -> Self {
-        kp.clone().into()
+impl From<KeyParameterValueRef<'_>> for KmKeyParameter {
+    /// Converts the borrowed value into a KeyMint parameter. Blob payloads are copied exactly
+    /// once.
+    fn from(v: KeyParameterValueRef<'_>) -> Self {
+        KeyParameterValue::from(v).into()
+    }
+}
+
+impl KeyParameterValue {
+    /// Converts all KeyMint parameters as by `from_km_parameter_lossy`. The parameters are
+    /// consumed, so that blob payloads are moved rather than copied.
+    pub fn from_km_params_lossy(params: Vec<KmKeyParameter>) -> Vec<Self> {
+        params.into_iter().map(Self::from_km_parameter_lossy).collect()
+    }
+
+    /// Fallible counterpart of `from_km_parameter_lossy`. Unlike the lossy conversion,
+    /// this returns an error if the tag is unknown, if the union field does not match
+    /// the field of the tag as given by `field_type_of`, or if the value is not defined for
+    /// the enum of the tag.
+    pub fn try_from_km_parameter(kp: KmKeyParameter) -> Result<Self, KeyParameterConversionError> {
+        let tag = kp.tag;
+        match field_type_of(tag) {
+            None => return Err(KeyParameterConversionError::UnknownTag { tag }),
+            Some(field) if field != FieldType::of_km_value(&kp.value) => {
+                return Err(KeyParameterConversionError::FieldTypeMismatch { tag });
+            }
+            Some(_) => {}
+        }
+        let value = Self::from_km_parameter_lossy(kp);
+        match value.validate_enum_ranges() {
+            Ok(()) => Ok(value),
+            Err(EnumRangeError::UndefinedValue { tag, value }) => {
+                Err(KeyParameterConversionError::InvalidValue { tag, value })
+            }
+        }
+    }
+
+    /// Converts the KeyMint value `v` of the given tag, e.g., of an entry of a KeyMint
+    /// response, as by `try_from_km_parameter`. This fails with
+    /// KeyParameterError::UnsupportedTag if the tag is unknown to Keystore, and with
+    /// KeyParameterError::TypeMismatch if the union field does not match the field of the tag.
+    /// Enum values that are not defined by KeyMint are rejected with
+    /// KeyParameterConversionError::InvalidValue.
+    pub fn from_km_key_parameter_value_with_tag(tag: Tag, v: KmKeyParameterValue) -> Result<Self> {
+        Self::try_from_km_parameter(KmKeyParameter { tag, value: v })
+            .map_err(|e| match e {
+                KeyParameterConversionError::UnknownTag { tag } => {
+                    KeyParameterError::UnsupportedTag(tag).into()
+                }
+                KeyParameterConversionError::FieldTypeMismatch { tag } => {
+                    KeyParameterError::TypeMismatch(tag).into()
+                }
+                e => anyhow::Error::new(e),
+            })
+            .with_context(|| format!("In from_km_key_parameter_value_with_tag: Tag {:?}.", tag))
+    }
+
+    /// Like `try_from_km_parameter`, but additionally rejects blobs that are longer than the
+    /// limit configured for their tag in `limits` with
+    /// KeyParameterConversionError::BlobTooLarge. This keeps pathological values returned by
+    /// a misbehaving KeyMint out of the database.
+    pub fn from_km_param_checked(
+        kp: KmKeyParameter,
+        limits: &BlobLimits,
+    ) -> Result<Self, KeyParameterConversionError> {
+        if let KmKeyParameterValue::Blob(blob) = &kp.value {
+            limits.check(kp.tag, blob)?;
+        }
+        Self::try_from_km_parameter(kp)
+    }
+
+    /// Converts all values into KeyMint parameters, moving blob payloads.
+    pub fn into_km_params(values: Vec<Self>) -> Vec<KmKeyParameter> {
+        values.into_iter().map(KmKeyParameter::from).collect()
+    }
+}
+
+impl KeyParameterValue {
+    /// Like `new_from_tag_primitive_pair`, but an I32 is widened to I64 if the tag's value is
+    /// carried by the LongInteger or DateTime field, e.g., for USER_SECURE_ID or
+    /// RSA_PUBLIC_EXPONENT given as i32 by a protobuf or JSON source. All other combinations
+    /// of tag and primitive are handled as by `new_from_tag_primitive_pair`.
+    pub fn new_from_tag_primitive_pair_lenient(
+        tag: Tag,
+        v: Primitive,
+    ) -> Result<Self, KeyParameterError> {
+        let v = match (field_type_of(tag), v) {
+            (Some(FieldType::LongInteger | FieldType::DateTime), Primitive::I32(v)) => {
+                Primitive::I64(v.into())
+            }
+            (_, v) => v,
+        };
+        Self::new_from_tag_primitive_pair(tag, v)
+    }
+
+    /// Like `new_from_tag_primitive_pair`, but does not check the size of blobs, boolean
+    /// parameters take no primitive, Invalid
+    /// may omit its payload, which then defaults to 0, and tags unknown to Keystore yield the
+    /// Unknown variant.
+    fn new_from_tag_optional_primitive(
+        tag: Tag,
+        primitive: Option<Primitive>,
+    ) -> Result<Self, PrimitiveError> {
+        // Variants that do not wrap a value ignore the primitive passed to
+        // new_from_tag_primitive_pair.
+        let result = match (tag_type_of(tag), primitive.clone()) {
+            (TagType::BOOL, None) => {
+                Self::new_from_tag_primitive_pair_unchecked(tag, Primitive::I32(0))
+            }
+            (TagType::BOOL, Some(p)) => Err(PrimitiveError::type_mismatch(tag, Some(&p))),
+            (_, Some(p)) => Self::new_from_tag_primitive_pair_unchecked(tag, p),
+            (_, None) if tag == Tag::INVALID => Ok(Self::Invalid(0)),
+            (_, None) => Err(PrimitiveError::type_mismatch(tag, None)),
+        };
+        match result {
+            Err(PrimitiveError::UnknownTag) => {
+                let mismatch = PrimitiveError::type_mismatch(tag, primitive.as_ref());
+                km_value_from_primitive(tag, primitive)
+                    .map(|value| Self::Unknown { tag, value })
+                    .ok_or(mismatch)
+            }
+            r => r,
+        }
+    }
+
+    /// Reads the value of a parameter with a tag unknown to Keystore from the database cell.
+    /// The expected primitive is derived from the type encoded in the tag. Parameters whose
+    /// tag type is invalid cannot be interpreted and are read as Invalid.
+    #[cfg(feature = "sql")]
+    fn new_unknown_from_sql(tag: Tag, data: &SqlField) -> Result<Self, KeyParameterError> {
+        let primitive = match tag_type_of(tag) {
+            TagType::ENUM | TagType::ENUM_REP | TagType::UINT | TagType::UINT_REP => {
+                data.get().map(|v: i32| Some(v.into()))
+            }
+            TagType::ULONG | TagType::ULONG_REP | TagType::DATE => {
+                data.get().map(|v: i64| Some(v.into()))
+            }
+            TagType::BIGNUM | TagType::BYTES => data.get().map(|v: Vec<u8>| Some(v.into())),
+            TagType::BOOL => Ok(None),
+            _ => return Ok(Self::Invalid(0)),
+        }
+        .map_err(|_| KeyParameterError::SqlCorrupted(tag))?;
+        Ok(km_value_from_primitive(tag, primitive)
+            .map_or(Self::Invalid(0), |value| Self::Unknown { tag, value }))
+    }
+}
+
+/// The default is `Invalid(0)`, matching the default of keymint::KeyParameterValue.
+impl Default for KeyParameterValue {
+    fn default() -> Self {
+        Self::Invalid(0)
+    }
+}
+
+/// Combines the numeric value of the tag with the hash of the wrapped value. Values that are
+/// equal always have the same tag and primitive, so this is consistent with `Eq`.
+impl Hash for KeyParameterValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get_tag().0.hash(state);
+        // Blobs are hashed in place to avoid copying them in get_primitive.
+        match self.blob_value() {
+            Some(blob) => blob.hash(state),
+            None => self.get_primitive().hash(state),
+        }
+    }
+}
+
+/// Orders values by the numeric value of their tag and then by the wrapped value, i.e., blobs
+/// lexicographically and integers numerically. Unlike an ordering by variant, this does not
+/// change when variants are added to or reordered in KeyParameterValue, so sorted collections
+/// of key parameters stay stable across versions. Unknown values with the same tag and
+/// primitive are ordered by their KeyMint representation to stay consistent with `Eq`.
+impl Ord for KeyParameterValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.get_tag()
+            .0
+            .cmp(&other.get_tag().0)
+            .then_with(|| match (self.blob_value(), other.blob_value()) {
+                // Blobs are compared in place to avoid copying them in get_primitive.
+                (Some(a), Some(b)) => a.cmp(b),
+                _ => self.get_primitive().cmp(&other.get_primitive()),
+            })
+            .then_with(|| match (self, other) {
+                (Self::Unknown { value: a, .. }, Self::Unknown { value: b, .. }) => a.cmp(b),
+                _ => Ordering::Equal,
+            })
+    }
+}
+
+impl PartialOrd for KeyParameterValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl KeyParameterValue {
+    /// Returns the wrapped value if it is stored in the Integer field or is an enum value.
+    #[deprecated(note = "Use `integer_value()`, or `i32::try_from(&value)` for enum values.")]
+    pub fn as_i32(&self) -> Option<i32> {
+        i32::try_from(self).ok()
+    }
+
+    /// Returns the wrapped value if it is stored in the LongInteger or DateTime field.
+    #[deprecated(note = "Use `long_integer_value()`, or `i64::try_from(&value)` for dates.")]
+    pub fn as_i64(&self) -> Option<i64> {
+        i64::try_from(self).ok()
+    }
+
+    /// Returns the wrapped bytes if the value is stored in the Blob field.
+    #[deprecated(note = "Use `blob_value()` instead.")]
+    pub fn as_blob(&self) -> Option<&[u8]> {
+        self.blob_value()
+    }
+
+    /// Returns the wrapped value of an enum parameter or of an unknown parameter with an enum
+    /// tag type.
+    fn enum_value(&self) -> Option<i32> {
+        match (self.tag_type(), self.get_primitive()) {
+            (TagType::ENUM | TagType::ENUM_REP, Some(Primitive::I32(v))) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the wrapped value of a DateTime parameter or of an unknown parameter with the
+    /// DATE tag type.
+    fn date_time_value(&self) -> Option<i64> {
+        match (self.tag_type(), self.get_primitive()) {
+            (TagType::DATE, Some(Primitive::I64(v))) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the error of the conversions from KeyParameterValue into a primitive if the
+    /// value is not of the `expected` primitive type. Invalid carries no meaningful value, so
+    /// it is reported as unknown tag.
+    fn extraction_error(&self, expected: &'static str) -> PrimitiveError {
+        if let Self::Invalid(_) = self {
+            return PrimitiveError::UnknownTag;
+        }
+        let got = match self.blob_value() {
+            Some(_) => "Vec<u8>",
+            None => self.get_primitive().as_ref().map_or("none", Primitive::type_name),
+        };
+        PrimitiveError::TypeMismatch { tag: self.get_tag(), expected, got }
+    }
+}
+
+/// Extracts the value stored in the Integer field or the enum value, see `integer_value`.
+/// Fails with PrimitiveError::UnknownTag for Invalid and with PrimitiveError::TypeMismatch for
+/// values of other fields.
+impl TryFrom<&KeyParameterValue> for i32 {
+    type Error = PrimitiveError;
+
+    fn try_from(value: &KeyParameterValue) -> Result<Self, Self::Error> {
+        value
+            .integer_value()
+            .or_else(|| value.enum_value())
+            .ok_or_else(|| value.extraction_error("i32"))
+    }
+}
+
+/// Extracts the value stored in the LongInteger or DateTime field, see `long_integer_value`.
+/// Fails as the conversion into i32.
+impl TryFrom<&KeyParameterValue> for i64 {
+    type Error = PrimitiveError;
+
+    fn try_from(value: &KeyParameterValue) -> Result<Self, Self::Error> {
+        value
+            .long_integer_value()
+            .or_else(|| value.date_time_value())
+            .ok_or_else(|| value.extraction_error("i64"))
+    }
+}
+
+/// Copies the bytes stored in the Blob field, see `blob_value`. Fails as the conversion into
+/// i32.
+impl TryFrom<&KeyParameterValue> for Vec<u8> {
+    type Error = PrimitiveError;
+
+    fn try_from(value: &KeyParameterValue) -> Result<Self, Self::Error> {
+        value.blob_value().map(<[u8]>::to_vec).ok_or_else(|| value.extraction_error("Vec<u8>"))
+    }
+}
+
+impl KeyParameterValue {
+    /// Creates a blob parameter for the given tag from a hex string, e.g., as passed on the
+    /// command line. An empty string yields an empty blob. Fails with
+    /// ResponseCode::INVALID_ARGUMENT if the tag is not stored in the Blob field or if the hex
+    /// string is malformed.
+    pub fn from_hex_string(tag: Tag, hex: &str) -> Result<Self> {
+        if field_type_of(tag) != Some(FieldType::Blob) {
+            return Err(KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT))
+                .with_context(|| format!("In from_hex_string: {:?} does not take a blob.", tag));
+        }
+        let bytes = hex::decode(hex)
+            .map_err(|_| KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT))
+            .context("In from_hex_string: Malformed hex string.")?;
+        Self::new_from_tag_primitive_pair(tag, bytes)
+            .map_err(|_| KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT))
+            .context("In from_hex_string.")
+    }
+
+    /// Returns the wrapped blob as lower case hex string, or None if the value is not stored in
+    /// the Blob field.
+    pub fn to_hex_string(&self) -> Option<String> {
+        self.blob_value().map(hex::encode)
+    }
+
+    /// Encodes a certificate serial number as expected by CertificateSerial, i.e., as the
+    /// content octets of a DER INTEGER: big-endian two's complement with leading zero bytes
+    /// stripped, and a zero byte prepended if the most significant bit is set, so that the
+    /// number stays positive. Serial numbers must be positive, so this fails with
+    /// ResponseCode::INVALID_ARGUMENT for zero and negative values.
+    pub fn encode_certificate_serial(serial: i64) -> Result<Vec<u8>> {
+        if serial <= 0 {
+            return Err(KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT)).with_context(|| {
+                format!("In encode_certificate_serial: Serial {} is not positive.", serial)
+            });
+        }
+        let bytes = serial.to_be_bytes();
+        // Keep one leading zero byte if the next byte has its most significant bit set.
+        let start = bytes
+            .iter()
+            .zip(&bytes[1..])
+            .take_while(|(b, next)| **b == 0 && **next & 0x80 == 0)
+            .count();
+        Ok(bytes[start..].to_vec())
+    }
+
+    /// Decodes a certificate serial number encoded as by `encode_certificate_serial`. Fails
+    /// with ResponseCode::VALUE_CORRUPTED if the encoding is empty, not minimal, negative,
+    /// zero, or does not fit into an i64.
+    pub fn decode_certificate_serial(der: &[u8]) -> Result<i64> {
+        let corrupted = || KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED);
+        match der {
+            [] => return Err(corrupted()).context("In decode_certificate_serial: Empty serial."),
+            [first, ..] if first & 0x80 != 0 => {
+                return Err(corrupted()).context("In decode_certificate_serial: Negative serial.")
+            }
+            [0, second, ..] if second & 0x80 == 0 => {
+                return Err(corrupted())
+                    .context("In decode_certificate_serial: Non-minimal encoding.")
+            }
+            _ => {}
+        }
+        if der.len() > 8 {
+            return Err(corrupted()).context("In decode_certificate_serial: Serial too large.");
+        }
+        match der.iter().fold(0i64, |acc, b| (acc << 8) | *b as i64) {
+            0 => Err(corrupted()).context("In decode_certificate_serial: Serial is zero."),
+            serial => Ok(serial),
+        }
+    }
+}
+
+impl KeyParameterValue {
+    /// Returns the tags that must be present for the generation of a key of the given
+    /// algorithm. Returns an empty list for algorithms unknown to Keystore.
+    pub fn required_for_algorithm(alg: Algorithm) -> Vec<Tag> {
+        match alg {
+            Algorithm::RSA => vec![Tag::KEY_SIZE, Tag::RSA_PUBLIC_EXPONENT],
+            Algorithm::EC => vec![Tag::EC_CURVE],
+            Algorithm::AES | Algorithm::TRIPLE_DES => vec![Tag::KEY_SIZE],
+            Algorithm::HMAC => vec![Tag::KEY_SIZE, Tag::DIGEST, Tag::MIN_MAC_LENGTH],
+            _ => vec![],
+        }
+    }
+}
+
+/// Converts the point in time into milliseconds since the Unix epoch as stored in the DateTime
+/// field. Fails if the result does not fit into an i64.
+fn system_time_to_millis(t: SystemTime) -> Result<i64> {
+    let millis = match t.duration_since(UNIX_EPOCH) {
+        Ok(d) => i128::try_from(d.as_millis()).ok(),
+        Err(e) => i128::try_from(e.duration().as_millis()).ok().map(|m| -m),
+    };
+    millis
+        .and_then(|m| i64::try_from(m).ok())
+        .ok_or(KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT))
+        .with_context(|| format!("In system_time_to_millis: {:?} is out of range.", t))
+}
+
+impl KeyParameterValue {
+    /// Returns the wrapped point in time if the parameter is stored in the DateTime field.
+    /// Values before the Unix epoch are supported. Returns None if the value cannot be
+    /// represented as SystemTime on this platform.
+    pub fn as_system_time(&self) -> Option<SystemTime> {
+        let millis = self.date_time_value()?;
+        let d = Duration::from_millis(millis.unsigned_abs());
+        if millis >= 0 {
+            UNIX_EPOCH.checked_add(d)
+        } else {
+            UNIX_EPOCH.checked_sub(d)
+        }
+    }
+
+    /// Creates an ActiveDateTime parameter. Fails if `t` is out of range.
+    pub fn active_date_time(t: SystemTime) -> Result<Self> {
+        Ok(Self::ActiveDateTime(system_time_to_millis(t)?))
+    }
+
+    /// Creates an OriginationExpireDateTime parameter. Fails if `t` is out of range.
+    pub fn origination_expire_date_time(t: SystemTime) -> Result<Self> {
+        Ok(Self::OriginationExpireDateTime(system_time_to_millis(t)?))
+    }
+
+    /// Creates a UsageExpireDateTime parameter. Fails if `t` is out of range.
+    pub fn usage_expire_date_time(t: SystemTime) -> Result<Self> {
+        Ok(Self::UsageExpireDateTime(system_time_to_millis(t)?))
+    }
+
+    /// Creates a CreationDateTime parameter. Fails if `t` is out of range.
+    pub fn creation_date_time(t: SystemTime) -> Result<Self> {
+        Ok(Self::CreationDateTime(system_time_to_millis(t)?))
+    }
+
+    /// Creates a CertificateNotBefore parameter. Fails if `t` is out of range.
+    pub fn certificate_not_before(t: SystemTime) -> Result<Self> {
+        Ok(Self::CertificateNotBefore(system_time_to_millis(t)?))
+    }
+
+    /// Creates a CertificateNotAfter parameter. Fails if `t` is out of range.
+    pub fn certificate_not_after(t: SystemTime) -> Result<Self> {
+        Ok(Self::CertificateNotAfter(system_time_to_millis(t)?))
+    }
+}
+
+impl KeyParameterValue {
+    /// Returns a wrapper whose Debug representation replaces the payload of sensitive
+    /// parameters, e.g., `ApplicationID(<redacted, 32 bytes>)`. Non sensitive parameters
+    /// are formatted exactly like KeyParameterValue itself.
+    pub fn redacted(&self) -> RedactedKeyParameterValue<'_> {
+        RedactedKeyParameterValue(self)
+    }
+}
+
+/// Debug formatting wrapper returned by `KeyParameterValue::redacted`.
+pub struct RedactedKeyParameterValue<'a>(&'a KeyParameterValue);
+
+impl std::fmt::Debug for RedactedKeyParameterValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if !self.0.is_sensitive() {
+            return std::fmt::Debug::fmt(self.0, f);
+        }
+        match KmKeyParameter::from(self.0.clone()).value {
+            KmKeyParameterValue::Blob(b) => {
+                write!(f, "{}(<redacted, {} bytes>)", self.0.variant_name(), b.len())
+            }
+            // Boolean parameters carry no payload.
+            KmKeyParameterValue::BoolValue(_) => f.write_str(self.0.variant_name()),
+            _ => write!(f, "{}(<redacted>)", self.0.variant_name()),
+        }
+    }
+}
+
+/// Returns the AIDL name of the given KeyMint value if it is an enum value, and None
+/// otherwise. Debug represents values without a name by their decimal value.
+#[cfg(any(feature = "json", feature = "serde"))]
+fn km_enum_value_name(value: &KmKeyParameterValue) -> Option<String> {
+    Some(match value {
+        KmKeyParameterValue::Algorithm(v) => format!("{:?}", v),
+        KmKeyParameterValue::BlockMode(v) => format!("{:?}", v),
+        KmKeyParameterValue::PaddingMode(v) => format!("{:?}", v),
+        KmKeyParameterValue::Digest(v) => format!("{:?}", v),
+        KmKeyParameterValue::EcCurve(v) => format!("{:?}", v),
+        KmKeyParameterValue::Origin(v) => format!("{:?}", v),
+        KmKeyParameterValue::KeyPurpose(v) => format!("{:?}", v),
+        KmKeyParameterValue::HardwareAuthenticatorType(v) => format!("{:?}", v),
+        KmKeyParameterValue::SecurityLevel(v) => format!("{:?}", v),
+        _ => return None,
+    })
+}
+
+/// Returns the numeric value of the enum variant whose Debug representation, i.e., its AIDL
+/// name, is `name`. Falls back to parsing `name` as a decimal number, which is how Debug
+/// represents values without a name.
+#[cfg(any(feature = "json", feature = "serde"))]
+fn value_from_name<T: std::fmt::Debug + Copy>(
+    values: &[T],
+    name: &str,
+    to_i32: impl Fn(T) -> i32,
+) -> Option<i32> {
+    values
+        .iter()
+        .find(|v| format!("{:?}", v) == name)
+        .map(|v| to_i32(*v))
+        .or_else(|| name.parse().ok())
+}
+
+/// Resolves the symbolic name of a value of the enum typed parameter `tag`. The enum type
+/// is found by converting a parameter with the given tag into its KeyMint representation.
+#[cfg(any(feature = "json", feature = "serde"))]
+fn enum_value_from_name(tag: Tag, name: &str) -> Option<i32> {
+    let template: KmKeyParameter =
+        KeyParameterValue::new_from_tag_primitive_pair(tag, 0i32).ok()?.into();
+    match template.value {
+        KmKeyParameterValue::Algorithm(_) => {
+            value_from_name(&Algorithm::enum_values(), name, |v| v.0)
+        }
+        KmKeyParameterValue::BlockMode(_) => {
+            value_from_name(&BlockMode::enum_values(), name, |v| v.0)
+        }
+        KmKeyParameterValue::PaddingMode(_) => {
+            value_from_name(&PaddingMode::enum_values(), name, |v| v.0)
+        }
+        KmKeyParameterValue::Digest(_) => value_from_name(&Digest::enum_values(), name, |v| v.0),
+        KmKeyParameterValue::EcCurve(_) => value_from_name(&EcCurve::enum_values(), name, |v| v.0),
+        KmKeyParameterValue::Origin(_) => value_from_name(&KeyOrigin::enum_values(), name, |v| v.0),
+        KmKeyParameterValue::KeyPurpose(_) => {
+            value_from_name(&KeyPurpose::enum_values(), name, |v| v.0)
+        }
+        KmKeyParameterValue::HardwareAuthenticatorType(_) => {
+            value_from_name(&HardwareAuthenticatorType::enum_values(), name, |v| v.0)
+        }
+        KmKeyParameterValue::SecurityLevel(_) => {
+            value_from_name(&SecurityLevel::enum_values(), name, |v| v.0)
+        }
+        _ => None,
+    }
+}
+
+/// Serde wrapper that selects the symbolic representation of a KeyParameterValue. The derived
+/// serde implementation of KeyParameterValue encodes enum values by their AIDL discriminant,
+/// e.g., `{"Algorithm":3}`. This wrapper instead encodes the tag and enum values by their AIDL
+/// names, e.g., `{"tag":"ALGORITHM","value":{"Name":"EC"}}`, so that long lived exports
+/// remain readable if discriminants are ever renumbered. All other values are encoded like
+/// `Primitive`, and boolean parameters have no value. Values without a name, e.g., unknown
+/// tags, are given as decimal strings.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolicKeyParameterValue(pub KeyParameterValue);
+
+#[cfg(feature = "serde")]
+impl From<KeyParameterValue> for SymbolicKeyParameterValue {
+    fn from(value: KeyParameterValue) -> Self {
+        Self(value)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<SymbolicKeyParameterValue> for KeyParameterValue {
+    fn from(value: SymbolicKeyParameterValue) -> Self {
+        value.0
+    }
+}
+
+/// Serialized form of SymbolicKeyParameterValue. Blobs are borrowed when serializing.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "SymbolicKeyParameterValue")]
+struct SymbolicRepr<'a> {
+    tag: String,
+    value: Option<SymbolicPrimitive<'a>>,
+}
+
+/// Like Primitive, but with an additional variant for enum values given by name.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+enum SymbolicPrimitive<'a> {
+    Name(String),
+    I64(i64),
+    I32(i32),
+    Vec(std::borrow::Cow<'a, [u8]>),
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for SymbolicKeyParameterValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let value = match self.0.blob_value() {
+            Some(blob) => Some(SymbolicPrimitive::Vec(blob.into())),
+            None => {
+                let km_value = KmKeyParameter::from(self.0.clone()).value;
+                match km_enum_value_name(&km_value) {
+                    Some(name) => Some(SymbolicPrimitive::Name(name)),
+                    None => km_value_to_primitive(&km_value).map(|p| match p {
+                        Primitive::I64(v) => SymbolicPrimitive::I64(v),
+                        Primitive::I32(v) => SymbolicPrimitive::I32(v),
+                        Primitive::Vec(v) => SymbolicPrimitive::Vec(v.into()),
+                    }),
+                }
+            }
+        };
+        SymbolicRepr { tag: format!("{:?}", self.0.get_tag()), value }.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for SymbolicKeyParameterValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let repr = SymbolicRepr::deserialize(deserializer)?;
+        let tag = value_from_name(&Tag::enum_values(), &repr.tag, |t| t.0)
+            .map(Tag)
+            .ok_or_else(|| D::Error::custom(format!("Unknown tag {}", repr.tag)))?;
+        let primitive = match repr.value {
+            None => None,
+            Some(SymbolicPrimitive::Name(name)) => match enum_value_from_name(tag, &name) {
+                Some(v) => Some(Primitive::I32(v)),
+                None => {
+                    return Err(D::Error::custom(format!("Unknown value {} for {:?}", name, tag)))
+                }
+            },
+            Some(SymbolicPrimitive::I64(v)) => Some(Primitive::I64(v)),
+            Some(SymbolicPrimitive::I32(v)) => Some(Primitive::I32(v)),
+            Some(SymbolicPrimitive::Vec(v)) => Some(Primitive::Vec(v.into_owned())),
+        };
+        KeyParameterValue::new_from_tag_optional_primitive(tag, primitive)
+            .map(Self)
+            .map_err(D::Error::custom)
     }
 }
 
+/// This error is returned when extracting a typed inner value from a KeyParameterValue.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum KeyParameterValueError {
+    /// Returned if the KeyParameterValue is not the variant wrapping the requested type.
+    #[error("Expected key parameter with tag {expected_tag:?} but got {actual_tag:?}.")]
+    WrongVariant {
+        /// The tag of the variant wrapping the requested type.
+        expected_tag: Tag,
+        /// The tag of the given KeyParameterValue.
+        actual_tag: Tag,
+    },
+}
+
 /// KeyParameter wraps the KeyParameterValue and the security level at which it is enforced.
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+/// The derived `Ord` compares the value first and then the raw numeric security level. It is
+/// stable and suitable for canonical serialization, but it does not reflect how trustworthy a
+/// security level is. Use `KeyParameter::cmp_by_trust` for that.
+/// The default is an Invalid parameter at SecurityLevel::SOFTWARE, the default of the AIDL
+/// enum.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct KeyParameter {
     value: KeyParameterValue,
-    #[serde(deserialize_with = "deserialize_primitive")]
-    #[serde(serialize_with = "serialize_primitive")]
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_primitive"))]
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_primitive"))]
     security_level: SecurityLevel,
 }
 
+impl Hash for KeyParameter {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+        self.security_level.0.hash(state);
+    }
+}
+
+impl KeyParameterValue {
+    /// Wraps the value in a KeyParameter at SecurityLevel::SOFTWARE. Chain with
+    /// `KeyParameter::with_security_level` to pick another security level.
+    pub fn into_key_parameter(self) -> KeyParameter {
+        KeyParameter::new(self, SecurityLevel::SOFTWARE)
+    }
+}
+
 impl KeyParameter {
     /// Create an instance of KeyParameter, given the value and the security level.
     pub fn new(value: KeyParameterValue, security_level: SecurityLevel) -> Self {
@@ -1065,20 +3685,36 @@ impl KeyParameter {
     }
 
     /// Construct a KeyParameter from the data from a rusqlite row.
-    /// Note that following variants of KeyParameterValue should not be stored:
-    /// IncludeUniqueID, ApplicationID, ApplicationData, RootOfTrust, UniqueID,
-    /// Attestation*, AssociatedData, Nonce, MacLength, ResetSinceIdRotation, ConfirmationToken.
-    /// This filtering is enforced at a higher level and here we support conversion for all the
-    /// variants.
+    /// Note that variants of KeyParameterValue for which `is_storable` returns false should not
+    /// be stored, i.e., RootOfTrust, Attestation*, AssociatedData, Nonce, ResetSinceIdRotation,
+    /// and ConfirmationToken. `KeystoreDB::store_new_key` rejects them, so callers drop them
+    /// first, see `retain_storable`, and here we support conversion for all the variants. Values that cannot be read fail with
+    /// ResponseCode::VALUE_CORRUPTED, and the error context names the tag and the column type
+    /// found.
+    #[cfg(feature = "sql")]
     pub fn new_from_sql(
         tag_val: Tag,
         data: &SqlField,
         security_level_val: SecurityLevel,
     ) -> Result<Self> {
-        Ok(Self {
-            value: KeyParameterValue::new_from_sql(tag_val, data)?,
-            security_level: security_level_val,
-        })
+        let value =
+            KeyParameterValue::new_from_sql(tag_val, data).map_err(|e| {
+                anyhow::Error::new(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+                    .context(format!("{}, column type: {}.", e, sql_column_type(data)))
+            })?;
+        Ok(Self { value, security_level: security_level_val })
+    }
+
+    /// Construct a KeyParameter from a rusqlite row of the keyparameter table, i.e., a row with
+    /// the columns `tag, data, security_level` in this order. The same restrictions as for
+    /// `new_from_sql` apply.
+    #[cfg(feature = "sql")]
+    pub fn from_sql_row(row: &Row) -> Result<Self> {
+        let tag = Tag(row.get(0).context("In from_sql_row: Failed to read tag.")?);
+        let security_level =
+            SecurityLevel(row.get(2).context("In from_sql_row: Failed to read security level.")?);
+        Self::new_from_sql(tag, &SqlField::new(1, row), security_level)
+            .context("In from_sql_row: Failed to read KeyParameter.")
     }
 
     /// Get the KeyMint Tag of this this key parameter.
@@ -1091,15 +3727,856 @@ impl KeyParameter {
         &self.value
     }
 
+    /// Returns false if the key parameter must not be stored in the database, see
+    /// `KeyParameterValue::is_storable`.
+    pub fn is_storable(&self) -> bool {
+        self.value.is_storable()
+    }
+
     /// Returns the security level of this key parameter.
     pub fn security_level(&self) -> &SecurityLevel {
         &self.security_level
     }
 
+    /// Returns a mutable reference to the security level of this key parameter. Use
+    /// `set_security_level_for_all` to reassign the security level of a whole parameter list
+    /// with validation.
+    pub fn security_level_mut(&mut self) -> &mut SecurityLevel {
+        &mut self.security_level
+    }
+
+    /// Returns the key parameter with its security level replaced by `level`.
+    pub fn with_security_level(self, level: SecurityLevel) -> Self {
+        Self { security_level: level, ..self }
+    }
+
+    /// Returns a copy of the key parameter with its security level replaced by `level`.
+    pub fn clone_with_level(&self, level: SecurityLevel) -> Self {
+        Self { value: self.value.clone(), security_level: level }
+    }
+
+    /// Compares the key parameters by the trust rank of their security levels, less
+    /// trustworthy first, see `trust_rank`. Parameters with equally trusted security levels
+    /// are ordered by the derived `Ord`, so that this is a total order consistent with `Eq`.
+    pub fn cmp_by_trust(&self, other: &KeyParameter) -> Ordering {
+        trust_rank(self.security_level)
+            .cmp(&trust_rank(other.security_level))
+            .then_with(|| self.cmp(other))
+    }
+
+    /// Returns true if both key parameters have the same value, regardless of the security
+    /// levels at which they are enforced.
+    pub fn value_eq(&self, other: &KeyParameter) -> bool {
+        self.value == other.value
+    }
+
+    /// Compares the key parameters by their values alone, ignoring the security levels. This
+    /// is consistent with `value_eq`.
+    pub fn value_cmp(&self, other: &KeyParameter) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+
+    /// Formats the key parameter as structured logd event of the key with the given id, e.g.,
+    /// `keystore2|key_id=7|tag=KEY_SIZE|value=256|security_level=TRUSTED_ENVIRONMENT`. The
+    /// value is formatted as by the `Display` implementation of KeyParameterValue, and the
+    /// values of sensitive parameters are replaced with `<redacted>`.
+    pub fn to_logd_event(&self, key_id: i64) -> String {
+        let value = if self.value.is_sensitive() {
+            "<redacted>".to_string()
+        } else {
+            let display = self.value.to_string();
+            match display.split_once('=') {
+                Some((_, value)) => value.to_string(),
+                None => display,
+            }
+        };
+        format!(
+            "keystore2|key_id={}|tag={:?}|value={}|security_level={:?}",
+            key_id,
+            self.get_tag(),
+            value,
+            self.security_level
+        )
+    }
+
     /// An authorization is a KeyParameter with an associated security level that is used
     /// to convey the key characteristics to keystore clients. This function consumes
     /// an internal KeyParameter representation to produce the Authorization wire type.
     pub fn into_authorization(self) -> Authorization {
         Authorization { securityLevel: self.security_level, keyParameter: self.value.into() }
     }
+
+    /// Counterpart of `into_authorization`. Consumes the Authorization wire type to produce
+    /// the internal KeyParameter representation. The key parameter is converted as by
+    /// `KeyParameterValue::from_km_parameter_lossy`.
+    pub fn from_authorization_lossy(auth: Authorization) -> Self {
+        Self::new(KeyParameterValue::from_km_parameter_lossy(auth.keyParameter), auth.securityLevel)
+    }
+
+    /// Converts all authorizations, e.g., the key characteristics returned by
+    /// `IKeystoreSecurityLevel::getKeyCharacteristics`, as by `from_authorization_lossy`.
+    pub fn from_authorizations_lossy(auths: Vec<Authorization>) -> Vec<KeyParameter> {
+        auths.into_iter().map(Self::from_authorization_lossy).collect()
+    }
+
+    /// Counterpart of `from_authorizations_lossy`. Converts all key parameters as by
+    /// `into_authorization`, consuming the vector.
+    pub fn into_authorizations(params: Vec<KeyParameter>) -> Vec<Authorization> {
+        params.into_iter().map(Self::into_authorization).collect()
+    }
+}
+
+/// Converts as by `KeyParameter::into_authorization`, so that whole lists can be converted
+/// with `params.into_iter().map(Authorization::from).collect()`. `From<Vec<KeyParameter>>`
+/// cannot be implemented for `Vec<Authorization>`, because neither vector is a local type.
+///
+/// Operation parameters such as NONCE or ASSOCIATED_DATA are never part of the key
+/// characteristics, so the security level they carry in an Authorization has no meaning to
+/// KeyMint or to clients. It is nevertheless carried over unchanged in both directions, so
+/// that a round trip through Authorization yields the original parameters.
+impl From<KeyParameter> for Authorization {
+    fn from(kp: KeyParameter) -> Self {
+        kp.into_authorization()
+    }
+}
+
+/// Tags that are key characteristics but are enforced by Keystore rather than by KeyMint.
+static KEYSTORE_ENFORCED_TAGS: LazyLock<HashSet<Tag>> = LazyLock::new(|| {
+    HashSet::from([
+        Tag::ACTIVE_DATETIME,
+        Tag::ORIGINATION_EXPIRE_DATETIME,
+        Tag::USAGE_EXPIRE_DATETIME,
+        Tag::USER_ID,
+        Tag::ALLOW_WHILE_ON_BODY,
+        Tag::UNLOCKED_DEVICE_REQUIRED,
+        Tag::CREATION_DATETIME,
+        Tag::MAX_BOOT_LEVEL,
+    ])
+});
+
+impl KeyParameter {
+    /// Returns true if this parameter is a key characteristic that can be enforced by a
+    /// hardware backed KeyMint instance, i.e., its security level is TRUSTED_ENVIRONMENT or
+    /// STRONGBOX and the tag is neither an operation or generation only parameter nor a
+    /// characteristic enforced by Keystore.
+    pub fn is_enforceable_by_hardware(&self) -> bool {
+        let tag = self.get_tag();
+        matches!(self.security_level, SecurityLevel::TRUSTED_ENVIRONMENT | SecurityLevel::STRONGBOX)
+            && KeyParameterValue::is_characteristic(tag)
+            && !KEYSTORE_ENFORCED_TAGS.contains(&tag)
+    }
+}
+
+/// Returns the first key parameter with the given tag if any.
+pub fn find_tag(params: &[KeyParameter], tag: Tag) -> Option<&KeyParameter> {
+    params.iter().find(|p| p.get_tag() == tag)
+}
+
+/// Returns all key parameters with the given tag. This is useful for repeatable tags such as
+/// BLOCK_MODE, DIGEST, or PURPOSE.
+pub fn find_all_tags(params: &[KeyParameter], tag: Tag) -> impl Iterator<Item = &KeyParameter> {
+    params.iter().filter(move |p| p.get_tag() == tag)
+}
+
+/// Returns true if at least one of the key parameters has the given tag.
+pub fn contains_tag(params: &[KeyParameter], tag: Tag) -> bool {
+    params.iter().any(|p| p.get_tag() == tag)
+}
+
+/// Drops the key parameters that must not be stored in the database, i.e., those whose variant
+/// is marked with `#[key_param(not_stored)]`, and keeps the order of the remaining ones.
+pub fn retain_storable(mut params: Vec<KeyParameter>) -> Vec<KeyParameter> {
+    params.retain(KeyParameter::is_storable);
+    params
+}
+
+/// Drops the parameters that are not key characteristics, e.g., NONCE, ASSOCIATED_DATA,
+/// APPLICATION_ID, or the ATTESTATION_ID_* parameters, see
+/// `KeyParameterValue::is_characteristic`, and keeps the order of the remaining ones. Unknown
+/// tags are kept.
+pub fn remove_operation_params(mut params: Vec<KeyParameter>) -> Vec<KeyParameter> {
+    params.retain(|p| KeyParameterValue::is_characteristic(p.get_tag()));
+    params
+}
+
+/// Returns the sum of `KeyParameterValue::estimated_serialized_size` over all key parameters,
+/// e.g., to cap the size of the key characteristics stored for a key.
+pub fn total_size(params: &[KeyParameter]) -> usize {
+    params.iter().map(|p| p.value.estimated_serialized_size()).sum()
+}
+
+/// Returns the set of purposes the key parameters allow. PURPOSE is repeatable, so a key may
+/// have several purposes, and duplicates are removed.
+pub fn collect_purposes(params: &[KeyParameter]) -> BTreeSet<KeyPurpose> {
+    params
+        .iter()
+        .filter_map(|p| match p.key_parameter_value() {
+            KeyParameterValue::KeyPurpose(purpose) => Some(*purpose),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns true if the key parameters allow the given purpose, e.g., SIGN before beginning a
+/// signing operation.
+pub fn allows_purpose(params: &[KeyParameter], purpose: KeyPurpose) -> bool {
+    params.iter().any(|p| p.key_parameter_value() == &KeyParameterValue::KeyPurpose(purpose))
+}
+
+/// Returns the set of digests authorized for the MGF1 mask generation function of RSA-OAEP
+/// operations. RSA_OAEP_MGF_DIGEST is repeatable, so a key may authorize several MGF digests,
+/// and duplicates are removed. Digests given by DIGEST are not included.
+pub fn collect_mgf_digests(params: &[KeyParameter]) -> BTreeSet<Digest> {
+    params
+        .iter()
+        .filter_map(|p| match p.key_parameter_value() {
+            KeyParameterValue::RsaOaepMgfDigest(digest) => Some(*digest),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns true if `now`, in milliseconds since the epoch, is past the
+/// ORIGINATION_EXPIRE_DATETIME of the key parameters, i.e., the key can no longer be used to
+/// encrypt or sign. Like the enforcements, the expiry time itself is not past. Keys without
+/// ORIGINATION_EXPIRE_DATETIME never expire. The times are compared directly, so all i64
+/// values are handled.
+pub fn is_expired_for_origination(params: &[KeyParameter], now: i64) -> bool {
+    params.iter().any(|p| match p.key_parameter_value() {
+        KeyParameterValue::OriginationExpireDateTime(t) => now > *t,
+        _ => false,
+    })
+}
+
+/// Returns true if `now`, in milliseconds since the epoch, is past the USAGE_EXPIRE_DATETIME of
+/// the key parameters, i.e., the key can no longer be used to decrypt or verify. See
+/// `is_expired_for_origination`.
+pub fn is_expired_for_usage(params: &[KeyParameter], now: i64) -> bool {
+    params.iter().any(|p| match p.key_parameter_value() {
+        KeyParameterValue::UsageExpireDateTime(t) => now > *t,
+        _ => false,
+    })
+}
+
+/// A predicate on key parameter values, see `KeyParameterValue::matches_filter` and
+/// `filter_params`. Filters can be combined, e.g., `And(vec![ExactTag(Tag::ALGORITHM),
+/// Not(Box::new(TagWithIntegerRange { tag: Tag::ALGORITHM, min: 1, max: 1 }))])` matches
+/// every ALGORITHM parameter other than RSA.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyParameterFilter {
+    /// Matches values with the given tag.
+    ExactTag(Tag),
+    /// Matches values with the given tag whose integer or enum value is in `min..=max`.
+    TagWithIntegerRange {
+        /// The tag of the value.
+        tag: Tag,
+        /// The smallest matching value.
+        min: i32,
+        /// The largest matching value.
+        max: i32,
+    },
+    /// Matches values with the given tag whose blob starts with `prefix`.
+    TagWithBlobPrefix {
+        /// The tag of the value.
+        tag: Tag,
+        /// The prefix of the blob.
+        prefix: Vec<u8>,
+    },
+    /// Matches values that match all of the filters. An empty list matches every value.
+    And(Vec<KeyParameterFilter>),
+    /// Matches values that match any of the filters. An empty list matches no value.
+    Or(Vec<KeyParameterFilter>),
+    /// Matches values that do not match the filter.
+    Not(Box<KeyParameterFilter>),
+}
+
+impl KeyParameterValue {
+    /// Returns true if the value matches the filter. Integer ranges apply to values stored in
+    /// the Integer field and to enum values, and blob prefixes to values stored in the Blob
+    /// field. Values of other fields never match these filters.
+    pub fn matches_filter(&self, filter: &KeyParameterFilter) -> bool {
+        match filter {
+            KeyParameterFilter::ExactTag(tag) => self.get_tag() == *tag,
+            KeyParameterFilter::TagWithIntegerRange { tag, min, max } => {
+                self.get_tag() == *tag
+                    && i32::try_from(self).is_ok_and(|v| (*min..=*max).contains(&v))
+            }
+            KeyParameterFilter::TagWithBlobPrefix { tag, prefix } => {
+                self.get_tag() == *tag && self.blob_value().is_some_and(|b| b.starts_with(prefix))
+            }
+            KeyParameterFilter::And(filters) => filters.iter().all(|f| self.matches_filter(f)),
+            KeyParameterFilter::Or(filters) => filters.iter().any(|f| self.matches_filter(f)),
+            KeyParameterFilter::Not(filter) => !self.matches_filter(filter),
+        }
+    }
+}
+
+/// Returns the key parameters whose values match the filter, in their original order.
+pub fn filter_params<'a>(
+    params: &'a [KeyParameter],
+    filter: &KeyParameterFilter,
+) -> Vec<&'a KeyParameter> {
+    params.iter().filter(|p| p.value.matches_filter(filter)).collect()
+}
+
+/// This error is returned by `KeyParameterValue::validate_enum_ranges`.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum EnumRangeError {
+    /// Returned if the wrapped enum value is not a constant defined for the enum.
+    #[error("Value {value} is not defined for the enum of tag {tag:?}.")]
+    UndefinedValue {
+        /// The tag of the parameter.
+        tag: Tag,
+        /// The undefined value.
+        value: i32,
+    },
+}
+
+/// This error is returned by `check_cardinality`.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Tags that are not repeatable occur more than once with differing values: {duplicates:?}.")]
+pub struct CardinalityError {
+    /// Every tag that occurs with differing values, with the security level at which it does,
+    /// in the order in which the conflicts were found.
+    pub duplicates: Vec<(Tag, SecurityLevel)>,
+}
+
+/// Checks that no tag that is single valued according to
+/// `KeyParameterValue::is_repeated_allowed`, e.g., KEY_SIZE, occurs more than once with
+/// differing values at the same security level. Identical repetitions are harmless and
+/// accepted. The same tag may occur at different security levels, e.g., once hardware enforced
+/// and once software enforced. Parameters with tags unknown to Keystore are not checked. This
+/// is the cardinality policy used by `KeyParameterSetBuilder::build` and applied to the key
+/// characteristics returned by KeyMint before a new key is stored.
+pub fn check_cardinality(params: &[KeyParameter]) -> Result<(), CardinalityError> {
+    let mut seen = HashMap::new();
+    let mut duplicates = Vec::new();
+    for p in params {
+        let tag = p.get_tag();
+        if KeyParameterValue::is_repeated_allowed(tag)
+            || matches!(p.value, KeyParameterValue::Unknown { .. })
+        {
+            continue;
+        }
+        let key = (tag, p.security_level);
+        match seen.get(&key) {
+            None => {
+                seen.insert(key, &p.value);
+            }
+            Some(value) if *value != &p.value && !duplicates.contains(&key) => duplicates.push(key),
+            Some(_) => {}
+        }
+    }
+    if duplicates.is_empty() {
+        Ok(())
+    } else {
+        Err(CardinalityError { duplicates })
+    }
+}
+
+/// Returns true if both key parameters have the same value. The security level is ignored.
+pub fn functionally_equal(a: &KeyParameter, b: &KeyParameter) -> bool {
+    a.value_eq(b)
+}
+
+/// Returns true if both lists hold the same values regardless of order and of the security
+/// levels at which they are enforced. Duplicates are significant, i.e., a value that occurs
+/// twice in one list must occur twice in the other.
+pub fn parameter_sets_functionally_equal(a: &[KeyParameter], b: &[KeyParameter]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    fn sorted_by_value(params: &[KeyParameter]) -> Vec<&KeyParameter> {
+        let mut sorted: Vec<&KeyParameter> = params.iter().collect();
+        sorted.sort_by(|x, y| x.value_cmp(y));
+        sorted
+    }
+    sorted_by_value(a).into_iter().zip(sorted_by_value(b)).all(|(x, y)| x.value_eq(y))
+}
+
+/// Returns true if both lists hold the same parameters regardless of order, ignoring the
+/// parameters whose tag is marked with `#[key_param(volatile)]`, e.g., CREATION_DATETIME.
+/// Unlike `parameter_sets_functionally_equal`, the security levels must match. This allows
+/// comparing the characteristics of a key before and after a reprovision.
+pub fn stable_eq(a: &[KeyParameter], b: &[KeyParameter]) -> bool {
+    fn sorted_stable(params: &[KeyParameter]) -> Vec<&KeyParameter> {
+        let mut sorted: Vec<&KeyParameter> =
+            params.iter().filter(|p| !KeyParameterValue::is_volatile(p.value.get_tag())).collect();
+        sorted.sort();
+        sorted
+    }
+    sorted_stable(a) == sorted_stable(b)
+}
+
+/// Returns the rank of the security level in the order of trust, higher is more trustworthy.
+/// The numeric value of SecurityLevel does not reflect this order, because the KEYSTORE
+/// pseudo-level (100), which denotes parameters enforced by Keystore itself, is less
+/// trustworthy than SOFTWARE (0). Security levels unknown to Keystore rank lowest.
+pub fn trust_rank(level: SecurityLevel) -> u8 {
+    match level {
+        SecurityLevel::STRONGBOX => 4,
+        SecurityLevel::TRUSTED_ENVIRONMENT => 3,
+        SecurityLevel::SOFTWARE => 2,
+        SecurityLevel::KEYSTORE => 1,
+        _ => 0,
+    }
+}
+
+/// Sorts the key parameters by security level, STRONGBOX first, followed by
+/// TRUSTED_ENVIRONMENT, SOFTWARE, KEYSTORE, and any other security level. Within each
+/// security level, the parameters are sorted by the numeric value of their tag. The sort is
+/// stable, i.e., parameters with the same security level and tag keep their relative order.
+pub fn sort_by_security_level(params: &mut [KeyParameter]) {
+    params.sort_by_key(|p| (Reverse(trust_rank(p.security_level)), p.get_tag()));
+}
+
+/// Returns the key parameters sorted as by `sort_by_security_level`.
+pub fn sorted_by_security_level(mut params: Vec<KeyParameter>) -> Vec<KeyParameter> {
+    sort_by_security_level(&mut params);
+    params
+}
+
+/// Groups the values of the given key parameters by security level, e.g., to reconstruct the
+/// hardware and software enforced lists of KeyMint's KeyCharacteristics. The values are moved
+/// out of the parameters and keep their relative order within each level.
+pub fn partition_by_level(
+    params: Vec<KeyParameter>,
+) -> BTreeMap<SecurityLevel, Vec<KeyParameterValue>> {
+    let mut result: BTreeMap<SecurityLevel, Vec<KeyParameterValue>> = BTreeMap::new();
+    for p in params {
+        result.entry(p.security_level).or_default().push(p.value);
+    }
+    result
+}
+
+/// Assigns the given security level to all key parameters, e.g., to promote software enforced
+/// parameters to hardware enforced parameters after a key upgrade. Note that 0 is
+/// SecurityLevel::SOFTWARE, so the level is validated against the security levels defined by
+/// KeyMint instead. Fails with ResponseCode::INVALID_ARGUMENT and leaves the parameters
+/// untouched if the level is not one of them.
+pub fn set_security_level_for_all(params: &mut [KeyParameter], level: SecurityLevel) -> Result<()> {
+    if !SecurityLevel::enum_values().contains(&level) {
+        return Err(KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT)).with_context(|| {
+            format!("In set_security_level_for_all: Invalid security level {:?}.", level)
+        });
+    }
+    for p in params {
+        *p.security_level_mut() = level;
+    }
+    Ok(())
+}
+
+/// Returns copies of the key parameters with their security level replaced by `level`, e.g.,
+/// to reclassify software enforced parameters as hardware enforced during key migration.
+/// Unlike `set_security_level_for_all`, the source parameters are left untouched and the level
+/// is not validated.
+pub fn clone_params_with_level(params: &[KeyParameter], level: SecurityLevel) -> Vec<KeyParameter> {
+    params.iter().map(|p| p.clone_with_level(level)).collect()
+}
+
+/// Formats each of the key parameters of the key with the given id as logd event, see
+/// `KeyParameter::to_logd_event`.
+pub fn params_to_logd_events(key_id: i64, params: &[KeyParameter]) -> Vec<String> {
+    params.iter().map(|p| p.to_logd_event(key_id)).collect()
+}
+
+/// Decodes the key parameters of the given raw rows of the keyparameter table, each given as
+/// tag, data column and security level, as by `KeyParameter::new_from_sql`. Decoding stops at
+/// the first row that cannot be read, and the error context names the index and tag of that
+/// row.
+#[cfg(feature = "sql")]
+pub fn decode_rows<'a, I>(rows: I) -> Result<Vec<KeyParameter>>
+where
+    I: Iterator<Item = (Tag, SqlField<'a>, SecurityLevel)>,
+{
+    rows.enumerate()
+        .map(|(i, (tag, data, security_level))| {
+            KeyParameter::new_from_sql(tag, &data, security_level).with_context(|| {
+                format!("In decode_rows: Failed to decode row {} with tag {:?}.", i, tag)
+            })
+        })
+        .collect()
+}
+
+impl KeyParameterValue {
+    /// Returns the number of bytes the value occupies in the data column of the keyparameter
+    /// table: 4 for integer and enum values, 8 for long integers and date times, the length of
+    /// the blob for blobs, 1 for boolean parameters, and 0 for Invalid. The tag and the
+    /// security level are not included. This is an estimate for memory budgets; SQLite
+    /// itself stores integers in fewer bytes if possible and booleans as NULL.
+    pub fn estimated_serialized_size(&self) -> usize {
+        if let Self::Invalid(_) = self {
+            return 0;
+        }
+        if let Some(blob) = self.blob_value() {
+            return blob.len();
+        }
+        match self.tag_type() {
+            TagType::ENUM | TagType::ENUM_REP | TagType::UINT | TagType::UINT_REP => 4,
+            TagType::ULONG | TagType::ULONG_REP | TagType::DATE => 8,
+            TagType::BOOL => 1,
+            _ => 0,
+        }
+    }
+
+    /// Checks that the wrapped value is within the bounds defined by the KeyMint
+    /// specification: MIN_MAC_LENGTH must be a multiple of 8 and at least 64,
+    /// MAX_USES_PER_BOOT must be positive, and date times must not be negative. Bounds that
+    /// depend on other parameters, such as the RSA key size or the upper bound of
+    /// MIN_MAC_LENGTH given by the digest, are checked by `KeyParameterSetBuilder`.
+    /// `new_from_tag_primitive_pair` calls this. Decoders and the conversions from KeyMint
+    /// do not, so that stored and imported parameters are preserved unchanged.
+    pub fn validate_value_range(&self) -> Result<(), ValidationError> {
+        match self.out_of_range() {
+            Some((value, allowed)) => {
+                Err(ValidationError::OutOfRange { tag: self.get_tag(), value, allowed })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the wrapped value and a description of the allowed values if the value is out
+    /// of range, see `validate_value_range`.
+    fn out_of_range(&self) -> Option<(Primitive, &'static str)> {
+        match self {
+            Self::MinMacLength(v) if *v < 64 || v % 8 != 0 => {
+                Some((Primitive::I32(*v), "a multiple of 8 of at least 64"))
+            }
+            Self::MaxUsesPerBoot(v) if *v <= 0 => Some((Primitive::I32(*v), "a positive value")),
+            _ => match (field_type_of(self.get_tag()), i64::try_from(self).ok()) {
+                (Some(FieldType::DateTime), Some(v)) if v < 0 => {
+                    Some((Primitive::I64(v), "a non-negative date time"))
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Returns true if the tag type allows the tag to occur more than once in a key's
+/// characteristics.
+pub(crate) fn is_repeatable_tag(tag: Tag) -> bool {
+    matches!(tag_type_of(tag), TagType::ENUM_REP | TagType::UINT_REP | TagType::ULONG_REP)
+}
+
+impl KeyParameter {
+    /// Computes how the key parameters `b` differ from `a`. Parameters are matched by tag.
+    /// Repeatable tags are compared as multisets, i.e., each value that occurs in only one
+    /// of the lists is reported as added or removed. A non repeatable tag whose value or
+    /// security level differs between the lists is reported as changed.
+    /// The result does not depend on the order of either list. All entries are sorted by
+    /// the `Ord` implementation of KeyParameter. This can be used, e.g., to compare the
+    /// parameters requested at key generation with the characteristics that KeyMint enforces.
+    pub fn diff(a: &[KeyParameter], b: &[KeyParameter]) -> KeyParameterDiff {
+        let mut unmatched_b: Vec<Option<&KeyParameter>> = b.iter().map(Some).collect();
+        let mut unmatched_a: Vec<&KeyParameter> = Vec::new();
+        let mut sorted_a: Vec<&KeyParameter> = a.iter().collect();
+        sorted_a.sort();
+        for p in sorted_a {
+            match unmatched_b.iter_mut().find(|q| **q == Some(p)) {
+                Some(q) => *q = None,
+                None => unmatched_a.push(p),
+            }
+        }
+
+        let mut diff = KeyParameterDiff::default();
+        for p in unmatched_a {
+            let tag = p.get_tag();
+            let counterpart = if is_repeatable_tag(tag) {
+                None
+            } else {
+                unmatched_b.iter_mut().find(|q| matches!(q, Some(q) if q.get_tag() == tag))
+            };
+            match counterpart.and_then(Option::take) {
+                Some(q) => diff.changed.push((p.clone(), q.clone())),
+                None => diff.removed.push(p.clone()),
+            }
+        }
+        diff.added = unmatched_b.into_iter().flatten().cloned().collect();
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+        diff
+    }
+}
+
+/// KeyParameterDiff describes how a list of key parameters differs from a previous one.
+/// See `KeyParameter::diff`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeyParameterDiff {
+    /// Parameters that are only present in the new list.
+    pub added: Vec<KeyParameter>,
+    /// Parameters that are only present in the old list.
+    pub removed: Vec<KeyParameter>,
+    /// Non repeatable parameters present in both lists with different values or security
+    /// levels, as pairs of (old, new).
+    pub changed: Vec<(KeyParameter, KeyParameter)>,
+}
+
+impl KeyParameterDiff {
+    /// Returns true if both lists hold the same parameters.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Formats the diff as a single line summary suitable for audit logs, e.g.,
+    /// `added: [KEY_SIZE=256@TRUSTED_ENVIRONMENT]; changed: [OS_PATCHLEVEL=202301@KEYSTORE ->
+    /// OS_PATCHLEVEL=202401@KEYSTORE]`. Blob values are summarized by their size only.
+    pub fn audit_summary(&self) -> String {
+        fn format_param(p: &KeyParameter) -> String {
+            format!("{}@{:?}", p.key_parameter_value(), p.security_level())
+        }
+        fn format_list<'a>(params: impl Iterator<Item = &'a KeyParameter>) -> String {
+            params.map(format_param).collect::<Vec<_>>().join(", ")
+        }
+
+        if self.is_empty() {
+            return "no changes".to_string();
+        }
+        let mut sections = Vec::new();
+        if !self.added.is_empty() {
+            sections.push(format!("added: [{}]", format_list(self.added.iter())));
+        }
+        if !self.removed.is_empty() {
+            sections.push(format!("removed: [{}]", format_list(self.removed.iter())));
+        }
+        if !self.changed.is_empty() {
+            let changed = self
+                .changed
+                .iter()
+                .map(|(old, new)| format!("{} -> {}", format_param(old), format_param(new)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            sections.push(format!("changed: [{}]", changed));
+        }
+        sections.join("; ")
+    }
+}
+
+/// KeyParameterBuilder assembles a list of key parameters with chainable setters. All values
+/// are added at the security level of the builder, which can be changed for subsequently added
+/// values with `at_level`.
+///
+/// ```
+/// let params = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+///     .algorithm(Algorithm::RSA)
+///     .key_size(2048)
+///     .purpose(KeyPurpose::SIGN)
+///     .digest(Digest::SHA_2_256)
+///     .at_level(SecurityLevel::KEYSTORE)
+///     .creation_datetime(1704067200000)
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct KeyParameterBuilder {
+    security_level: SecurityLevel,
+    params: Vec<KeyParameter>,
+}
+
+impl KeyParameterBuilder {
+    /// Creates an empty builder adding values at the given security level.
+    pub fn new(security_level: SecurityLevel) -> Self {
+        Self { security_level, params: Vec::new() }
+    }
+
+    /// Sets the security level for all values added after this call.
+    pub fn at_level(&mut self, security_level: SecurityLevel) -> &mut Self {
+        self.security_level = security_level;
+        self
+    }
+
+    /// Adds an arbitrary key parameter value at the current security level.
+    pub fn value(&mut self, value: KeyParameterValue) -> &mut Self {
+        self.params.push(KeyParameter::new(value, self.security_level));
+        self
+    }
+
+    /// Adds a purpose for which the key may be used.
+    pub fn purpose(&mut self, v: KeyPurpose) -> &mut Self {
+        self.value(KeyParameterValue::KeyPurpose(v))
+    }
+
+    /// Adds the algorithm of the key.
+    pub fn algorithm(&mut self, v: Algorithm) -> &mut Self {
+        self.value(KeyParameterValue::Algorithm(v))
+    }
+
+    /// Adds the key size in bits.
+    pub fn key_size(&mut self, v: i32) -> &mut Self {
+        self.value(KeyParameterValue::KeySize(v))
+    }
+
+    /// Adds a block mode with which the key may be used.
+    pub fn block_mode(&mut self, v: BlockMode) -> &mut Self {
+        self.value(KeyParameterValue::BlockMode(v))
+    }
+
+    /// Adds a digest with which the key may be used.
+    pub fn digest(&mut self, v: Digest) -> &mut Self {
+        self.value(KeyParameterValue::Digest(v))
+    }
+
+    /// Adds a padding mode with which the key may be used.
+    pub fn padding(&mut self, v: PaddingMode) -> &mut Self {
+        self.value(KeyParameterValue::PaddingMode(v))
+    }
+
+    /// Adds the elliptic curve of an EC key.
+    pub fn ec_curve(&mut self, v: EcCurve) -> &mut Self {
+        self.value(KeyParameterValue::EcCurve(v))
+    }
+
+    /// Adds the public exponent of an RSA key.
+    pub fn rsa_public_exponent(&mut self, v: i64) -> &mut Self {
+        self.value(KeyParameterValue::RSAPublicExponent(v))
+    }
+
+    /// Adds the minimum MAC length in bits.
+    pub fn min_mac_length(&mut self, v: i32) -> &mut Self {
+        self.value(KeyParameterValue::MinMacLength(v))
+    }
+
+    /// Adds a secure user id to which the key is bound.
+    pub fn user_secure_id(&mut self, v: i64) -> &mut Self {
+        self.value(KeyParameterValue::UserSecureID(v))
+    }
+
+    /// Adds the authenticator types that may authorize the key.
+    pub fn user_auth_type(&mut self, v: HardwareAuthenticatorType) -> &mut Self {
+        self.value(KeyParameterValue::HardwareAuthenticatorType(v))
+    }
+
+    /// Adds the authentication timeout in seconds.
+    pub fn auth_timeout(&mut self, v: i32) -> &mut Self {
+        self.value(KeyParameterValue::AuthTimeout(v))
+    }
+
+    /// Adds the time, in milliseconds since the epoch, from which on the key may be used.
+    pub fn active_datetime(&mut self, v: i64) -> &mut Self {
+        self.value(KeyParameterValue::ActiveDateTime(v))
+    }
+
+    /// Adds the time, in milliseconds since the epoch, at which the key was created.
+    pub fn creation_datetime(&mut self, v: i64) -> &mut Self {
+        self.value(KeyParameterValue::CreationDateTime(v))
+    }
+
+    /// Allows the caller to specify a nonce or initialization vector.
+    pub fn caller_nonce(&mut self) -> &mut Self {
+        self.value(KeyParameterValue::CallerNonce)
+    }
+
+    /// Allows the key to be used without user authentication.
+    pub fn no_auth_required(&mut self) -> &mut Self {
+        self.value(KeyParameterValue::NoAuthRequired)
+    }
+
+    /// Returns the key parameters added so far, in the order they were added.
+    pub fn build(&self) -> Vec<KeyParameter> {
+        self.params.clone()
+    }
+}
+
+/// BoolTagSet records which boolean parameters, e.g., CALLER_NONCE or NO_AUTH_REQUIRED, are
+/// present in a list of key parameters, so that several flags can be checked without scanning
+/// the list for each of them. See `boolean_flags`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BoolTagSet(u64);
+
+impl BoolTagSet {
+    /// Returns true if the boolean parameter `v` is in the set. Returns false for variants
+    /// that are not boolean parameters and for unknown boolean tags.
+    pub fn contains(&self, v: &KeyParameterValue) -> bool {
+        v.bool_flag_bit().is_some_and(|bit| self.0 & (1 << bit) != 0)
+    }
+}
+
+/// Returns the set of boolean parameters present in `params`. Unknown boolean tags are not
+/// recorded.
+pub fn boolean_flags(params: &[KeyParameter]) -> BoolTagSet {
+    BoolTagSet(
+        params.iter().filter_map(|p| p.value.bool_flag_bit()).fold(0, |set, bit| set | 1 << bit),
+    )
+}
+
+/// KeyParameterCollection owns a list of key parameters and indexes them by tag, so that
+/// looking up the parameters of a given tag does not require a linear scan.
+/// On construction, parameters with the same tag are grouped together, preserving their
+/// relative order and the order in which the tags first appear.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct KeyParameterCollection {
+    params: Vec<KeyParameter>,
+    index: HashMap<Tag, Vec<usize>>,
+}
+
+impl KeyParameterCollection {
+    /// Returns the first key parameter with the given tag if any.
+    pub fn get_first(&self, tag: Tag) -> Option<&KeyParameter> {
+        self.index.get(&tag).and_then(|positions| positions.first()).map(|i| &self.params[*i])
+    }
+
+    /// Returns all key parameters with the given tag. The slice is empty if there are none.
+    pub fn get_all(&self, tag: Tag) -> &[KeyParameter] {
+        match self.index.get(&tag).map(|positions| (positions.first(), positions.last())) {
+            Some((Some(first), Some(last))) => &self.params[*first..=*last],
+            _ => &[],
+        }
+    }
+
+    /// Returns true if the collection holds at least one key parameter with the given tag.
+    pub fn contains_tag(&self, tag: Tag) -> bool {
+        self.index.contains_key(&tag)
+    }
+
+    /// Returns the number of key parameters in the collection.
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Returns true if the collection holds no key parameters.
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// Returns an iterator over the key parameters in the collection.
+    pub fn iter(&self) -> std::slice::Iter<'_, KeyParameter> {
+        self.params.iter()
+    }
+}
+
+impl From<Vec<KeyParameter>> for KeyParameterCollection {
+    fn from(mut params: Vec<KeyParameter>) -> Self {
+        let mut first_seen: HashMap<Tag, usize> = HashMap::new();
+        for p in params.iter() {
+            let next = first_seen.len();
+            first_seen.entry(p.get_tag()).or_insert(next);
+        }
+        // The sort is stable, so parameters with the same tag keep their relative order.
+        params.sort_by_key(|p| first_seen[&p.get_tag()]);
+
+        let mut index: HashMap<Tag, Vec<usize>> = HashMap::new();
+        for (i, p) in params.iter().enumerate() {
+            index.entry(p.get_tag()).or_default().push(i);
+        }
+        Self { params, index }
+    }
+}
+
+impl IntoIterator for KeyParameterCollection {
+    type Item = KeyParameter;
+    type IntoIter = std::vec::IntoIter<KeyParameter>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.params.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a KeyParameterCollection {
+    type Item = &'a KeyParameter;
+    type IntoIter = std::slice::Iter<'a, KeyParameter>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.params.iter()
+    }
 }
@@ -0,0 +1,79 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The arbitrary_tests module checks that the Arbitrary implementation of KeyParameterValue
+//! only generates well formed values.
+
+use super::*;
+use arbitrary::{Arbitrary, Unstructured};
+
+/// Generates a value for each seed. The seed determines the input bytes.
+fn arbitrary_values(count: usize) -> Vec<KeyParameterValue> {
+    (0..count)
+        .map(|seed| {
+            let data: Vec<u8> = (0..512).map(|i| (i * 31 + seed * 17 + i * seed) as u8).collect();
+            KeyParameterValue::arbitrary(&mut Unstructured::new(&data))
+                .expect("Failed to generate key parameter value.")
+        })
+        .collect()
+}
+
+fn is_in_range<T: PartialEq>(values: &[T], v: &T) -> bool {
+    values.contains(v)
+}
+
+#[test]
+fn arbitrary_values_are_well_formed() {
+    for value in arbitrary_values(1000) {
+        assert!(!matches!(value, KeyParameterValue::Unknown { .. }));
+        let km_param: KmKeyParameter = value.clone().into();
+        assert_eq!(KeyParameterValue::try_from_km_parameter(km_param.clone()).as_ref(), Ok(&value));
+        let in_range = match &km_param.value {
+            KmKeyParameterValue::Algorithm(v) => is_in_range(&Algorithm::enum_values(), v),
+            KmKeyParameterValue::BlockMode(v) => is_in_range(&BlockMode::enum_values(), v),
+            KmKeyParameterValue::PaddingMode(v) => is_in_range(&PaddingMode::enum_values(), v),
+            KmKeyParameterValue::Digest(v) => is_in_range(&Digest::enum_values(), v),
+            KmKeyParameterValue::EcCurve(v) => is_in_range(&EcCurve::enum_values(), v),
+            KmKeyParameterValue::Origin(v) => is_in_range(&KeyOrigin::enum_values(), v),
+            KmKeyParameterValue::KeyPurpose(v) => is_in_range(&KeyPurpose::enum_values(), v),
+            KmKeyParameterValue::HardwareAuthenticatorType(v) => {
+                is_in_range(&HardwareAuthenticatorType::enum_values(), v)
+            }
+            KmKeyParameterValue::SecurityLevel(v) => is_in_range(&SecurityLevel::enum_values(), v),
+            KmKeyParameterValue::Blob(v) => v.len() <= MAX_ARBITRARY_BLOB_LEN,
+            _ => true,
+        };
+        assert!(in_range, "{:?}", value);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn arbitrary_values_survive_serde_round_trip() {
+    for value in arbitrary_values(200) {
+        let param = KeyParameter::new(value, SecurityLevel::TRUSTED_ENVIRONMENT);
+        let serialized = serde_cbor::to_vec(&param).expect("Failed to serialize.");
+        let deserialized: KeyParameter =
+            serde_cbor::from_slice(&serialized).expect("Failed to deserialize.");
+        assert_eq!(param, deserialized);
+    }
+}
+
+#[test]
+fn arbitrary_does_not_fail_on_short_input() {
+    for len in 0..8 {
+        let data = vec![0xffu8; len];
+        assert!(KeyParameterValue::arbitrary(&mut Unstructured::new(&data)).is_ok());
+    }
+}
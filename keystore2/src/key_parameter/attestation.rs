@@ -0,0 +1,351 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module extracts key parameters from the Android key attestation extension of X.509
+//! certificates and decodes certificate subjects. It is only built with the `attestation`
+//! feature.
+
+use super::{
+    tag_type_of, KeyParameter, KeyParameterError, KeyParameterValue, KeystoreError, Primitive,
+    ResponseCode, SecurityLevel, Tag, TagType,
+};
+use anyhow::{Context, Result};
+use der::Decode;
+
+/// DER encoding of the OID 1.3.6.1.4.1.11129.2.1.17 of the Android key attestation extension.
+pub(super) const KEY_ATTESTATION_OID: &[u8] =
+    &[0x2B, 0x06, 0x01, 0x04, 0x01, 0xD6, 0x79, 0x02, 0x01, 0x11];
+
+/// A DER encoded ASN.1 element.
+struct DerElement<'a> {
+    /// The class bits of the identifier, e.g., 0x80 for context specific tags.
+    class: u8,
+    constructed: bool,
+    number: u32,
+    content: &'a [u8],
+    /// The complete encoding including identifier and length.
+    encoded: &'a [u8],
+}
+
+impl<'a> DerElement<'a> {
+    const UNIVERSAL: u8 = 0x00;
+    const CONTEXT_SPECIFIC: u8 = 0x80;
+    const INTEGER: u32 = 2;
+    const OCTET_STRING: u32 = 4;
+    const NULL: u32 = 5;
+    const ENUMERATED: u32 = 10;
+    const SEQUENCE: u32 = 16;
+    const SET: u32 = 17;
+
+    /// Reads the next element from `input` and advances `input` past it. High tag numbers are
+    /// supported, because the AuthorizationList uses them, e.g., [701] for creationDateTime.
+    /// Encodings that are valid BER but not DER are rejected, i.e., indefinite lengths,
+    /// tag numbers and lengths that are not encoded in the fewest possible bytes, and
+    /// universal types with the wrong constructed bit.
+    fn read(input: &mut &'a [u8]) -> Result<Self> {
+        let corrupted = || KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED);
+        let start = *input;
+        let mut next = || -> Result<u8> {
+            let (b, rest) = input
+                .split_first()
+                .ok_or_else(corrupted)
+                .context("In DerElement::read: Unexpected end of input.")?;
+            *input = rest;
+            Ok(*b)
+        };
+
+        let identifier = next()?;
+        let mut number = (identifier & 0x1F) as u32;
+        if number == 0x1F {
+            number = 0;
+            loop {
+                let b = next()?;
+                if number == 0 && b == 0x80 {
+                    return Err(corrupted())
+                        .context("In DerElement::read: Non-minimal tag number encoding.");
+                }
+                if number > (u32::MAX >> 7) {
+                    return Err(corrupted()).context("In DerElement::read: Tag number too large.");
+                }
+                number = (number << 7) | (b & 0x7F) as u32;
+                if b & 0x80 == 0 {
+                    break;
+                }
+            }
+            if number < 0x1F {
+                return Err(corrupted())
+                    .context("In DerElement::read: Non-minimal tag number encoding.");
+            }
+        }
+
+        let mut len = next()? as usize;
+        if len & 0x80 != 0 {
+            let len_bytes = len & 0x7F;
+            if len_bytes == 0 || len_bytes > 4 {
+                return Err(corrupted())
+                    .context("In DerElement::read: Unsupported length encoding.");
+            }
+            len = 0;
+            for _ in 0..len_bytes {
+                len = (len << 8) | next()? as usize;
+            }
+            if len < 0x80 || len >> (8 * (len_bytes - 1)) == 0 {
+                return Err(corrupted()).context("In DerElement::read: Non-minimal length.");
+            }
+        }
+        if input.len() < len {
+            return Err(corrupted()).context("In DerElement::read: Content exceeds input.");
+        }
+        let (content, rest) = input.split_at(len);
+        *input = rest;
+        let element = Self {
+            class: identifier & 0xC0,
+            constructed: identifier & 0x20 != 0,
+            number,
+            content,
+            encoded: &start[..start.len() - rest.len()],
+        };
+        if element.class == Self::UNIVERSAL
+            && element.constructed != matches!(number, Self::SEQUENCE | Self::SET)
+        {
+            return Err(corrupted()).with_context(|| {
+                format!("In DerElement::read: Wrong constructed bit for type {}.", number)
+            });
+        }
+        Ok(element)
+    }
+
+    /// Reads the next element from `input` and checks that it is a universal element of the
+    /// given type.
+    fn read_universal(input: &mut &'a [u8], number: u32) -> Result<Self> {
+        let element = Self::read(input)?;
+        if element.class != Self::UNIVERSAL || element.number != number {
+            return Err(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED)).with_context(|| {
+                format!(
+                    "In DerElement::read_universal: Expected type {}, found {}.",
+                    number, element.number
+                )
+            });
+        }
+        Ok(element)
+    }
+
+    /// Returns the value of an INTEGER or ENUMERATED element that fits into an i64. The value
+    /// must be encoded in the fewest possible bytes.
+    fn to_i64(&self) -> Result<i64> {
+        if self.content.is_empty() || self.content.len() > 8 {
+            return Err(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+                .context("In DerElement::to_i64: Integer out of range.");
+        }
+        if let [first, second, ..] = self.content {
+            if (*first == 0x00 && second & 0x80 == 0) || (*first == 0xFF && second & 0x80 != 0) {
+                return Err(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+                    .context("In DerElement::to_i64: Non-minimal integer encoding.");
+            }
+        }
+        let sign_extension = if self.content[0] & 0x80 != 0 { -1i64 } else { 0 };
+        Ok(self.content.iter().fold(sign_extension, |acc, b| (acc << 8) | *b as i64))
+    }
+}
+
+impl KeyParameterValue {
+    /// Decodes a single entry of an AuthorizationList of the certificate extension with the
+    /// DER encoded `oid`. `value` is the DER encoding of the entry, i.e., an explicitly tagged
+    /// element whose context specific tag number is the KeyMint tag without its type bits.
+    /// Returns None if `oid` is not the key attestation OID 1.3.6.1.4.1.11129.2.1.17 or if the
+    /// tag is not known to KeyMint. Repeatable tags are encoded as SET OF, which must hold
+    /// exactly one value here; use `key_parameters_from_attestation_extension` to decode
+    /// complete extensions. The root of trust is kept as its DER encoded SEQUENCE.
+    pub fn from_certificate_extension(oid: &[u8], value: &[u8]) -> Result<Option<Self>> {
+        if oid != KEY_ATTESTATION_OID {
+            return Ok(None);
+        }
+        let mut input = value;
+        let entry = DerElement::read(&mut input)
+            .context("In from_certificate_extension: Malformed entry.")?;
+        if !input.is_empty() {
+            return Err(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+                .context("In from_certificate_extension: Trailing data after entry.");
+        }
+        let mut values = Self::from_authorization_list_entry(&entry)
+            .context("In from_certificate_extension.")?;
+        match values.len() {
+            0 => Ok(None),
+            1 => Ok(values.pop()),
+            n => Err(KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT)).with_context(|| {
+                format!("In from_certificate_extension: Entry holds {} values.", n)
+            }),
+        }
+    }
+
+    /// Decodes all values of the given AuthorizationList entry. Returns an empty list if the
+    /// tag is not known to KeyMint.
+    fn from_authorization_list_entry(entry: &DerElement) -> Result<Vec<Self>> {
+        let corrupted = || KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED);
+        if entry.class != DerElement::CONTEXT_SPECIFIC || !entry.constructed {
+            return Err(corrupted())
+                .context("In from_authorization_list_entry: Expected an explicitly tagged entry.");
+        }
+        let tag = match Tag::enum_values()
+            .into_iter()
+            .find(|t| t.0 != 0 && (t.0 as u32 & 0x0FFFFFFF) == entry.number)
+        {
+            Some(tag) => tag,
+            None => return Ok(vec![]),
+        };
+
+        let mut input = entry.content;
+        let inner = DerElement::read(&mut input).context("In from_authorization_list_entry.")?;
+        if !input.is_empty() {
+            return Err(corrupted()).with_context(|| {
+                format!("In from_authorization_list_entry: Trailing data in {:?}.", tag)
+            });
+        }
+        let items = match tag_type_of(tag) {
+            TagType::ENUM_REP | TagType::UINT_REP | TagType::ULONG_REP => {
+                if inner.class != DerElement::UNIVERSAL || inner.number != DerElement::SET {
+                    return Err(corrupted()).with_context(|| {
+                        format!("In from_authorization_list_entry: Expected SET OF for {:?}.", tag)
+                    });
+                }
+                let mut items = vec![];
+                let mut set = inner.content;
+                while !set.is_empty() {
+                    items.push(
+                        DerElement::read(&mut set).context("In from_authorization_list_entry.")?,
+                    );
+                }
+                items
+            }
+            _ => vec![inner],
+        };
+
+        items
+            .iter()
+            .map(|item| {
+                let primitive = match (tag_type_of(tag), item.class, item.number) {
+                    (TagType::BOOL, DerElement::UNIVERSAL, DerElement::NULL) => None,
+                    (
+                        TagType::ENUM | TagType::ENUM_REP | TagType::UINT | TagType::UINT_REP,
+                        DerElement::UNIVERSAL,
+                        DerElement::INTEGER,
+                    ) => Some(Primitive::I32(item.to_i64()?.try_into().map_err(|_| corrupted())?)),
+                    (
+                        TagType::ULONG | TagType::ULONG_REP | TagType::DATE,
+                        DerElement::UNIVERSAL,
+                        DerElement::INTEGER,
+                    ) => Some(Primitive::I64(item.to_i64()?)),
+                    (
+                        TagType::BIGNUM | TagType::BYTES,
+                        DerElement::UNIVERSAL,
+                        DerElement::OCTET_STRING,
+                    ) => Some(Primitive::Vec(item.content.to_vec())),
+                    (TagType::BYTES, DerElement::UNIVERSAL, DerElement::SEQUENCE)
+                        if tag == Tag::ROOT_OF_TRUST =>
+                    {
+                        Some(Primitive::Vec(item.encoded.to_vec()))
+                    }
+                    _ => {
+                        return Err(corrupted()).with_context(|| {
+                            format!(
+                                "In from_authorization_list_entry: Unexpected type {} for {:?}.",
+                                item.number, tag
+                            )
+                        })
+                    }
+                };
+                Self::new_from_tag_optional_primitive(tag, primitive)
+                    .map_err(|_| corrupted())
+                    .with_context(|| {
+                        format!("In from_authorization_list_entry: Invalid value for {:?}.", tag)
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Decodes the key parameters of the value of the Android key attestation extension, i.e., the
+/// DER encoded KeyDescription. Parameters of the softwareEnforced list get
+/// SecurityLevel::SOFTWARE, and parameters of the hardwareEnforced list get the
+/// attestationSecurityLevel of the description. Entries with tags unknown to KeyMint are
+/// skipped.
+pub fn key_parameters_from_attestation_extension(value: &[u8]) -> Result<Vec<KeyParameter>> {
+    let mut input = value;
+    let description = DerElement::read_universal(&mut input, DerElement::SEQUENCE)
+        .context("In key_parameters_from_attestation_extension: Expected KeyDescription.")?;
+
+    let mut fields = description.content;
+    // attestationVersion
+    DerElement::read_universal(&mut fields, DerElement::INTEGER)
+        .context("In key_parameters_from_attestation_extension: attestationVersion.")?;
+    let attestation_level = DerElement::read_universal(&mut fields, DerElement::ENUMERATED)
+        .and_then(|e| e.to_i64())
+        .context("In key_parameters_from_attestation_extension: attestationSecurityLevel.")?;
+    // keyMintVersion, keyMintSecurityLevel, attestationChallenge, and uniqueId
+    for number in [
+        DerElement::INTEGER,
+        DerElement::ENUMERATED,
+        DerElement::OCTET_STRING,
+        DerElement::OCTET_STRING,
+    ] {
+        DerElement::read_universal(&mut fields, number)
+            .context("In key_parameters_from_attestation_extension: Malformed KeyDescription.")?;
+    }
+    let hw_level = SecurityLevel(
+        attestation_level
+            .try_into()
+            .map_err(|_| KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+            .context("In key_parameters_from_attestation_extension: Invalid security level.")?,
+    );
+
+    let mut result = vec![];
+    for level in [SecurityLevel::SOFTWARE, hw_level] {
+        let list = DerElement::read_universal(&mut fields, DerElement::SEQUENCE)
+            .context("In key_parameters_from_attestation_extension: Expected AuthorizationList.")?;
+        let mut entries = list.content;
+        while !entries.is_empty() {
+            let entry = DerElement::read(&mut entries)
+                .context("In key_parameters_from_attestation_extension.")?;
+            let values = KeyParameterValue::from_authorization_list_entry(&entry)
+                .context("In key_parameters_from_attestation_extension.")?;
+            result.extend(values.into_iter().map(|v| KeyParameter::new(v, level)));
+        }
+    }
+    Ok(result)
+}
+
+impl KeyParameterValue {
+    /// Returns the RFC 4514 string representation of the DER encoded X.509 distinguished name
+    /// of a CertificateSubject, e.g., `CN=test,O=Android,C=US`, as produced by the Display
+    /// implementation of `x509_cert::name::Name`. Fails with
+    /// KeyParameterError::InvalidCertificateSubject if the DER is malformed or contains an
+    /// empty RelativeDistinguishedName, and with ResponseCode::INVALID_ARGUMENT for other
+    /// variants.
+    pub fn certificate_subject_to_string(&self) -> Result<String> {
+        let Self::CertificateSubject(der) = self else {
+            return Err(KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT)).with_context(|| {
+                format!("In certificate_subject_to_string: Not a subject: {:?}.", self.get_tag())
+            });
+        };
+        let name = x509_cert::name::Name::from_der(der).map_err(|e| {
+            anyhow::Error::new(KeyParameterError::InvalidCertificateSubject)
+                .context(format!("In certificate_subject_to_string: {}", e))
+        })?;
+        if name.0.iter().any(|rdn| rdn.0.is_empty()) {
+            return Err(KeyParameterError::InvalidCertificateSubject)
+                .context("In certificate_subject_to_string: Empty RelativeDistinguishedName.");
+        }
+        Ok(name.to_string())
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The attestation_tests module checks that the authorization lists of the Android key
+//! attestation extension are decoded into the expected key parameters.
+
+use super::attestation::KEY_ATTESTATION_OID;
+use super::*;
+use base64::Engine;
+
+/// KeyDescription of a TEE attested EC P-256 signing key. It has an unknown tag [9999] in its
+/// hardwareEnforced list.
+const KEY_DESCRIPTION: &str = "\
+    MIHsAgIBLAoBAQICASwKAQEECWNoYWxsZW5nZQQAMCG/hT0IAgYBjMJR9AC/hUURBA9jb20uZXhhbXBsZS5hcHAwgauh\
+    CDEGAgECAgEDogMCAQOjBAICAQClBTEDAgEEqgMCAQG/g3cCBQC/hT4DAgEAv4VATDBKBCABAQEBAQEBAQEBAQEBAQEB\
+    AQEBAQEBAQEBAQEBAQEBAQEB/woBAAQgAgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAgK/hUEFAgMCIuC/hUIF\
+    AgMDFqG/hU4GAgQBNNbpv4VPBgIEATTW6b/ODwMCAQE=";
+
+/// DER encoding of the RootOfTrust in KEY_DESCRIPTION.
+const ROOT_OF_TRUST: &str =
+    "304a04200101010101010101010101010101010101010101010101010101010101010101\
+    0101ff0a010004200202020202020202020202020202020202020202020202020202020202020202";
+
+fn decode(s: &str) -> Vec<u8> {
+    base64::engine::general_purpose::STANDARD.decode(s).unwrap()
+}
+
+#[test]
+fn decode_key_description() {
+    let params = key_parameters_from_attestation_extension(&decode(KEY_DESCRIPTION)).unwrap();
+    let sw = SecurityLevel::SOFTWARE;
+    let tee = SecurityLevel::TRUSTED_ENVIRONMENT;
+    assert_eq!(
+        params,
+        vec![
+            KeyParameter::new(KeyParameterValue::CreationDateTime(1704067200000), sw),
+            KeyParameter::new(
+                KeyParameterValue::AttestationApplicationID(b"com.example.app".to_vec()),
+                sw
+            ),
+            KeyParameter::new(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN), tee),
+            KeyParameter::new(KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY), tee),
+            KeyParameter::new(KeyParameterValue::Algorithm(Algorithm::EC), tee),
+            KeyParameter::new(KeyParameterValue::KeySize(256), tee),
+            KeyParameter::new(KeyParameterValue::Digest(Digest::SHA_2_256), tee),
+            KeyParameter::new(KeyParameterValue::EcCurve(EcCurve::P_256), tee),
+            KeyParameter::new(KeyParameterValue::NoAuthRequired, tee),
+            KeyParameter::new(KeyParameterValue::KeyOrigin(KeyOrigin::GENERATED), tee),
+            KeyParameter::new(
+                KeyParameterValue::RootOfTrust(hex::decode(ROOT_OF_TRUST).unwrap()),
+                tee
+            ),
+            KeyParameter::new(KeyParameterValue::OSVersion(140000), tee),
+            KeyParameter::new(KeyParameterValue::OSPatchLevel(202401), tee),
+            KeyParameter::new(KeyParameterValue::VendorPatchLevel(20240105), tee),
+            KeyParameter::new(KeyParameterValue::BootPatchLevel(20240105), tee),
+        ]
+    );
+}
+
+#[test]
+fn from_certificate_extension_single_entry() {
+    assert_eq!(
+        KeyParameterValue::from_certificate_extension(KEY_ATTESTATION_OID, &decode("ogMCAQM="))
+            .unwrap(),
+        Some(KeyParameterValue::Algorithm(Algorithm::EC))
+    );
+    assert_eq!(
+        KeyParameterValue::from_certificate_extension(
+            KEY_ATTESTATION_OID,
+            &hex::decode("bf853d080206018cc251f400").unwrap()
+        )
+        .unwrap(),
+        Some(KeyParameterValue::CreationDateTime(1704067200000))
+    );
+    // Unknown tags and other extensions are not key parameters.
+    assert_eq!(
+        KeyParameterValue::from_certificate_extension(
+            KEY_ATTESTATION_OID,
+            &hex::decode("bfce0f03020101").unwrap()
+        )
+        .unwrap(),
+        None
+    );
+    // OID 2.5.29.19 of the basic constraints extension.
+    assert_eq!(
+        KeyParameterValue::from_certificate_extension(&[0x55, 0x1D, 0x13], &decode("ogMCAQM="))
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn from_certificate_extension_errors() {
+    let check = |entry: &str, expected: ResponseCode| {
+        let err = KeyParameterValue::from_certificate_extension(
+            KEY_ATTESTATION_OID,
+            &hex::decode(entry).unwrap(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.root_cause().downcast_ref::<KeystoreError>(),
+            Some(&KeystoreError::Rc(expected)),
+            "{}",
+            entry
+        );
+    };
+    // Purpose SET OF with two values.
+    check("a1083106020102020103", ResponseCode::INVALID_ARGUMENT);
+    // Algorithm encoded as OCTET STRING.
+    check("a203040103", ResponseCode::VALUE_CORRUPTED);
+    // Truncated content.
+    check("a2030201", ResponseCode::VALUE_CORRUPTED);
+    // Trailing data.
+    check("a20302010300", ResponseCode::VALUE_CORRUPTED);
+    // Implicitly tagged entry.
+    check("820103", ResponseCode::VALUE_CORRUPTED);
+}
+
+#[test]
+fn from_certificate_extension_rejects_ber_encodings() {
+    let check = |entry: &str| {
+        let err = KeyParameterValue::from_certificate_extension(
+            KEY_ATTESTATION_OID,
+            &hex::decode(entry).unwrap(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.root_cause().downcast_ref::<KeystoreError>(),
+            Some(&KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED)),
+            "{}",
+            entry
+        );
+    };
+    // The DER encoding of Algorithm EC is accepted.
+    assert!(KeyParameterValue::from_certificate_extension(
+        KEY_ATTESTATION_OID,
+        &hex::decode("a203020103").unwrap()
+    )
+    .is_ok());
+
+    // Indefinite length.
+    check("a2800201030000");
+    // Long form length for a short content.
+    check("a28103020103");
+    // Long form length with a leading zero byte.
+    check("a282000303020103");
+    // INTEGER with a redundant leading zero byte.
+    check("a20402020003");
+    // INTEGER with a redundant leading 0xFF byte.
+    check("a2040202ff83");
+    // High tag number form for a tag number below 31.
+    check("bf0203020103");
+    // High tag number with a leading 0x80 byte.
+    check("bf80853d080206018cc251f400");
+    // INTEGER with the constructed bit set.
+    check("a203220103");
+}
+
+#[test]
+fn decode_key_description_errors() {
+    let mut description = decode(KEY_DESCRIPTION);
+    description.truncate(description.len() - 1);
+    assert!(key_parameters_from_attestation_extension(&description).is_err());
+    assert!(key_parameters_from_attestation_extension(&[]).is_err());
+    assert!(key_parameters_from_attestation_extension(&hex::decode("3000").unwrap()).is_err());
+}
+
+#[test]
+fn certificate_subject_to_string() {
+    let subject = |der: &str| KeyParameterValue::CertificateSubject(hex::decode(der).unwrap());
+
+    // C=US, O=Android, CN=test in encoding order.
+    let name = subject(
+        "302e310b30090603550406130255533110300e060355040a0c07416e64726f6964310d300b0603550403\
+        0c0474657374",
+    );
+    assert_eq!(name.certificate_subject_to_string().unwrap(), "CN=test,O=Android,C=US");
+
+    // An attribute of unknown type, followed by a multi valued RDN with a CN encoded as
+    // BMPString, which has no string representation, and an OU that needs escaping.
+    let name = subject(
+        "302e310a300806032a03040c01763120300d06035504031e060020007800e9300f060355040b0c082361\
+        2c20622b6320",
+    );
+    assert_eq!(
+        name.certificate_subject_to_string().unwrap(),
+        "2.5.4.3=#1e060020007800e9+OU=\\#a\\, b\\+c\\ ,1.2.3.4=#0c0176"
+    );
+
+    assert_eq!(subject("3000").certificate_subject_to_string().unwrap(), "");
+}
+
+#[test]
+fn certificate_subject_to_string_errors() {
+    let check = |der: &str| {
+        let err = KeyParameterValue::CertificateSubject(hex::decode(der).unwrap())
+            .certificate_subject_to_string()
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().downcast_ref::<KeyParameterError>(),
+            Some(&KeyParameterError::InvalidCertificateSubject),
+            "{}",
+            der
+        );
+    };
+    // Empty input.
+    check("");
+    // Truncated RDNSequence.
+    check("300d310b30090603550406130255");
+    // Trailing data.
+    check("300d310b3009060355040613025553ff");
+    // RDN encoded as SEQUENCE.
+    check("300d300b3009060355040613025553");
+    // Empty RDN.
+    check("30023100");
+    // Attribute type encoded as INTEGER.
+    check("300d310b3009020355040613025553");
+    // Truncated OID.
+    check("300d310b3009060355048613025553");
+    // OID arc with a leading 0x80 byte.
+    check("300e310c300a06048055040613025553");
+    // Name encoded with an indefinite length.
+    check("3080310b30090603550406130255530000");
+
+    let err =
+        KeyParameterValue::CertificateSerial(vec![1]).certificate_subject_to_string().unwrap_err();
+    assert_eq!(
+        err.root_cause().downcast_ref::<KeystoreError>(),
+        Some(&KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT))
+    );
+}
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#![deny(clippy::derivable_impls)]
+
 use crate::key_parameter::*;
 
 // Test basic functionality of KeyParameter.
@@ -26,3 +28,1842 @@ fn test_key_parameter() {
 
     assert_eq!(*key_parameter.security_level(), SecurityLevel::STRONGBOX);
 }
+
+/// Tags that select the type of a key, evaluated at compile time.
+const KEY_TYPE_TAGS: [Tag; 3] = [
+    KeyParameterValue::Algorithm(Algorithm::EC).static_tag(),
+    KeyParameterValue::KeySize(256).static_tag(),
+    KeyParameterValue::EcCurve(EcCurve::P_256).static_tag(),
+];
+
+#[test]
+fn test_static_tag() {
+    assert_eq!(KEY_TYPE_TAGS, [Tag::ALGORITHM, Tag::KEY_SIZE, Tag::EC_CURVE]);
+    for param in KeyParameterValue::make_key_parameter_defaults_vector() {
+        assert_eq!(param.key_parameter_value().static_tag(), param.get_tag());
+    }
+    let unknown = KeyParameterValue::Unknown {
+        tag: Tag(TagType::UINT.0 | 0xfff),
+        value: KmKeyParameterValue::Integer(1),
+    };
+    assert_eq!(unknown.static_tag(), Tag(TagType::UINT.0 | 0xfff));
+}
+
+#[test]
+fn test_key_parameter_default() {
+    let kp = KeyParameter::default();
+    assert_eq!(kp.get_tag(), Tag::INVALID);
+    assert_eq!(*kp.key_parameter_value(), KeyParameterValue::Invalid(0));
+    assert_eq!(*kp.security_level(), SecurityLevel::SOFTWARE);
+    assert_eq!(KeyParameterValue::default(), KeyParameterValue::Invalid(0));
+    assert_eq!(KmKeyParameter::from(KeyParameterValue::default()), KmKeyParameter::default());
+}
+
+#[test]
+fn test_key_parameter_value_display() {
+    assert_eq!(KeyParameterValue::Invalid(0).to_string(), "INVALID=0");
+    assert_eq!(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN).to_string(), "PURPOSE=SIGN");
+    assert_eq!(KeyParameterValue::Algorithm(Algorithm::AES).to_string(), "ALGORITHM=AES");
+    assert_eq!(KeyParameterValue::Digest(Digest::SHA_2_256).to_string(), "DIGEST=SHA_2_256");
+    assert_eq!(KeyParameterValue::KeySize(2048).to_string(), "KEY_SIZE=2048");
+    assert_eq!(
+        KeyParameterValue::RSAPublicExponent(65537).to_string(),
+        "RSA_PUBLIC_EXPONENT=65537"
+    );
+    assert_eq!(KeyParameterValue::CallerNonce.to_string(), "CALLER_NONCE=true");
+    assert_eq!(
+        KeyParameterValue::ActiveDateTime(1704067200000).to_string(),
+        "ACTIVE_DATETIME=2024-01-01T00:00:00Z"
+    );
+    assert_eq!(
+        KeyParameterValue::CreationDateTime(951782400123).to_string(),
+        "CREATION_DATETIME=2000-02-29T00:00:00.123Z"
+    );
+    assert_eq!(
+        KeyParameterValue::UsageExpireDateTime(-1).to_string(),
+        "USAGE_EXPIRE_DATETIME=1969-12-31T23:59:59.999Z"
+    );
+    assert_eq!(
+        KeyParameterValue::CertificateSubject(vec![1, 2, 3]).to_string(),
+        "CERTIFICATE_SUBJECT=3 bytes"
+    );
+    assert_eq!(
+        KeyParameterValue::Unknown { tag: Tag(12345), value: KmKeyParameterValue::Integer(7) }
+            .to_string(),
+        "12345=7"
+    );
+}
+
+#[test]
+fn test_try_from_key_parameter_value() {
+    let alg: Algorithm = KeyParameterValue::Algorithm(Algorithm::EC).try_into().unwrap();
+    assert_eq!(alg, Algorithm::EC);
+    let mode: BlockMode = KeyParameterValue::BlockMode(BlockMode::GCM).try_into().unwrap();
+    assert_eq!(mode, BlockMode::GCM);
+    let digest: Digest = KeyParameterValue::Digest(Digest::SHA_2_256).try_into().unwrap();
+    assert_eq!(digest, Digest::SHA_2_256);
+    let curve: EcCurve = KeyParameterValue::EcCurve(EcCurve::P_256).try_into().unwrap();
+    assert_eq!(curve, EcCurve::P_256);
+    let auth_type: HardwareAuthenticatorType =
+        KeyParameterValue::HardwareAuthenticatorType(HardwareAuthenticatorType::FINGERPRINT)
+            .try_into()
+            .unwrap();
+    assert_eq!(auth_type, HardwareAuthenticatorType::FINGERPRINT);
+    let origin: KeyOrigin = KeyParameterValue::KeyOrigin(KeyOrigin::IMPORTED).try_into().unwrap();
+    assert_eq!(origin, KeyOrigin::IMPORTED);
+    let purpose: KeyPurpose = KeyParameterValue::KeyPurpose(KeyPurpose::SIGN).try_into().unwrap();
+    assert_eq!(purpose, KeyPurpose::SIGN);
+    let padding: PaddingMode =
+        KeyParameterValue::PaddingMode(PaddingMode::RSA_PSS).try_into().unwrap();
+    assert_eq!(padding, PaddingMode::RSA_PSS);
+}
+
+#[test]
+fn test_try_from_key_parameter_value_wrong_variant() {
+    assert_eq!(
+        Algorithm::try_from(KeyParameterValue::KeySize(256)),
+        Err(KeyParameterValueError::WrongVariant {
+            expected_tag: Tag::ALGORITHM,
+            actual_tag: Tag::KEY_SIZE
+        })
+    );
+    assert_eq!(
+        Digest::try_from(KeyParameterValue::RsaOaepMgfDigest(Digest::SHA1)),
+        Err(KeyParameterValueError::WrongVariant {
+            expected_tag: Tag::DIGEST,
+            actual_tag: Tag::RSA_OAEP_MGF_DIGEST
+        })
+    );
+}
+
+#[test]
+fn test_is_sensitive() {
+    assert!(KeyParameterValue::ApplicationID(vec![1]).is_sensitive());
+    assert!(KeyParameterValue::ApplicationData(vec![1]).is_sensitive());
+    assert!(KeyParameterValue::Nonce(vec![1]).is_sensitive());
+    assert!(KeyParameterValue::AssociatedData(vec![1]).is_sensitive());
+    assert!(KeyParameterValue::ConfirmationToken(vec![1]).is_sensitive());
+    assert!(KeyParameterValue::RootOfTrust(vec![1]).is_sensitive());
+    assert!(!KeyParameterValue::KeySize(256).is_sensitive());
+    assert!(!KeyParameterValue::Algorithm(Algorithm::AES).is_sensitive());
+    assert!(KeyParameterValue::IncludeUniqueID.is_sensitive());
+    assert!(KeyParameterValue::UniqueID(vec![1]).is_sensitive());
+    assert!(KeyParameterValue::AttestationChallenge(vec![1]).is_sensitive());
+    assert!(KeyParameterValue::AttestationIdBrand(vec![1]).is_sensitive());
+    assert!(!KeyParameterValue::MacLength(128).is_sensitive());
+    assert!(KeyParameterValue::ResetSinceIdRotation.is_sensitive());
+    assert!(!KeyParameterValue::CreationDateTime(0).is_sensitive());
+    assert!(!KeyParameterValue::UserSecureID(1).is_sensitive());
+}
+
+#[test]
+fn test_display_redacts_sensitive_values() {
+    for value in [
+        KeyParameterValue::ApplicationID(vec![1, 2, 3]),
+        KeyParameterValue::ApplicationData(vec![1, 2, 3]),
+        KeyParameterValue::ConfirmationToken(vec![1, 2, 3]),
+        KeyParameterValue::RootOfTrust(vec![1, 2, 3]),
+    ] {
+        assert!(value.is_sensitive());
+        assert_eq!(value.to_string(), format!("{:?}=<redacted>", value.get_tag()));
+    }
+    assert_eq!(KeyParameterValue::ApplicationID(vec![1]).to_string(), "APPLICATION_ID=<redacted>");
+    // The presence of a boolean parameter is not secret.
+    assert_eq!(KeyParameterValue::IncludeUniqueID.to_string(), "INCLUDE_UNIQUE_ID=true");
+    assert_eq!(KeyParameterValue::MacLength(128).to_string(), "MAC_LENGTH=128");
+}
+
+#[test]
+fn test_redacted_debug() {
+    assert_eq!(
+        format!("{:?}", KeyParameterValue::ApplicationID(vec![0xaa; 32]).redacted()),
+        "ApplicationID(<redacted, 32 bytes>)"
+    );
+    assert_eq!(
+        format!("{:?}", KeyParameterValue::Nonce(vec![0xaa; 12]).redacted()),
+        "Nonce(<redacted, 12 bytes>)"
+    );
+    assert_eq!(format!("{:?}", KeyParameterValue::IncludeUniqueID.redacted()), "IncludeUniqueID");
+}
+
+#[test]
+fn test_redacted_debug_leaves_non_sensitive_parameters_intact() {
+    for value in [
+        KeyParameterValue::KeySize(256),
+        KeyParameterValue::Algorithm(Algorithm::EC),
+        KeyParameterValue::CreationDateTime(1704067200000),
+        KeyParameterValue::MacLength(128),
+    ] {
+        assert_eq!(format!("{:?}", value.redacted()), format!("{:?}", value));
+    }
+}
+
+#[test]
+fn test_key_parameter_builder() {
+    let params = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .algorithm(Algorithm::RSA)
+        .key_size(2048)
+        .purpose(KeyPurpose::SIGN)
+        .digest(Digest::SHA_2_256)
+        .no_auth_required()
+        .at_level(SecurityLevel::KEYSTORE)
+        .creation_datetime(1704067200000)
+        .build();
+    assert_eq!(
+        params,
+        vec![
+            KeyParameter::new(
+                KeyParameterValue::Algorithm(Algorithm::RSA),
+                SecurityLevel::TRUSTED_ENVIRONMENT
+            ),
+            KeyParameter::new(KeyParameterValue::KeySize(2048), SecurityLevel::TRUSTED_ENVIRONMENT),
+            KeyParameter::new(
+                KeyParameterValue::KeyPurpose(KeyPurpose::SIGN),
+                SecurityLevel::TRUSTED_ENVIRONMENT
+            ),
+            KeyParameter::new(
+                KeyParameterValue::Digest(Digest::SHA_2_256),
+                SecurityLevel::TRUSTED_ENVIRONMENT
+            ),
+            KeyParameter::new(
+                KeyParameterValue::NoAuthRequired,
+                SecurityLevel::TRUSTED_ENVIRONMENT
+            ),
+            KeyParameter::new(
+                KeyParameterValue::CreationDateTime(1704067200000),
+                SecurityLevel::KEYSTORE
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_key_parameter_builder_empty() {
+    assert!(KeyParameterBuilder::new(SecurityLevel::STRONGBOX).build().is_empty());
+}
+
+#[test]
+fn test_key_parameter_collection_lookup() {
+    let collection: KeyParameterCollection = KeyParameterBuilder::new(SecurityLevel::STRONGBOX)
+        .purpose(KeyPurpose::SIGN)
+        .algorithm(Algorithm::EC)
+        .purpose(KeyPurpose::VERIFY)
+        .ec_curve(EcCurve::P_256)
+        .purpose(KeyPurpose::AGREE_KEY)
+        .build()
+        .into();
+
+    assert_eq!(collection.len(), 5);
+    assert!(collection.contains_tag(Tag::ALGORITHM));
+    assert!(!collection.contains_tag(Tag::KEY_SIZE));
+    assert_eq!(
+        collection.get_first(Tag::EC_CURVE).map(KeyParameter::key_parameter_value),
+        Some(&KeyParameterValue::EcCurve(EcCurve::P_256))
+    );
+    assert_eq!(collection.get_first(Tag::KEY_SIZE), None);
+    assert!(collection.get_all(Tag::KEY_SIZE).is_empty());
+
+    let purposes: Vec<&KeyParameterValue> =
+        collection.get_all(Tag::PURPOSE).iter().map(KeyParameter::key_parameter_value).collect();
+    assert_eq!(
+        purposes,
+        vec![
+            &KeyParameterValue::KeyPurpose(KeyPurpose::SIGN),
+            &KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY),
+            &KeyParameterValue::KeyPurpose(KeyPurpose::AGREE_KEY),
+        ]
+    );
+    assert_eq!(
+        collection.get_first(Tag::PURPOSE).map(KeyParameter::key_parameter_value),
+        Some(&KeyParameterValue::KeyPurpose(KeyPurpose::SIGN))
+    );
+}
+
+#[test]
+fn test_key_parameter_collection_into_iter() {
+    let params = KeyParameterBuilder::new(SecurityLevel::STRONGBOX)
+        .purpose(KeyPurpose::SIGN)
+        .algorithm(Algorithm::EC)
+        .purpose(KeyPurpose::VERIFY)
+        .build();
+    let collection = KeyParameterCollection::from(params.clone());
+    assert_eq!((&collection).into_iter().count(), 3);
+    let tags: Vec<Tag> = collection.into_iter().map(|p| p.get_tag()).collect();
+    assert_eq!(tags, vec![Tag::PURPOSE, Tag::PURPOSE, Tag::ALGORITHM]);
+    assert!(KeyParameterCollection::from(Vec::new()).is_empty());
+}
+
+#[test]
+fn test_boolean_flags() {
+    let tee = |v| KeyParameter::new(v, SecurityLevel::TRUSTED_ENVIRONMENT);
+    let flags = boolean_flags(&[
+        tee(KeyParameterValue::Algorithm(Algorithm::AES)),
+        tee(KeyParameterValue::CallerNonce),
+        tee(KeyParameterValue::NoAuthRequired),
+    ]);
+    assert!(flags.contains(&KeyParameterValue::CallerNonce));
+    assert!(flags.contains(&KeyParameterValue::NoAuthRequired));
+    assert!(!flags.contains(&KeyParameterValue::RollbackResistance));
+    assert!(!flags.contains(&KeyParameterValue::Algorithm(Algorithm::AES)));
+    assert_eq!(boolean_flags(&[]), BoolTagSet::default());
+
+    // Every boolean variant has its own bit.
+    let bool_params: Vec<KeyParameter> = KeyParameterValue::make_key_parameter_defaults_vector()
+        .into_iter()
+        .filter(|p| p.value.bool_value().is_some())
+        .collect();
+    let bits: HashSet<u32> = bool_params.iter().map(|p| p.value.bool_flag_bit().unwrap()).collect();
+    assert_eq!(bits.len(), bool_params.len());
+    assert!(bits.iter().all(|bit| *bit < u64::BITS));
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_typed_accessors() {
+    let key_size = KeyParameterValue::KeySize(2048);
+    assert_eq!(key_size.as_i32(), Some(2048));
+    assert_eq!(key_size.as_i64(), None);
+    assert_eq!(key_size.as_blob(), None);
+    assert_eq!(key_size.get_primitive(), Some(Primitive::I32(2048)));
+
+    let algorithm = KeyParameterValue::Algorithm(Algorithm::EC);
+    assert_eq!(algorithm.as_i32(), Some(Algorithm::EC.0));
+
+    let exponent = KeyParameterValue::RSAPublicExponent(65537);
+    assert_eq!(exponent.as_i64(), Some(65537));
+    assert_eq!(exponent.as_i32(), None);
+
+    let app_id = KeyParameterValue::ApplicationID(vec![1, 2, 3]);
+    assert_eq!(app_id.as_blob(), Some(&[1u8, 2, 3][..]));
+    assert_eq!(app_id.as_i32(), None);
+    assert_eq!(app_id.get_primitive(), Some(Primitive::Vec(vec![1, 2, 3])));
+
+    assert_eq!(KeyParameterValue::CallerNonce.get_primitive(), None);
+    assert_eq!(KeyParameterValue::Invalid(7).get_primitive(), Some(Primitive::I32(7)));
+    assert_eq!(KeyParameterValue::CallerNonce.as_i32(), None);
+
+    let unknown = KeyParameterValue::Unknown {
+        tag: Tag(TagType::BYTES.0 | 0xfff),
+        value: KmKeyParameterValue::Blob(vec![9]),
+    };
+    assert_eq!(unknown.as_blob(), Some(&[9u8][..]));
+}
+
+#[test]
+fn test_try_from_key_parameter_value_into_primitive() {
+    assert_eq!(i32::try_from(&KeyParameterValue::KeySize(2048)), Ok(2048));
+    assert_eq!(i32::try_from(&KeyParameterValue::Algorithm(Algorithm::EC)), Ok(Algorithm::EC.0));
+    assert_eq!(i64::try_from(&KeyParameterValue::RSAPublicExponent(65537)), Ok(65537));
+    assert_eq!(i64::try_from(&KeyParameterValue::ActiveDateTime(1704067200000)), Ok(1704067200000));
+    assert_eq!(
+        Vec::<u8>::try_from(&KeyParameterValue::ApplicationID(vec![1, 2, 3])),
+        Ok(vec![1, 2, 3])
+    );
+    let unknown = KeyParameterValue::Unknown {
+        tag: Tag(TagType::BYTES.0 | 0xfff),
+        value: KmKeyParameterValue::Blob(vec![9]),
+    };
+    assert_eq!(Vec::<u8>::try_from(&unknown), Ok(vec![9]));
+
+    assert_eq!(
+        i32::try_from(&KeyParameterValue::RSAPublicExponent(3)),
+        Err(PrimitiveError::TypeMismatch {
+            tag: Tag::RSA_PUBLIC_EXPONENT,
+            expected: "i32",
+            got: "i64"
+        })
+    );
+    assert_eq!(
+        i64::try_from(&KeyParameterValue::ApplicationID(vec![1])),
+        Err(PrimitiveError::TypeMismatch {
+            tag: Tag::APPLICATION_ID,
+            expected: "i64",
+            got: "Vec<u8>"
+        })
+    );
+    assert_eq!(
+        Vec::<u8>::try_from(&KeyParameterValue::CallerNonce),
+        Err(PrimitiveError::TypeMismatch {
+            tag: Tag::CALLER_NONCE,
+            expected: "Vec<u8>",
+            got: "none"
+        })
+    );
+    assert_eq!(i32::try_from(&KeyParameterValue::Invalid(7)), Err(PrimitiveError::UnknownTag));
+    assert_eq!(i64::try_from(&KeyParameterValue::Invalid(7)), Err(PrimitiveError::UnknownTag));
+    assert_eq!(
+        Vec::<u8>::try_from(&KeyParameterValue::Invalid(7)),
+        Err(PrimitiveError::UnknownTag)
+    );
+}
+
+#[test]
+fn test_field_value_accessors() {
+    let key_size = KeyParameterValue::KeySize(2048);
+    assert_eq!(key_size.integer_value(), Some(2048));
+    assert_eq!(key_size.long_integer_value(), None);
+    assert_eq!(key_size.blob_value(), None);
+    assert_eq!(key_size.bool_value(), None);
+
+    let exponent = KeyParameterValue::RSAPublicExponent(65537);
+    assert_eq!(exponent.long_integer_value(), Some(65537));
+    assert_eq!(exponent.integer_value(), None);
+
+    let root_of_trust = KeyParameterValue::RootOfTrust(vec![1, 2, 3]);
+    assert_eq!(root_of_trust.blob_value(), Some(&[1u8, 2, 3][..]));
+    assert_eq!(root_of_trust.integer_value(), None);
+    assert_eq!(root_of_trust.bool_value(), None);
+
+    assert_eq!(KeyParameterValue::CallerNonce.bool_value(), Some(true));
+    assert_eq!(KeyParameterValue::CallerNonce.integer_value(), None);
+    assert_eq!(KeyParameterValue::CallerNonce.blob_value(), None);
+
+    // Enum and DateTime values are not stored in the Integer or LongInteger fields.
+    assert_eq!(KeyParameterValue::Algorithm(Algorithm::EC).integer_value(), None);
+    assert_eq!(KeyParameterValue::CreationDateTime(1).long_integer_value(), None);
+    assert_eq!(KeyParameterValue::Invalid(0).integer_value(), None);
+    assert_eq!(KeyParameterValue::Invalid(0).bool_value(), None);
+
+    let unknown = KeyParameterValue::Unknown {
+        tag: Tag(TagType::UINT.0 | 0xfff),
+        value: KmKeyParameterValue::Integer(7),
+    };
+    assert_eq!(unknown.integer_value(), Some(7));
+    assert_eq!(unknown.blob_value(), None);
+}
+
+#[test]
+fn test_hex_string_round_trip() {
+    let value = KeyParameterValue::from_hex_string(Tag::APPLICATION_ID, "deadBEEF00").unwrap();
+    assert_eq!(value, KeyParameterValue::ApplicationID(vec![0xde, 0xad, 0xbe, 0xef, 0x00]));
+    assert_eq!(value.to_hex_string().as_deref(), Some("deadbeef00"));
+
+    let empty = KeyParameterValue::from_hex_string(Tag::ATTESTATION_CHALLENGE, "").unwrap();
+    assert_eq!(empty, KeyParameterValue::AttestationChallenge(vec![]));
+    assert_eq!(empty.to_hex_string().as_deref(), Some(""));
+
+    assert_eq!(KeyParameterValue::KeySize(256).to_hex_string(), None);
+}
+
+#[test]
+fn test_hex_string_rejects_invalid_input() {
+    for (tag, hex) in [
+        (Tag::APPLICATION_ID, "abc"),
+        (Tag::APPLICATION_ID, "zz"),
+        (Tag::KEY_SIZE, "0100"),
+        (Tag::CALLER_NONCE, ""),
+        (Tag(TagType::BYTES.0 | 0xfff), "00"),
+    ] {
+        let err = KeyParameterValue::from_hex_string(tag, hex).unwrap_err();
+        assert_eq!(
+            err.root_cause().downcast_ref::<KeystoreError>(),
+            Some(&KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT)),
+            "{:?} {:?}",
+            tag,
+            hex
+        );
+    }
+}
+
+#[test]
+fn test_certificate_serial_round_trip() {
+    for (serial, der) in [
+        (1, vec![0x01]),
+        (0x7f, vec![0x7f]),
+        (0x80, vec![0x00, 0x80]),
+        (0x0100, vec![0x01, 0x00]),
+        (0x12_3456, vec![0x12, 0x34, 0x56]),
+        (0x80_0000, vec![0x00, 0x80, 0x00, 0x00]),
+        (i64::MAX, vec![0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]),
+    ] {
+        assert_eq!(KeyParameterValue::encode_certificate_serial(serial).unwrap(), der);
+        assert_eq!(KeyParameterValue::decode_certificate_serial(&der).unwrap(), serial);
+    }
+
+    for serial in [0, -1, i64::MIN] {
+        let err = KeyParameterValue::encode_certificate_serial(serial).unwrap_err();
+        assert_eq!(
+            err.root_cause().downcast_ref::<KeystoreError>(),
+            Some(&KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT)),
+            "{}",
+            serial
+        );
+    }
+
+    for der in [
+        &[][..],
+        &[0x00],
+        &[0x80],
+        &[0xff, 0x01],
+        &[0x00, 0x01],
+        &[0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        &[0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    ] {
+        let err = KeyParameterValue::decode_certificate_serial(der).unwrap_err();
+        assert_eq!(
+            err.root_cause().downcast_ref::<KeystoreError>(),
+            Some(&KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED)),
+            "{:?}",
+            der
+        );
+    }
+}
+
+#[test]
+fn test_field_value_accessors_match_field() {
+    for param in KeyParameterValue::make_key_parameter_defaults_vector() {
+        let value = param.key_parameter_value();
+        let km_param: KmKeyParameter = value.clone().into();
+        assert_eq!(
+            value.integer_value().is_some(),
+            matches!(km_param.value, KmKeyParameterValue::Integer(_))
+        );
+        assert_eq!(
+            value.long_integer_value().is_some(),
+            matches!(km_param.value, KmKeyParameterValue::LongInteger(_))
+        );
+        assert_eq!(
+            value.blob_value().is_some(),
+            matches!(km_param.value, KmKeyParameterValue::Blob(_))
+        );
+        assert_eq!(
+            value.bool_value().is_some(),
+            matches!(km_param.value, KmKeyParameterValue::BoolValue(_))
+        );
+    }
+}
+
+#[test]
+fn test_key_parameter_diff_no_op() {
+    let params = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .algorithm(Algorithm::EC)
+        .purpose(KeyPurpose::SIGN)
+        .purpose(KeyPurpose::VERIFY)
+        .build();
+    let mut reordered = params.clone();
+    reordered.reverse();
+    let diff = KeyParameter::diff(&params, &reordered);
+    assert!(diff.is_empty());
+    assert_eq!(diff.audit_summary(), "no changes");
+}
+
+#[test]
+fn test_key_parameter_diff_added_removed() {
+    let old = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .purpose(KeyPurpose::SIGN)
+        .purpose(KeyPurpose::VERIFY)
+        .caller_nonce()
+        .build();
+    let new = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .purpose(KeyPurpose::SIGN)
+        .purpose(KeyPurpose::SIGN)
+        .key_size(256)
+        .build();
+    let diff = KeyParameter::diff(&old, &new);
+    assert_eq!(
+        diff.added,
+        vec![
+            KeyParameter::new(
+                KeyParameterValue::KeyPurpose(KeyPurpose::SIGN),
+                SecurityLevel::TRUSTED_ENVIRONMENT
+            ),
+            KeyParameter::new(KeyParameterValue::KeySize(256), SecurityLevel::TRUSTED_ENVIRONMENT),
+        ]
+    );
+    assert_eq!(
+        diff.removed,
+        vec![
+            KeyParameter::new(
+                KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY),
+                SecurityLevel::TRUSTED_ENVIRONMENT
+            ),
+            KeyParameter::new(KeyParameterValue::CallerNonce, SecurityLevel::TRUSTED_ENVIRONMENT),
+        ]
+    );
+    assert!(diff.changed.is_empty());
+    assert_eq!(
+        diff.audit_summary(),
+        "added: [PURPOSE=SIGN@TRUSTED_ENVIRONMENT, KEY_SIZE=256@TRUSTED_ENVIRONMENT]; \
+         removed: [PURPOSE=VERIFY@TRUSTED_ENVIRONMENT, CALLER_NONCE=true@TRUSTED_ENVIRONMENT]"
+    );
+}
+
+#[test]
+fn test_key_parameter_diff_requested_vs_enforced() {
+    let requested = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .algorithm(Algorithm::EC)
+        .purpose(KeyPurpose::SIGN)
+        .value(KeyParameterValue::OSPatchLevel(202301))
+        .build();
+    let enforced = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .value(KeyParameterValue::UsageCountLimit(1))
+        .value(KeyParameterValue::OSPatchLevel(202401))
+        .purpose(KeyPurpose::SIGN)
+        .algorithm(Algorithm::EC)
+        .build();
+    let diff = KeyParameter::diff(&requested, &enforced);
+    assert_eq!(
+        diff.added,
+        vec![KeyParameter::new(
+            KeyParameterValue::UsageCountLimit(1),
+            SecurityLevel::TRUSTED_ENVIRONMENT
+        )]
+    );
+    assert!(diff.removed.is_empty());
+    assert_eq!(
+        diff.changed,
+        vec![(
+            KeyParameter::new(
+                KeyParameterValue::OSPatchLevel(202301),
+                SecurityLevel::TRUSTED_ENVIRONMENT
+            ),
+            KeyParameter::new(
+                KeyParameterValue::OSPatchLevel(202401),
+                SecurityLevel::TRUSTED_ENVIRONMENT
+            ),
+        )]
+    );
+
+    // The result does not depend on the order of either list.
+    let mut shuffled_requested = requested.clone();
+    shuffled_requested.reverse();
+    let mut shuffled_enforced = enforced.clone();
+    shuffled_enforced.rotate_left(2);
+    assert_eq!(KeyParameter::diff(&shuffled_requested, &shuffled_enforced), diff);
+}
+
+#[test]
+fn test_key_parameter_diff_changed() {
+    let old = KeyParameterBuilder::new(SecurityLevel::KEYSTORE)
+        .value(KeyParameterValue::OSPatchLevel(202301))
+        .key_size(256)
+        .build();
+    let new = KeyParameterBuilder::new(SecurityLevel::KEYSTORE)
+        .value(KeyParameterValue::OSPatchLevel(202401))
+        .at_level(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .key_size(256)
+        .build();
+    let diff = KeyParameter::diff(&old, &new);
+    assert!(diff.added.is_empty());
+    assert!(diff.removed.is_empty());
+    assert_eq!(diff.changed.len(), 2);
+    assert_eq!(diff.changed[1].0, old[0]);
+    assert_eq!(diff.changed[1].1, new[0]);
+    assert_eq!(
+        diff.audit_summary(),
+        "changed: [KEY_SIZE=256@KEYSTORE -> KEY_SIZE=256@TRUSTED_ENVIRONMENT, \
+         OS_PATCHLEVEL=202301@KEYSTORE -> OS_PATCHLEVEL=202401@KEYSTORE]"
+    );
+
+    #[cfg(feature = "serde")]
+    {
+        let mut serialized = Vec::new();
+        serde_cbor::to_writer(&mut serialized, &diff).expect("Failed to serialize diff.");
+        let deserialized: KeyParameterDiff =
+            serde_cbor::from_reader(serialized.as_slice()).expect("Failed to deserialize diff.");
+        assert_eq!(diff, deserialized);
+    }
+}
+
+#[test]
+fn test_find_tag_helpers() {
+    let params = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .algorithm(Algorithm::AES)
+        .block_mode(BlockMode::GCM)
+        .block_mode(BlockMode::CTR)
+        .key_size(128)
+        .build();
+
+    assert_eq!(
+        find_tag(&params, Tag::BLOCK_MODE).map(KeyParameter::key_parameter_value),
+        Some(&KeyParameterValue::BlockMode(BlockMode::GCM))
+    );
+    assert_eq!(find_tag(&params, Tag::DIGEST), None);
+
+    let modes: Vec<&KeyParameterValue> =
+        find_all_tags(&params, Tag::BLOCK_MODE).map(KeyParameter::key_parameter_value).collect();
+    assert_eq!(
+        modes,
+        vec![
+            &KeyParameterValue::BlockMode(BlockMode::GCM),
+            &KeyParameterValue::BlockMode(BlockMode::CTR)
+        ]
+    );
+    assert_eq!(find_all_tags(&params, Tag::PURPOSE).count(), 0);
+
+    assert!(contains_tag(&params, Tag::KEY_SIZE));
+    assert!(!contains_tag(&params, Tag::PURPOSE));
+    assert!(!contains_tag(&[], Tag::KEY_SIZE));
+}
+
+#[test]
+fn test_retain_storable() {
+    let tee = SecurityLevel::TRUSTED_ENVIRONMENT;
+    let params = vec![
+        KeyParameter::new(KeyParameterValue::Nonce(vec![1, 2, 3]), tee),
+        KeyParameter::new(KeyParameterValue::KeySize(256), tee),
+        KeyParameter::new(KeyParameterValue::ConfirmationToken(vec![4, 5]), tee),
+        KeyParameter::new(KeyParameterValue::ApplicationID(vec![6]), tee),
+    ];
+    assert!(!params[0].is_storable());
+    assert!(params[1].is_storable());
+    assert!(!params[2].is_storable());
+    // Sensitive parameters are not logged, but that does not keep them from being stored.
+    assert!(params[3].key_parameter_value().is_sensitive());
+    assert!(params[3].is_storable());
+    assert_eq!(
+        retain_storable(params),
+        vec![
+            KeyParameter::new(KeyParameterValue::KeySize(256), tee),
+            KeyParameter::new(KeyParameterValue::ApplicationID(vec![6]), tee),
+        ]
+    );
+}
+
+#[test]
+fn test_remove_operation_params() {
+    let tee = SecurityLevel::TRUSTED_ENVIRONMENT;
+    let params = vec![
+        KeyParameter::new(KeyParameterValue::Nonce(vec![1, 2, 3]), tee),
+        KeyParameter::new(KeyParameterValue::KeySize(256), tee),
+        KeyParameter::new(KeyParameterValue::AttestationIdBrand(vec![4]), tee),
+        KeyParameter::new(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN), tee),
+        KeyParameter::new(KeyParameterValue::MacLength(128), tee),
+    ];
+    assert_eq!(
+        remove_operation_params(params),
+        vec![
+            KeyParameter::new(KeyParameterValue::KeySize(256), tee),
+            KeyParameter::new(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN), tee),
+        ]
+    );
+
+    let all = KeyParameterValue::make_key_parameter_defaults_vector();
+    let kept = remove_operation_params(all.clone());
+    let removed: Vec<Tag> =
+        all.iter().filter(|p| !kept.contains(p)).map(KeyParameter::get_tag).collect();
+    assert_eq!(
+        removed,
+        vec![
+            Tag::INVALID,
+            Tag::INCLUDE_UNIQUE_ID,
+            Tag::APPLICATION_ID,
+            Tag::APPLICATION_DATA,
+            Tag::ROOT_OF_TRUST,
+            Tag::UNIQUE_ID,
+            Tag::ATTESTATION_CHALLENGE,
+            Tag::ATTESTATION_APPLICATION_ID,
+            Tag::ATTESTATION_ID_BRAND,
+            Tag::ATTESTATION_ID_DEVICE,
+            Tag::ATTESTATION_ID_PRODUCT,
+            Tag::ATTESTATION_ID_SERIAL,
+            Tag::ATTESTATION_ID_IMEI,
+            Tag::ATTESTATION_ID_SECOND_IMEI,
+            Tag::ATTESTATION_ID_MEID,
+            Tag::ATTESTATION_ID_MANUFACTURER,
+            Tag::ATTESTATION_ID_MODEL,
+            Tag::ASSOCIATED_DATA,
+            Tag::NONCE,
+            Tag::MAC_LENGTH,
+            Tag::RESET_SINCE_ID_ROTATION,
+            Tag::CONFIRMATION_TOKEN,
+            Tag::CERTIFICATE_SERIAL,
+            Tag::CERTIFICATE_SUBJECT,
+            Tag::CERTIFICATE_NOT_BEFORE,
+            Tag::CERTIFICATE_NOT_AFTER,
+        ]
+    );
+
+    let unknown = KeyParameter::new(
+        KeyParameterValue::Unknown {
+            tag: Tag(TagType::BYTES.0 | 0xfff),
+            value: KmKeyParameterValue::Blob(vec![1]),
+        },
+        SecurityLevel::TRUSTED_ENVIRONMENT,
+    );
+    assert_eq!(remove_operation_params(vec![unknown.clone()]), vec![unknown]);
+}
+
+#[test]
+fn test_check_cardinality() {
+    let tee = SecurityLevel::TRUSTED_ENVIRONMENT;
+    let mut params = vec![
+        KeyParameter::new(KeyParameterValue::Algorithm(Algorithm::EC), tee),
+        KeyParameter::new(KeyParameterValue::KeySize(256), tee),
+        KeyParameter::new(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN), tee),
+        KeyParameter::new(KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY), tee),
+    ];
+    assert_eq!(check_cardinality(&params), Ok(()));
+
+    // The same tag may occur at different security levels.
+    params.push(KeyParameter::new(KeyParameterValue::KeySize(256), SecurityLevel::KEYSTORE));
+    assert_eq!(check_cardinality(&params), Ok(()));
+
+    // Identical repetitions at the same security level are accepted.
+    params.push(KeyParameter::new(KeyParameterValue::KeySize(256), tee));
+    assert_eq!(check_cardinality(&params), Ok(()));
+
+    // Differing values at the same security level are rejected, and each repeated tag is
+    // reported once.
+    params.push(KeyParameter::new(KeyParameterValue::KeySize(384), tee));
+    params.push(KeyParameter::new(KeyParameterValue::KeySize(512), tee));
+    params.push(KeyParameter::new(KeyParameterValue::Algorithm(Algorithm::RSA), tee));
+    assert_eq!(
+        check_cardinality(&params),
+        Err(CardinalityError { duplicates: vec![(Tag::KEY_SIZE, tee), (Tag::ALGORITHM, tee)] })
+    );
+}
+
+#[test]
+fn test_collect_purposes() {
+    let params = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .algorithm(Algorithm::EC)
+        .purpose(KeyPurpose::VERIFY)
+        .purpose(KeyPurpose::SIGN)
+        .at_level(SecurityLevel::KEYSTORE)
+        .purpose(KeyPurpose::SIGN)
+        .build();
+    assert_eq!(
+        collect_purposes(&params).into_iter().collect::<Vec<_>>(),
+        vec![KeyPurpose::SIGN, KeyPurpose::VERIFY]
+    );
+    assert!(allows_purpose(&params, KeyPurpose::SIGN));
+    assert!(allows_purpose(&params, KeyPurpose::VERIFY));
+    assert!(!allows_purpose(&params, KeyPurpose::DECRYPT));
+    assert!(collect_purposes(&[]).is_empty());
+    assert!(!allows_purpose(&[], KeyPurpose::SIGN));
+}
+
+#[test]
+fn test_collect_mgf_digests() {
+    let params = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .algorithm(Algorithm::RSA)
+        .purpose(KeyPurpose::DECRYPT)
+        .padding(PaddingMode::RSA_OAEP)
+        .digest(Digest::SHA_2_512)
+        .value(KeyParameterValue::RsaOaepMgfDigest(Digest::SHA_2_256))
+        .value(KeyParameterValue::RsaOaepMgfDigest(Digest::SHA1))
+        .value(KeyParameterValue::RsaOaepMgfDigest(Digest::SHA_2_256))
+        .build();
+    assert!(KeyParameterValue::is_repeated_allowed(Tag::RSA_OAEP_MGF_DIGEST));
+    assert_eq!(
+        collect_mgf_digests(&params).into_iter().collect::<Vec<_>>(),
+        vec![Digest::SHA1, Digest::SHA_2_256]
+    );
+    assert!(collect_mgf_digests(&[]).is_empty());
+}
+
+#[test]
+fn test_is_expired() {
+    let now = 1704067200000;
+    let tee = |v| vec![KeyParameter::new(v, SecurityLevel::TRUSTED_ENVIRONMENT)];
+
+    let past = tee(KeyParameterValue::OriginationExpireDateTime(now - 1));
+    assert!(is_expired_for_origination(&past, now));
+    assert!(!is_expired_for_usage(&past, now));
+
+    let future = tee(KeyParameterValue::OriginationExpireDateTime(now + 1));
+    assert!(!is_expired_for_origination(&future, now));
+    let at_expiry = tee(KeyParameterValue::OriginationExpireDateTime(now));
+    assert!(!is_expired_for_origination(&at_expiry, now));
+
+    let no_expiry = tee(KeyParameterValue::KeySize(256));
+    assert!(!is_expired_for_origination(&no_expiry, now));
+    assert!(!is_expired_for_usage(&no_expiry, now));
+    assert!(!is_expired_for_origination(&[], i64::MAX));
+
+    let usage = tee(KeyParameterValue::UsageExpireDateTime(now));
+    assert!(is_expired_for_usage(&usage, now + 1));
+    assert!(!is_expired_for_origination(&usage, now + 1));
+
+    // Extreme values must neither overflow nor panic.
+    let min = tee(KeyParameterValue::OriginationExpireDateTime(i64::MIN));
+    assert!(is_expired_for_origination(&min, i64::MAX));
+    assert!(!is_expired_for_origination(&min, i64::MIN));
+    let max = tee(KeyParameterValue::UsageExpireDateTime(i64::MAX));
+    assert!(!is_expired_for_usage(&max, i64::MAX));
+    assert!(!is_expired_for_usage(&max, i64::MIN));
+}
+
+#[test]
+fn test_functionally_equal() {
+    let tee = |v| KeyParameter::new(v, SecurityLevel::TRUSTED_ENVIRONMENT);
+    let sw = |v| KeyParameter::new(v, SecurityLevel::KEYSTORE);
+
+    assert!(functionally_equal(
+        &tee(KeyParameterValue::KeySize(256)),
+        &sw(KeyParameterValue::KeySize(256))
+    ));
+    assert!(!functionally_equal(
+        &tee(KeyParameterValue::KeySize(256)),
+        &tee(KeyParameterValue::KeySize(128))
+    ));
+
+    let hw_params = vec![
+        tee(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+        tee(KeyParameterValue::Algorithm(Algorithm::EC)),
+        tee(KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY)),
+    ];
+    let sw_params = vec![
+        sw(KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY)),
+        sw(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+        sw(KeyParameterValue::Algorithm(Algorithm::EC)),
+    ];
+    assert!(parameter_sets_functionally_equal(&hw_params, &sw_params));
+    assert!(!parameter_sets_functionally_equal(&hw_params, &sw_params[..2]));
+
+    // Same security levels, but different values.
+    let other_params = vec![
+        tee(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+        tee(KeyParameterValue::Algorithm(Algorithm::EC)),
+        tee(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+    ];
+    assert!(!parameter_sets_functionally_equal(&hw_params, &other_params));
+    assert!(parameter_sets_functionally_equal(&[], &[]));
+}
+
+#[test]
+fn test_stable_eq() {
+    let tee = |v| KeyParameter::new(v, SecurityLevel::TRUSTED_ENVIRONMENT);
+    let keystore = |v| KeyParameter::new(v, SecurityLevel::KEYSTORE);
+    let before = vec![
+        tee(KeyParameterValue::Algorithm(Algorithm::EC)),
+        tee(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+        keystore(KeyParameterValue::CreationDateTime(1704067200000)),
+    ];
+    let after = vec![
+        keystore(KeyParameterValue::CreationDateTime(1735689600000)),
+        tee(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+        tee(KeyParameterValue::Algorithm(Algorithm::EC)),
+    ];
+    assert!(stable_eq(&before, &after));
+    assert!(stable_eq(&before, &after[1..]));
+    assert!(KeyParameterValue::is_volatile(Tag::UNIQUE_ID));
+    assert!(!KeyParameterValue::is_volatile(Tag::ALGORITHM));
+
+    // Stable parameters must match including their security level.
+    let mut other = after.clone();
+    other[1] = keystore(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN));
+    assert!(!stable_eq(&before, &other));
+    assert!(!stable_eq(&before, &after[..2]));
+}
+
+#[test]
+fn test_key_parameter_hash_set() {
+    use std::collections::HashSet;
+
+    let params = KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .purpose(KeyPurpose::SIGN)
+        .purpose(KeyPurpose::SIGN)
+        .value(KeyParameterValue::ApplicationID(vec![1, 2, 3]))
+        .value(KeyParameterValue::ApplicationID(vec![1, 2, 3]))
+        .value(KeyParameterValue::ApplicationID(vec![1, 2, 4]))
+        .at_level(SecurityLevel::KEYSTORE)
+        .purpose(KeyPurpose::SIGN)
+        .build();
+
+    let param_set: HashSet<KeyParameter> = params.iter().cloned().collect();
+    assert_eq!(param_set.len(), 4);
+
+    let value_set: HashSet<KeyParameterValue> =
+        params.into_iter().map(|p| p.key_parameter_value().clone()).collect();
+    assert_eq!(value_set.len(), 3);
+    assert!(value_set.contains(&KeyParameterValue::ApplicationID(vec![1, 2, 4])));
+    assert!(!value_set.contains(&KeyParameterValue::ApplicationID(vec![1, 2])));
+}
+
+#[test]
+fn test_key_parameter_hash_set_reinsertion() {
+    use std::collections::HashSet;
+
+    let mut params: Vec<KeyParameter> = (0..25)
+        .map(|i| KeyParameter::new(KeyParameterValue::KeySize(i), SecurityLevel::STRONGBOX))
+        .collect();
+    params.extend((0..25u8).map(|i| {
+        KeyParameter::new(KeyParameterValue::ApplicationID(vec![i]), SecurityLevel::SOFTWARE)
+    }));
+
+    let mut set = HashSet::new();
+    for p in &params {
+        assert!(set.insert(p.clone()));
+    }
+    assert_eq!(set.len(), 50);
+    let duplicates = params.iter().filter(|p| !set.insert((*p).clone())).count();
+    assert_eq!(duplicates, 50);
+    assert_eq!(set.len(), 50);
+
+    // The same value at a different security level is a distinct key parameter.
+    assert!(set.insert(KeyParameter::new(KeyParameterValue::KeySize(0), SecurityLevel::KEYSTORE)));
+    assert!(set.contains(&KeyParameter::new(
+        KeyParameterValue::ApplicationID(vec![24]),
+        SecurityLevel::SOFTWARE
+    )));
+}
+
+#[test]
+fn test_primitive_hash_distinguishes_types() {
+    use std::collections::HashSet;
+
+    let set: HashSet<Primitive> =
+        [Primitive::I32(1), Primitive::I64(1), Primitive::Vec(vec![1]), Primitive::I32(1)]
+            .into_iter()
+            .collect();
+    assert_eq!(set.len(), 3);
+}
+
+#[test]
+fn test_is_enforceable_by_hardware() {
+    let enforceable = |value: KeyParameterValue| {
+        KeyParameter::new(value, SecurityLevel::TRUSTED_ENVIRONMENT).is_enforceable_by_hardware()
+    };
+
+    // Key characteristics enforced by KeyMint.
+    assert!(enforceable(KeyParameterValue::Algorithm(Algorithm::EC)));
+    assert!(enforceable(KeyParameterValue::KeySize(256)));
+    assert!(enforceable(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)));
+    assert!(enforceable(KeyParameterValue::EcCurve(EcCurve::P_256)));
+    assert!(enforceable(KeyParameterValue::NoAuthRequired));
+    assert!(enforceable(KeyParameterValue::UserSecureID(42)));
+    assert!(enforceable(KeyParameterValue::RollbackResistance));
+
+    // Operation parameters are never key characteristics.
+    assert!(!enforceable(KeyParameterValue::Nonce(vec![0; 12])));
+    assert!(!enforceable(KeyParameterValue::AssociatedData(vec![1, 2, 3])));
+    assert!(!enforceable(KeyParameterValue::MacLength(128)));
+
+    // Generation and attestation only parameters.
+    assert!(!enforceable(KeyParameterValue::AttestationChallenge(vec![1, 2, 3])));
+    assert!(!enforceable(KeyParameterValue::ApplicationID(vec![1, 2, 3])));
+    assert!(!enforceable(KeyParameterValue::CertificateNotAfter(0)));
+
+    // Characteristics enforced by Keystore.
+    assert!(!enforceable(KeyParameterValue::UsageExpireDateTime(0)));
+    assert!(!enforceable(KeyParameterValue::CreationDateTime(0)));
+    assert!(!enforceable(KeyParameterValue::UnlockedDeviceRequired));
+
+    // StrongBox enforces the same characteristics as the TEE.
+    let ec = KeyParameterValue::Algorithm(Algorithm::EC);
+    assert!(KeyParameter::new(ec.clone(), SecurityLevel::STRONGBOX).is_enforceable_by_hardware());
+
+    // Nothing is enforceable by hardware at a software security level.
+    for level in [SecurityLevel::SOFTWARE, SecurityLevel::KEYSTORE] {
+        assert!(!KeyParameter::new(ec.clone(), level).is_enforceable_by_hardware());
+        for param in KeyParameterValue::make_key_parameter_defaults_vector() {
+            let value = param.key_parameter_value().clone();
+            assert!(!KeyParameter::new(value, level).is_enforceable_by_hardware());
+        }
+    }
+}
+
+#[test]
+fn test_sort_by_security_level() {
+    let expected = vec![
+        KeyParameter::new(KeyParameterValue::Algorithm(Algorithm::EC), SecurityLevel::STRONGBOX),
+        KeyParameter::new(KeyParameterValue::KeySize(256), SecurityLevel::STRONGBOX),
+        KeyParameter::new(
+            KeyParameterValue::KeyPurpose(KeyPurpose::SIGN),
+            SecurityLevel::TRUSTED_ENVIRONMENT,
+        ),
+        KeyParameter::new(
+            KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY),
+            SecurityLevel::TRUSTED_ENVIRONMENT,
+        ),
+        KeyParameter::new(KeyParameterValue::OSVersion(14), SecurityLevel::TRUSTED_ENVIRONMENT),
+        KeyParameter::new(KeyParameterValue::CallerNonce, SecurityLevel::SOFTWARE),
+        KeyParameter::new(KeyParameterValue::UserID(0), SecurityLevel::KEYSTORE),
+        KeyParameter::new(KeyParameterValue::CreationDateTime(1), SecurityLevel::KEYSTORE),
+    ];
+    let mut reversed = expected.clone();
+    reversed.reverse();
+    // Parameters with the same level and tag keep their relative order.
+    reversed.swap(4, 5);
+    assert_eq!(sorted_by_security_level(reversed), expected);
+
+    let mut params = expected.clone();
+    params.rotate_left(4);
+    sort_by_security_level(&mut params);
+    for pair in params.windows(2) {
+        let key = |p: &KeyParameter| (Reverse(trust_rank(p.security_level)), p.get_tag());
+        assert!(key(&pair[0]) <= key(&pair[1]));
+    }
+    assert_eq!(params, expected);
+}
+
+#[test]
+fn test_trust_rank() {
+    let by_trust = [
+        SecurityLevel(42),
+        SecurityLevel::KEYSTORE,
+        SecurityLevel::SOFTWARE,
+        SecurityLevel::TRUSTED_ENVIRONMENT,
+        SecurityLevel::STRONGBOX,
+    ];
+    for pair in by_trust.windows(2) {
+        assert!(trust_rank(pair[0]) < trust_rank(pair[1]), "{:?} < {:?}", pair[0], pair[1]);
+    }
+    // The numeric order puts the KEYSTORE pseudo-level above all hardware levels.
+    assert!(SecurityLevel::KEYSTORE > SecurityLevel::STRONGBOX);
+    assert!(trust_rank(SecurityLevel::KEYSTORE) < trust_rank(SecurityLevel::SOFTWARE));
+}
+
+#[test]
+fn test_cmp_by_trust() {
+    use std::cmp::Ordering;
+
+    let value = KeyParameterValue::KeySize(256);
+    let keystore = KeyParameter::new(value.clone(), SecurityLevel::KEYSTORE);
+    let software = KeyParameter::new(value.clone(), SecurityLevel::SOFTWARE);
+    let strongbox = KeyParameter::new(value, SecurityLevel::STRONGBOX);
+
+    // The derived Ord follows the raw numeric security level.
+    assert!(keystore > strongbox);
+    assert_eq!(keystore.cmp_by_trust(&strongbox), Ordering::Less);
+    assert_eq!(keystore.cmp_by_trust(&software), Ordering::Less);
+    assert_eq!(strongbox.cmp_by_trust(&software), Ordering::Greater);
+    assert_eq!(software.cmp_by_trust(&software.clone()), Ordering::Equal);
+
+    // Equally trusted levels fall back to the derived Ord.
+    let a = KeyParameter::new(KeyParameterValue::KeySize(128), SecurityLevel::STRONGBOX);
+    assert_eq!(a.cmp_by_trust(&strongbox), a.cmp(&strongbox));
+    assert_ne!(a.cmp_by_trust(&strongbox), Ordering::Equal);
+
+    let mut params = vec![strongbox.clone(), keystore.clone(), software.clone()];
+    params.sort_by(KeyParameter::cmp_by_trust);
+    assert_eq!(params, vec![keystore, software, strongbox]);
+}
+
+#[test]
+fn test_value_eq_ignores_security_level() {
+    use std::cmp::Ordering;
+
+    let software = KeyParameter::new(KeyParameterValue::KeySize(256), SecurityLevel::SOFTWARE);
+    let tee =
+        KeyParameter::new(KeyParameterValue::KeySize(256), SecurityLevel::TRUSTED_ENVIRONMENT);
+    assert_ne!(software, tee);
+    assert!(software.value_eq(&tee));
+    assert_eq!(software.value_cmp(&tee), Ordering::Equal);
+
+    let smaller = KeyParameter::new(KeyParameterValue::KeySize(128), SecurityLevel::STRONGBOX);
+    assert!(!smaller.value_eq(&tee));
+    assert_eq!(smaller.value_cmp(&tee), Ordering::Less);
+    assert_eq!(tee.value_cmp(&smaller), Ordering::Greater);
+}
+
+#[test]
+fn test_partition_by_level() {
+    let tee = SecurityLevel::TRUSTED_ENVIRONMENT;
+    let params = vec![
+        KeyParameter::new(KeyParameterValue::Algorithm(Algorithm::EC), tee),
+        KeyParameter::new(KeyParameterValue::CreationDateTime(1000), SecurityLevel::SOFTWARE),
+        KeyParameter::new(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN), tee),
+        KeyParameter::new(KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY), tee),
+        KeyParameter::new(KeyParameterValue::UsageCountLimit(5), SecurityLevel::SOFTWARE),
+    ];
+    let partition = partition_by_level(params);
+    assert_eq!(partition.len(), 2);
+    assert_eq!(
+        partition[&tee],
+        vec![
+            KeyParameterValue::Algorithm(Algorithm::EC),
+            KeyParameterValue::KeyPurpose(KeyPurpose::SIGN),
+            KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY),
+        ]
+    );
+    assert_eq!(
+        partition[&SecurityLevel::SOFTWARE],
+        vec![KeyParameterValue::CreationDateTime(1000), KeyParameterValue::UsageCountLimit(5)]
+    );
+    assert!(partition_by_level(vec![]).is_empty());
+}
+
+#[test]
+fn test_set_security_level_for_all() {
+    let mut params = KeyParameterBuilder::new(SecurityLevel::SOFTWARE)
+        .algorithm(Algorithm::EC)
+        .purpose(KeyPurpose::SIGN)
+        .at_level(SecurityLevel::KEYSTORE)
+        .value(KeyParameterValue::UserID(0))
+        .build();
+    set_security_level_for_all(&mut params, SecurityLevel::TRUSTED_ENVIRONMENT).unwrap();
+    assert!(params.iter().all(|p| *p.security_level() == SecurityLevel::TRUSTED_ENVIRONMENT));
+
+    *params[0].security_level_mut() = SecurityLevel::STRONGBOX;
+    assert_eq!(*params[0].security_level(), SecurityLevel::STRONGBOX);
+
+    let before = params.clone();
+    let err = set_security_level_for_all(&mut params, SecurityLevel(42)).unwrap_err();
+    assert_eq!(
+        err.root_cause().downcast_ref::<KeystoreError>(),
+        Some(&KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT))
+    );
+    assert_eq!(params, before);
+}
+
+#[test]
+fn test_with_security_level() {
+    let param = KeyParameterValue::Algorithm(Algorithm::AES).into_key_parameter();
+    assert_eq!(*param.security_level(), SecurityLevel::SOFTWARE);
+
+    let param = param.with_security_level(SecurityLevel::STRONGBOX);
+    assert_eq!(
+        param,
+        KeyParameter::new(KeyParameterValue::Algorithm(Algorithm::AES), SecurityLevel::STRONGBOX)
+    );
+
+    let param = KeyParameterValue::KeySize(256)
+        .into_key_parameter()
+        .with_security_level(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .with_security_level(SecurityLevel::KEYSTORE);
+    assert_eq!(*param.security_level(), SecurityLevel::KEYSTORE);
+    assert_eq!(param.key_parameter_value(), &KeyParameterValue::KeySize(256));
+}
+
+#[test]
+fn test_clone_params_with_level() {
+    let sw = |v| KeyParameter::new(v, SecurityLevel::SOFTWARE);
+    let params = vec![
+        sw(KeyParameterValue::Algorithm(Algorithm::EC)),
+        sw(KeyParameterValue::KeySize(256)),
+        sw(KeyParameterValue::ApplicationID(vec![1, 2, 3])),
+    ];
+    let original = params.clone();
+
+    let param = params[1].clone_with_level(SecurityLevel::STRONGBOX);
+    assert_eq!(param, KeyParameter::new(KeyParameterValue::KeySize(256), SecurityLevel::STRONGBOX));
+
+    let cloned = clone_params_with_level(&params, SecurityLevel::TRUSTED_ENVIRONMENT);
+    assert_eq!(params, original);
+    assert_eq!(cloned.len(), params.len());
+    for (c, p) in cloned.iter().zip(&params) {
+        assert_eq!(c.key_parameter_value(), p.key_parameter_value());
+        assert_eq!(*c.security_level(), SecurityLevel::TRUSTED_ENVIRONMENT);
+    }
+}
+
+#[test]
+fn test_validate_value_range() {
+    use crate::key_parameter_set::ValidationError;
+
+    // The upper bound depends on the digest and is checked by KeyParameterSetBuilder.
+    for v in [64, 72, 128, 248, 256, 512] {
+        assert_eq!(KeyParameterValue::MinMacLength(v).validate_value_range(), Ok(()));
+    }
+    for v in [0, 56, 63, 65, 100, 260, -64] {
+        assert_eq!(
+            KeyParameterValue::MinMacLength(v).validate_value_range(),
+            Err(ValidationError::OutOfRange {
+                tag: Tag::MIN_MAC_LENGTH,
+                value: Primitive::I32(v),
+                allowed: "a multiple of 8 of at least 64",
+            })
+        );
+    }
+
+    assert_eq!(KeyParameterValue::MaxUsesPerBoot(1).validate_value_range(), Ok(()));
+    assert!(KeyParameterValue::MaxUsesPerBoot(0).validate_value_range().is_err());
+    assert!(KeyParameterValue::MaxUsesPerBoot(-1).validate_value_range().is_err());
+
+    assert_eq!(KeyParameterValue::CreationDateTime(0).validate_value_range(), Ok(()));
+    assert_eq!(KeyParameterValue::UsageExpireDateTime(i64::MAX).validate_value_range(), Ok(()));
+    assert_eq!(
+        KeyParameterValue::CertificateNotBefore(-1).validate_value_range(),
+        Err(ValidationError::OutOfRange {
+            tag: Tag::CERTIFICATE_NOT_BEFORE,
+            value: Primitive::I64(-1),
+            allowed: "a non-negative date time",
+        })
+    );
+
+    // Key sizes depend on the algorithm and are not checked here.
+    assert_eq!(KeyParameterValue::KeySize(256).validate_value_range(), Ok(()));
+    assert_eq!(KeyParameterValue::UserSecureID(-1).validate_value_range(), Ok(()));
+}
+
+#[test]
+fn test_new_from_tag_primitive_pair_checks_range() {
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair(Tag::MIN_MAC_LENGTH, 64),
+        Ok(KeyParameterValue::MinMacLength(64))
+    );
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair(Tag::MIN_MAC_LENGTH, 32),
+        Err(KeyParameterError::OutOfRange(ValidationError::OutOfRange {
+            tag: Tag::MIN_MAC_LENGTH,
+            value: Primitive::I32(32),
+            allowed: "a multiple of 8 of at least 64",
+        }))
+    );
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair(Tag::ACTIVE_DATETIME, 0i64),
+        Ok(KeyParameterValue::ActiveDateTime(0))
+    );
+    assert!(matches!(
+        KeyParameterValue::new_from_tag_primitive_pair(Tag::ACTIVE_DATETIME, -1i64),
+        Err(KeyParameterError::OutOfRange(ValidationError::OutOfRange { .. }))
+    ));
+    assert!(matches!(
+        KeyParameterValue::new_from_tag_primitive_pair(Tag::MAX_USES_PER_BOOT, 0),
+        Err(KeyParameterError::OutOfRange(ValidationError::OutOfRange { .. }))
+    ));
+
+    // Stored and imported parameters are preserved.
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair_without_range_check(Tag::MIN_MAC_LENGTH, 32),
+        Ok(KeyParameterValue::MinMacLength(32))
+    );
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair_without_range_check(
+            Tag::ACTIVE_DATETIME,
+            -1i64
+        ),
+        Ok(KeyParameterValue::ActiveDateTime(-1))
+    );
+
+    let value = KeyParameterValue::MaxUsesPerBoot(0);
+    let blob = encode_blob(&[KeyParameter::new(value.clone(), SecurityLevel::KEYSTORE)]);
+    assert_eq!(*decode_blob(&blob).unwrap()[0].key_parameter_value(), value);
+}
+
+#[test]
+fn test_new_from_tag_primitive_pair_checks_blob_size() {
+    let id_max = KeyParameterValue::APPLICATION_ID_MAX_SIZE;
+    let data_max = KeyParameterValue::APPLICATION_DATA_MAX_SIZE;
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair(Tag::APPLICATION_ID, vec![1u8; id_max]),
+        Ok(KeyParameterValue::ApplicationID(vec![1u8; id_max]))
+    );
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair(Tag::APPLICATION_ID, vec![1u8; id_max + 1]),
+        Err(BlobTooLargeError { tag: Tag::APPLICATION_ID, size: id_max + 1, max: id_max }.into())
+    );
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair(Tag::APPLICATION_DATA, vec![2u8; data_max]),
+        Ok(KeyParameterValue::ApplicationData(vec![2u8; data_max]))
+    );
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair(
+            Tag::APPLICATION_DATA,
+            vec![2u8; data_max + 1]
+        ),
+        Err(BlobTooLargeError { tag: Tag::APPLICATION_DATA, size: data_max + 1, max: data_max }
+            .into())
+    );
+}
+
+#[test]
+fn test_new_from_tag_primitive_pair_lenient() {
+    // I32 is widened for the LongInteger and DateTime fields.
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair_lenient(
+            Tag::USER_SECURE_ID,
+            Primitive::I32(42)
+        ),
+        Ok(KeyParameterValue::UserSecureID(42))
+    );
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair_lenient(
+            Tag::RSA_PUBLIC_EXPONENT,
+            Primitive::I32(65537)
+        ),
+        Ok(KeyParameterValue::RSAPublicExponent(65537))
+    );
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair_lenient(
+            Tag::CREATION_DATETIME,
+            Primitive::I32(-1)
+        ),
+        Err(KeyParameterError::OutOfRange(ValidationError::OutOfRange {
+            tag: Tag::CREATION_DATETIME,
+            value: Primitive::I64(-1),
+            allowed: "a non-negative date time",
+        }))
+    );
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair_lenient(
+            Tag::CREATION_DATETIME,
+            Primitive::I32(1000)
+        ),
+        Ok(KeyParameterValue::CreationDateTime(1000))
+    );
+    // The strict variant still rejects the I32.
+    assert!(matches!(
+        KeyParameterValue::new_from_tag_primitive_pair(Tag::USER_SECURE_ID, 42i32),
+        Err(KeyParameterError::InvalidPrimitive(PrimitiveError::TypeMismatch { .. }))
+    ));
+
+    // Incompatible combinations are still rejected.
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair_lenient(
+            Tag::APPLICATION_ID,
+            Primitive::I32(1)
+        ),
+        Err(KeyParameterError::InvalidPrimitive(PrimitiveError::TypeMismatch {
+            tag: Tag::APPLICATION_ID,
+            expected: "Vec<u8>",
+            got: "i32"
+        }))
+    );
+    assert!(matches!(
+        KeyParameterValue::new_from_tag_primitive_pair_lenient(Tag::KEY_SIZE, Primitive::I64(256)),
+        Err(KeyParameterError::InvalidPrimitive(PrimitiveError::TypeMismatch { .. }))
+    ));
+}
+
+#[test]
+fn test_is_repeated_allowed() {
+    let table = [
+        (Tag::INVALID, false),
+        (Tag::PURPOSE, true),
+        (Tag::ALGORITHM, false),
+        (Tag::KEY_SIZE, false),
+        (Tag::BLOCK_MODE, true),
+        (Tag::DIGEST, true),
+        (Tag::PADDING, true),
+        (Tag::CALLER_NONCE, false),
+        (Tag::MIN_MAC_LENGTH, false),
+        (Tag::EC_CURVE, false),
+        (Tag::RSA_PUBLIC_EXPONENT, false),
+        (Tag::INCLUDE_UNIQUE_ID, false),
+        (Tag::RSA_OAEP_MGF_DIGEST, true),
+        (Tag::BOOTLOADER_ONLY, false),
+        (Tag::ROLLBACK_RESISTANCE, false),
+        (Tag::HARDWARE_TYPE, false),
+        (Tag::EARLY_BOOT_ONLY, false),
+        (Tag::ACTIVE_DATETIME, false),
+        (Tag::ORIGINATION_EXPIRE_DATETIME, false),
+        (Tag::USAGE_EXPIRE_DATETIME, false),
+        (Tag::MIN_SECONDS_BETWEEN_OPS, false),
+        (Tag::MAX_USES_PER_BOOT, false),
+        (Tag::USAGE_COUNT_LIMIT, false),
+        (Tag::USER_ID, false),
+        (Tag::USER_SECURE_ID, true),
+        (Tag::NO_AUTH_REQUIRED, false),
+        (Tag::USER_AUTH_TYPE, false),
+        (Tag::AUTH_TIMEOUT, false),
+        (Tag::ALLOW_WHILE_ON_BODY, false),
+        (Tag::TRUSTED_USER_PRESENCE_REQUIRED, false),
+        (Tag::TRUSTED_CONFIRMATION_REQUIRED, false),
+        (Tag::UNLOCKED_DEVICE_REQUIRED, false),
+        (Tag::APPLICATION_ID, false),
+        (Tag::APPLICATION_DATA, false),
+        (Tag::CREATION_DATETIME, false),
+        (Tag::ORIGIN, false),
+        (Tag::ROOT_OF_TRUST, false),
+        (Tag::OS_VERSION, false),
+        (Tag::OS_PATCHLEVEL, false),
+        (Tag::UNIQUE_ID, false),
+        (Tag::ATTESTATION_CHALLENGE, false),
+        (Tag::ATTESTATION_APPLICATION_ID, false),
+        (Tag::ATTESTATION_ID_BRAND, false),
+        (Tag::ATTESTATION_ID_DEVICE, false),
+        (Tag::ATTESTATION_ID_PRODUCT, false),
+        (Tag::ATTESTATION_ID_SERIAL, false),
+        (Tag::ATTESTATION_ID_IMEI, false),
+        (Tag::ATTESTATION_ID_MEID, false),
+        (Tag::ATTESTATION_ID_MANUFACTURER, false),
+        (Tag::ATTESTATION_ID_MODEL, false),
+        (Tag::VENDOR_PATCHLEVEL, false),
+        (Tag::BOOT_PATCHLEVEL, false),
+        (Tag::DEVICE_UNIQUE_ATTESTATION, false),
+        (Tag::IDENTITY_CREDENTIAL_KEY, false),
+        (Tag::STORAGE_KEY, false),
+        (Tag::ATTESTATION_ID_SECOND_IMEI, false),
+        (Tag::MODULE_HASH, false),
+        (Tag::ASSOCIATED_DATA, false),
+        (Tag::NONCE, false),
+        (Tag::MAC_LENGTH, false),
+        (Tag::RESET_SINCE_ID_ROTATION, false),
+        (Tag::CONFIRMATION_TOKEN, false),
+        (Tag::CERTIFICATE_SERIAL, false),
+        (Tag::CERTIFICATE_SUBJECT, false),
+        (Tag::CERTIFICATE_NOT_BEFORE, false),
+        (Tag::CERTIFICATE_NOT_AFTER, false),
+        (Tag::MAX_BOOT_LEVEL, false),
+    ];
+    assert_eq!(table.len(), Tag::enum_values().len());
+    for (tag, expected) in table {
+        assert_eq!(KeyParameterValue::is_repeated_allowed(tag), expected, "{:?}", tag);
+        // The set agrees with the repeatable tag types of the AIDL spec.
+        assert_eq!(is_repeatable_tag(tag), expected, "{:?}", tag);
+    }
+    assert!(!KeyParameterValue::is_repeated_allowed(Tag(TagType::ENUM_REP.0 | 0xfff)));
+}
+
+#[test]
+fn test_system_time_conversion() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let t = UNIX_EPOCH + Duration::from_millis(1704067200123);
+    let value = KeyParameterValue::active_date_time(t).unwrap();
+    assert_eq!(value, KeyParameterValue::ActiveDateTime(1704067200123));
+    assert_eq!(value.as_system_time(), Some(t));
+
+    let before_epoch = UNIX_EPOCH - Duration::from_millis(1500);
+    let value = KeyParameterValue::certificate_not_before(before_epoch).unwrap();
+    assert_eq!(value, KeyParameterValue::CertificateNotBefore(-1500));
+    assert_eq!(value.as_system_time(), Some(before_epoch));
+
+    assert_eq!(
+        KeyParameterValue::usage_expire_date_time(UNIX_EPOCH).unwrap().as_system_time(),
+        Some(UNIX_EPOCH)
+    );
+    assert_eq!(KeyParameterValue::KeySize(256).as_system_time(), None);
+    assert_eq!(KeyParameterValue::RSAPublicExponent(65537).as_system_time(), None);
+}
+
+#[test]
+fn test_system_time_out_of_range() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    // Whether such extreme values are representable depends on the platform. Either way, the
+    // conversions must not panic.
+    for millis in [i64::MIN, i64::MAX] {
+        if let Some(t) = KeyParameterValue::CreationDateTime(millis).as_system_time() {
+            assert_eq!(
+                KeyParameterValue::creation_date_time(t).unwrap(),
+                KeyParameterValue::CreationDateTime(millis)
+            );
+        }
+    }
+    if let Some(t) = UNIX_EPOCH.checked_add(Duration::from_secs(u64::MAX / 2)) {
+        assert!(KeyParameterValue::origination_expire_date_time(t).is_err());
+    }
+}
+
+#[test]
+fn test_required_for_algorithm() {
+    for alg in Algorithm::enum_values() {
+        let required = KeyParameterValue::required_for_algorithm(alg);
+        assert!(!required.is_empty(), "No required tags for {:?}.", alg);
+        assert!(!required.contains(&Tag::ALGORITHM));
+        for tag in &required {
+            assert!(
+                !matches!(tag_type_of(*tag), TagType::BOOL | TagType::INVALID),
+                "{:?} cannot be required for {:?}.",
+                tag,
+                alg
+            );
+        }
+    }
+    assert_eq!(
+        KeyParameterValue::required_for_algorithm(Algorithm::RSA),
+        vec![Tag::KEY_SIZE, Tag::RSA_PUBLIC_EXPONENT]
+    );
+    assert_eq!(KeyParameterValue::required_for_algorithm(Algorithm::EC), vec![Tag::EC_CURVE]);
+    assert_eq!(KeyParameterValue::required_for_algorithm(Algorithm::AES), vec![Tag::KEY_SIZE]);
+    assert_eq!(
+        KeyParameterValue::required_for_algorithm(Algorithm::TRIPLE_DES),
+        vec![Tag::KEY_SIZE]
+    );
+    assert_eq!(
+        KeyParameterValue::required_for_algorithm(Algorithm::HMAC),
+        vec![Tag::KEY_SIZE, Tag::DIGEST, Tag::MIN_MAC_LENGTH]
+    );
+    assert!(KeyParameterValue::required_for_algorithm(Algorithm(1234)).is_empty());
+}
+
+#[test]
+fn test_primitive_error_identifies_tag() {
+    let err = KeyParameterValue::new_from_tag_primitive_pair(Tag::PURPOSE, 2i64).unwrap_err();
+    assert_eq!(
+        err,
+        KeyParameterError::InvalidPrimitive(PrimitiveError::TypeMismatch {
+            tag: Tag::PURPOSE,
+            expected: "i32",
+            got: "i64"
+        })
+    );
+    assert_eq!(err.to_string(), "tag PURPOSE expects i32 but got i64");
+
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair(Tag::APPLICATION_ID, 1i32).unwrap_err(),
+        KeyParameterError::InvalidPrimitive(PrimitiveError::TypeMismatch {
+            tag: Tag::APPLICATION_ID,
+            expected: "Vec<u8>",
+            got: "i32"
+        })
+    );
+    assert_eq!(
+        KeyParameterValue::new_from_tag_primitive_pair(Tag(12345), 1i32).unwrap_err(),
+        KeyParameterError::UnsupportedTag(Tag(12345))
+    );
+    assert_eq!(
+        i32::try_from(Primitive::I64(3)),
+        Err(PrimitiveError::UnexpectedPrimitive(Primitive::I64(3)))
+    );
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_deprecated_variant_is_still_supported() {
+    let value = KeyParameterValue::AllowWhileOnBody;
+    assert_eq!(value.get_tag(), Tag::ALLOW_WHILE_ON_BODY);
+    let km: KmKeyParameter = value.clone().into();
+    assert_eq!(KeyParameterValue::from_km_parameter_lossy(km), value);
+}
+
+#[test]
+fn test_value_ordering_is_canonical() {
+    let values = vec![
+        KeyParameterValue::Invalid(0),
+        KeyParameterValue::Invalid(1),
+        KeyParameterValue::KeyPurpose(KeyPurpose::SIGN),
+        KeyParameterValue::KeyPurpose(KeyPurpose::ENCRYPT),
+        KeyParameterValue::Algorithm(Algorithm::EC),
+        KeyParameterValue::Algorithm(Algorithm::RSA),
+        KeyParameterValue::KeySize(256),
+        KeyParameterValue::KeySize(-1),
+        KeyParameterValue::UserSecureID(i64::MAX),
+        KeyParameterValue::UserSecureID(7),
+        KeyParameterValue::NoAuthRequired,
+        KeyParameterValue::ApplicationID(vec![2]),
+        KeyParameterValue::ApplicationID(vec![1, 2]),
+        KeyParameterValue::ApplicationID(vec![1]),
+        KeyParameterValue::ApplicationID(vec![]),
+        KeyParameterValue::Unknown {
+            tag: Tag(TagType::UINT.0 | 0xfff),
+            value: KmKeyParameterValue::Integer(3),
+        },
+        KeyParameterValue::Unknown {
+            tag: Tag(TagType::UINT.0 | 0xfff),
+            value: KmKeyParameterValue::Algorithm(Algorithm(3)),
+        },
+    ];
+
+    for a in &values {
+        for b in &values {
+            assert_eq!(a.cmp(b) == Ordering::Equal, a == b, "{:?} {:?}", a, b);
+            assert_eq!(a.cmp(b), b.cmp(a).reverse(), "{:?} {:?}", a, b);
+            assert_eq!(a.partial_cmp(b), Some(a.cmp(b)));
+            for c in &values {
+                if a <= b && b <= c {
+                    assert!(a <= c, "{:?} {:?} {:?}", a, b, c);
+                }
+            }
+        }
+    }
+
+    let mut sorted = values.clone();
+    sorted.sort();
+    for pair in sorted.windows(2) {
+        assert!(pair[0].get_tag().0 <= pair[1].get_tag().0, "{:?}", pair);
+    }
+    let blobs: Vec<&[u8]> = sorted.iter().filter_map(|v| v.blob_value()).collect();
+    assert_eq!(blobs, [&[][..], &[1], &[1, 2], &[2]]);
+    let key_sizes: Vec<&KeyParameterValue> =
+        sorted.iter().filter(|v| v.get_tag() == Tag::KEY_SIZE).collect();
+    assert_eq!(key_sizes, [&KeyParameterValue::KeySize(-1), &KeyParameterValue::KeySize(256)]);
+}
+
+#[test]
+fn test_canonical_digest_input_ignores_order() {
+    let params = vec![
+        KeyParameter::new(KeyParameterValue::KeySize(256), SecurityLevel::TRUSTED_ENVIRONMENT),
+        KeyParameter::new(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN), SecurityLevel::KEYSTORE),
+        KeyParameter::new(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN), SecurityLevel::SOFTWARE),
+        KeyParameter::new(KeyParameterValue::ApplicationID(vec![7, 8]), SecurityLevel::KEYSTORE),
+        KeyParameter::new(KeyParameterValue::NoAuthRequired, SecurityLevel::TRUSTED_ENVIRONMENT),
+    ];
+    let expected = canonical_digest_input(&params);
+    let mut reordered = params.clone();
+    reordered.reverse();
+    assert_eq!(canonical_digest_input(&reordered), expected);
+    reordered.rotate_left(2);
+    assert_eq!(canonical_digest_input(&reordered), expected);
+
+    let mut changed = params.clone();
+    *changed[0].security_level_mut() = SecurityLevel::STRONGBOX;
+    assert_ne!(canonical_digest_input(&changed), expected);
+    assert!(canonical_digest_input(&[]).is_empty());
+
+    // The canonical encoding of the value followed by the security level as little-endian i32.
+    let blob = KeyParameter::new(KeyParameterValue::ApplicationID(vec![7, 8]), SecurityLevel(1));
+    let mut layout = blob.value.to_canonical_bytes();
+    layout.extend_from_slice(&[1, 0, 0, 0]);
+    assert_eq!(canonical_digest_input(&[blob]), layout);
+}
+
+#[test]
+fn test_to_canonical_bytes() {
+    // ALGORITHM is 0x10000002, KEY_SIZE is 0x30000003, USER_SECURE_ID is 0xa00001f6,
+    // APPLICATION_ID is 0x90000259, and NO_AUTH_REQUIRED is 0x700001f7. The tag is followed
+    // by the kind of the primitive: 1 for i32, 2 for i64, 3 for blobs, and 0 for none.
+    assert_eq!(
+        KeyParameterValue::Algorithm(Algorithm::EC).to_canonical_bytes(),
+        vec![0x02, 0x00, 0x00, 0x10, 0x01, 0x03, 0x00, 0x00, 0x00]
+    );
+    assert_eq!(
+        KeyParameterValue::KeySize(0x01020304).to_canonical_bytes(),
+        vec![0x03, 0x00, 0x00, 0x30, 0x01, 0x04, 0x03, 0x02, 0x01]
+    );
+    assert_eq!(
+        KeyParameterValue::UserSecureID(-2).to_canonical_bytes(),
+        vec![0xf6, 0x01, 0x00, 0xa0, 0x02, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+    );
+    assert_eq!(
+        KeyParameterValue::ApplicationID(vec![7, 8]).to_canonical_bytes(),
+        vec![0x59, 0x02, 0x00, 0x90, 0x03, 0x02, 0x00, 0x00, 0x00, 7, 8]
+    );
+    assert_eq!(
+        KeyParameterValue::NoAuthRequired.to_canonical_bytes(),
+        vec![0xf7, 0x01, 0x00, 0x70, 0x00]
+    );
+}
+
+#[test]
+fn test_key_parameter_set_fingerprint() {
+    let params = vec![
+        KeyParameter::new(KeyParameterValue::KeySize(256), SecurityLevel::TRUSTED_ENVIRONMENT),
+        KeyParameter::new(
+            KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY),
+            SecurityLevel::KEYSTORE,
+        ),
+        KeyParameter::new(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN), SecurityLevel::KEYSTORE),
+        KeyParameter::new(KeyParameterValue::ApplicationID(vec![7, 8]), SecurityLevel::KEYSTORE),
+        KeyParameter::new(KeyParameterValue::NoAuthRequired, SecurityLevel::TRUSTED_ENVIRONMENT),
+    ];
+    let expected = key_parameter_set_fingerprint(&params).unwrap();
+    let mut reordered = params.clone();
+    reordered.reverse();
+    assert_eq!(key_parameter_set_fingerprint(&reordered).unwrap(), expected);
+    reordered.rotate_left(2);
+    assert_eq!(key_parameter_set_fingerprint(&reordered).unwrap(), expected);
+
+    let mut changed = params.clone();
+    changed[0] = KeyParameter::new(KeyParameterValue::KeySize(128), SecurityLevel::KEYSTORE);
+    assert_ne!(key_parameter_set_fingerprint(&changed).unwrap(), expected);
+    assert_ne!(key_parameter_set_fingerprint(&params[1..]).unwrap(), expected);
+
+    // SHA-256 of the empty input.
+    assert_eq!(
+        hex::encode(key_parameter_set_fingerprint(&[]).unwrap()),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+}
+
+#[test]
+fn test_validate_enum_ranges() {
+    assert_eq!(KeyParameterValue::Digest(Digest::SHA_2_256).validate_enum_ranges(), Ok(()));
+    assert_eq!(
+        KeyParameterValue::Digest(Digest(9999)).validate_enum_ranges(),
+        Err(EnumRangeError::UndefinedValue { tag: Tag::DIGEST, value: 9999 })
+    );
+    assert_eq!(
+        KeyParameterValue::RsaOaepMgfDigest(Digest(-1)).validate_enum_ranges(),
+        Err(EnumRangeError::UndefinedValue { tag: Tag::RSA_OAEP_MGF_DIGEST, value: -1 })
+    );
+
+    // HardwareAuthenticatorType is a bit mask.
+    let both = HardwareAuthenticatorType(
+        HardwareAuthenticatorType::PASSWORD.0 | HardwareAuthenticatorType::FINGERPRINT.0,
+    );
+    assert_eq!(KeyParameterValue::HardwareAuthenticatorType(both).validate_enum_ranges(), Ok(()));
+    assert_eq!(
+        KeyParameterValue::HardwareAuthenticatorType(HardwareAuthenticatorType::ANY)
+            .validate_enum_ranges(),
+        Ok(())
+    );
+    assert!(KeyParameterValue::HardwareAuthenticatorType(HardwareAuthenticatorType(1 << 20))
+        .validate_enum_ranges()
+        .is_err());
+
+    // Values that are not enums are not checked.
+    assert_eq!(KeyParameterValue::KeySize(-5).validate_enum_ranges(), Ok(()));
+    assert_eq!(KeyParameterValue::NoAuthRequired.validate_enum_ranges(), Ok(()));
+    assert_eq!(
+        KeyParameterValue::Unknown {
+            tag: Tag(TagType::ENUM.0 | 0xfff),
+            value: KmKeyParameterValue::Integer(9999)
+        }
+        .validate_enum_ranges(),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_logd_events() {
+    let tee = SecurityLevel::TRUSTED_ENVIRONMENT;
+    let params = vec![
+        KeyParameter::new(KeyParameterValue::KeySize(256), tee),
+        KeyParameter::new(KeyParameterValue::Digest(Digest::SHA_2_256), tee),
+        KeyParameter::new(KeyParameterValue::ApplicationID(b"secret".to_vec()), tee),
+        KeyParameter::new(KeyParameterValue::IncludeUniqueID, SecurityLevel::KEYSTORE),
+    ];
+    let events = params_to_logd_events(7, &params);
+    assert_eq!(
+        events[0],
+        "keystore2|key_id=7|tag=KEY_SIZE|value=256|security_level=TRUSTED_ENVIRONMENT"
+    );
+
+    let parsed: Vec<HashMap<&str, &str>> = events
+        .iter()
+        .map(|event| {
+            let mut fields = event.split('|');
+            assert_eq!(fields.next(), Some("keystore2"));
+            fields.map(|field| field.split_once('=').expect("Field is not key=value.")).collect()
+        })
+        .collect();
+    for (fields, param) in parsed.iter().zip(&params) {
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields["key_id"], "7");
+        assert_eq!(fields["tag"], format!("{:?}", param.get_tag()));
+        assert_eq!(fields["security_level"], format!("{:?}", param.security_level()));
+    }
+    assert_eq!(parsed[1]["value"], "SHA_2_256");
+    assert_eq!(parsed[2]["value"], "<redacted>");
+    assert_eq!(parsed[3]["value"], "<redacted>");
+    assert!(events.iter().all(|event| !event.contains("secret")));
+}
+
+#[test]
+fn test_matches_filter() {
+    use KeyParameterFilter::*;
+    let tee = SecurityLevel::TRUSTED_ENVIRONMENT;
+    let key = |values: Vec<KeyParameterValue>| -> Vec<KeyParameter> {
+        values.into_iter().map(|v| KeyParameter::new(v, tee)).collect()
+    };
+    let keys = [
+        key(vec![
+            KeyParameterValue::Algorithm(Algorithm::EC),
+            KeyParameterValue::EcCurve(EcCurve::P_256),
+            KeyParameterValue::ApplicationID(b"com.example.app".to_vec()),
+        ]),
+        key(vec![
+            KeyParameterValue::Algorithm(Algorithm::EC),
+            KeyParameterValue::EcCurve(EcCurve::P_384),
+        ]),
+        key(vec![KeyParameterValue::Algorithm(Algorithm::RSA), KeyParameterValue::KeySize(2048)]),
+    ];
+
+    // Any EC key with the P-256 curve.
+    let ec =
+        TagWithIntegerRange { tag: Tag::ALGORITHM, min: Algorithm::EC.0, max: Algorithm::EC.0 };
+    let p256 =
+        TagWithIntegerRange { tag: Tag::EC_CURVE, min: EcCurve::P_256.0, max: EcCurve::P_256.0 };
+    let ec_p256 = Or(vec![ec.clone(), p256]);
+    let matching: Vec<usize> = keys
+        .iter()
+        .enumerate()
+        .filter(|(_, params)| filter_params(params, &ec_p256).len() == 2)
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(matching, vec![0]);
+
+    // Every curve other than P-256, and every algorithm other than EC.
+    let other_curves = And(vec![ExactTag(Tag::EC_CURVE), Not(Box::new(ec_p256.clone()))]);
+    assert_eq!(filter_params(&keys[1], &other_curves), vec![&keys[1][1]]);
+    let not_ec = And(vec![ExactTag(Tag::ALGORITHM), Not(Box::new(ec))]);
+    assert_eq!(filter_params(&keys[2], &not_ec), vec![&keys[2][0]]);
+
+    let key_size = TagWithIntegerRange { tag: Tag::KEY_SIZE, min: 2048, max: 4096 };
+    assert!(KeyParameterValue::KeySize(2048).matches_filter(&key_size));
+    assert!(!KeyParameterValue::KeySize(1024).matches_filter(&key_size));
+    assert!(!KeyParameterValue::MinMacLength(2048).matches_filter(&key_size));
+
+    let app = TagWithBlobPrefix { tag: Tag::APPLICATION_ID, prefix: b"com.example.".to_vec() };
+    assert_eq!(filter_params(&keys[0], &app), vec![&keys[0][2]]);
+    assert!(!KeyParameterValue::ApplicationID(b"com.other".to_vec()).matches_filter(&app));
+
+    assert!(KeyParameterValue::CallerNonce.matches_filter(&And(vec![])));
+    assert!(!KeyParameterValue::CallerNonce.matches_filter(&Or(vec![])));
+}
@@ -0,0 +1,174 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module implements the compact binary encoding of lists of key parameters written by
+//! `encode_blob`, and the canonical encodings that serve as digest input.
+
+use super::{
+    KeyParameter, KeyParameterValue, KeystoreError, Primitive, ResponseCode, SecurityLevel, Tag,
+};
+use anyhow::{Context, Result};
+use keystore2_crypto::{sha256, SHA256_LEN};
+
+/// Version of the format written by `encode_blob`. It must be incremented whenever the
+/// format changes, so that `decode_blob` rejects blobs it cannot interpret.
+const KEY_PARAMETER_BLOB_VERSION: u8 = 1;
+
+/// Encodes a list of key parameters into a single opaque blob, so that it can be stored in
+/// one database column and decoded again in one pass by `decode_blob`.
+///
+/// The blob starts with a version byte followed by the number of parameters as
+/// little-endian u32. Each parameter is encoded as its tag and security level as
+/// little-endian i32, followed by a byte indicating the primitive it wraps: 0 for none,
+/// 1 for i32, 2 for i64, and 3 for a blob. i32 and i64 are written in little-endian byte
+/// order, and blobs are prefixed by their length as little-endian u32.
+pub fn encode_blob(params: &[KeyParameter]) -> Vec<u8> {
+    let mut out = vec![KEY_PARAMETER_BLOB_VERSION];
+    out.extend_from_slice(&(params.len() as u32).to_le_bytes());
+    for p in params {
+        encode_blob_param(&mut out, p);
+    }
+    out
+}
+
+/// Appends the encoding of a single key parameter as described for `encode_blob`.
+fn encode_blob_param(out: &mut Vec<u8>, p: &KeyParameter) {
+    out.extend_from_slice(&p.get_tag().0.to_le_bytes());
+    out.extend_from_slice(&p.security_level.0.to_le_bytes());
+    encode_primitive(out, p.value.get_primitive());
+}
+
+/// Appends the primitive as described for `encode_blob`: a byte indicating the kind of the
+/// primitive followed by its little-endian encoding.
+fn encode_primitive(out: &mut Vec<u8>, p: Option<Primitive>) {
+    match p {
+        None => out.push(0),
+        Some(Primitive::I32(v)) => {
+            out.push(1);
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Some(Primitive::I64(v)) => {
+            out.push(2);
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Some(Primitive::Vec(v)) => {
+            out.push(3);
+            out.extend_from_slice(&(v.len() as u32).to_le_bytes());
+            out.extend_from_slice(&v);
+        }
+    }
+}
+
+impl KeyParameterValue {
+    /// Returns the canonical encoding of the value that does not depend on the platform or
+    /// on any serialization library, e.g., for use as a cache key or as digest input. The
+    /// encoding is the tag as little-endian i32 followed by the primitive as in
+    /// `encode_blob`: a byte indicating the kind of the primitive, and its little-endian
+    /// encoding, with blobs prefixed by their length. The security level is not part of the
+    /// encoding.
+    pub fn to_canonical_bytes(&self) -> Vec<u8> {
+        let mut out = self.get_tag().0.to_le_bytes().to_vec();
+        encode_primitive(&mut out, self.get_primitive());
+        out
+    }
+}
+
+/// Returns a deterministic encoding of the key parameters that is suitable as input for a
+/// digest such as SHA-256. The parameters are sorted by tag, value, and security level,
+/// i.e., by the `Ord` of KeyParameter, so the result does not depend on their order. Each
+/// parameter is encoded as the canonical encoding of its value, see
+/// `KeyParameterValue::to_canonical_bytes`, followed by its security level as little-endian
+/// i32.
+pub fn canonical_digest_input(params: &[KeyParameter]) -> Vec<u8> {
+    let mut sorted: Vec<&KeyParameter> = params.iter().collect();
+    sorted.sort();
+    let mut out = Vec::new();
+    for p in sorted {
+        out.append(&mut p.value.to_canonical_bytes());
+        out.extend_from_slice(&p.security_level.0.to_le_bytes());
+    }
+    out
+}
+
+/// Returns the SHA-256 digest of the canonical encodings of the key parameter values, see
+/// `KeyParameterValue::to_canonical_bytes`. The values are sorted before their encodings are
+/// concatenated, so the fingerprint does not depend on the order of the parameters. Unlike
+/// `canonical_digest_input`, the fingerprint ignores the security levels.
+pub fn key_parameter_set_fingerprint(params: &[KeyParameter]) -> Result<[u8; SHA256_LEN]> {
+    let mut sorted: Vec<&KeyParameterValue> = params.iter().map(|p| &p.value).collect();
+    sorted.sort();
+    let encoded: Vec<u8> = sorted.into_iter().flat_map(|v| v.to_canonical_bytes()).collect();
+    sha256(&encoded).context("In key_parameter_set_fingerprint: Failed to compute digest.")
+}
+
+/// Decodes a blob written by `encode_blob`. Fails with ResponseCode::VALUE_CORRUPTED if the
+/// blob has an unknown version, is truncated, has trailing bytes, or holds a value that does
+/// not match its tag.
+pub fn decode_blob(bytes: &[u8]) -> Result<Vec<KeyParameter>> {
+    let mut rest = bytes;
+    let version = take_blob_bytes::<1>(&mut rest)?[0];
+    if version != KEY_PARAMETER_BLOB_VERSION {
+        return Err(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+            .with_context(|| format!("In decode_blob: Unsupported version {}.", version));
+    }
+    let count = u32::from_le_bytes(take_blob_bytes(&mut rest)?);
+    // The count is not trusted for preallocation, because the blob may be corrupted.
+    let mut params = Vec::new();
+    for _ in 0..count {
+        let tag = Tag(i32::from_le_bytes(take_blob_bytes(&mut rest)?));
+        let security_level = SecurityLevel(i32::from_le_bytes(take_blob_bytes(&mut rest)?));
+        let primitive = match take_blob_bytes::<1>(&mut rest)?[0] {
+            0 => None,
+            1 => Some(Primitive::I32(i32::from_le_bytes(take_blob_bytes(&mut rest)?))),
+            2 => Some(Primitive::I64(i64::from_le_bytes(take_blob_bytes(&mut rest)?))),
+            3 => {
+                let len = u32::from_le_bytes(take_blob_bytes(&mut rest)?) as usize;
+                if rest.len() < len {
+                    return Err(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+                        .context("In decode_blob: Truncated blob value.");
+                }
+                let (v, tail) = rest.split_at(len);
+                rest = tail;
+                Some(Primitive::Vec(v.to_vec()))
+            }
+            kind => {
+                return Err(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+                    .with_context(|| format!("In decode_blob: Unknown primitive kind {}.", kind));
+            }
+        };
+        let value = KeyParameterValue::new_from_tag_optional_primitive(tag, primitive)
+            .map_err(|_| KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+            .with_context(|| format!("In decode_blob: Invalid value for {:?}.", tag))?;
+        params.push(KeyParameter::new(value, security_level));
+    }
+    if !rest.is_empty() {
+        return Err(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+            .with_context(|| format!("In decode_blob: {} trailing bytes.", rest.len()));
+    }
+    Ok(params)
+}
+
+/// Removes the first N bytes from the front of the blob and returns them. Fails with
+/// ResponseCode::VALUE_CORRUPTED if the blob is too short.
+fn take_blob_bytes<const N: usize>(rest: &mut &[u8]) -> Result<[u8; N]> {
+    if rest.len() < N {
+        return Err(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+            .context("In decode_blob: Unexpected end of blob.");
+    }
+    let (head, tail) = rest.split_at(N);
+    *rest = tail;
+    let mut out = [0u8; N];
+    out.copy_from_slice(head);
+    Ok(out)
+}
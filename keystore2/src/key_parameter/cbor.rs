@@ -0,0 +1,191 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module implements the deterministic CBOR encoding of key parameters. It is only built
+//! with the `cbor` feature.
+
+use super::{
+    tag_type_of, AssociatePrimitive, KeyParameter, KeyParameterValue, KeystoreError, Primitive,
+    ResponseCode, SecurityLevel, Tag, TagType,
+};
+use anyhow::{Context, Result};
+
+/// Builds a CBOR map from the given entries with text keys. The entries are sorted by the
+/// bytewise lexicographic order of the encoded keys as required for deterministic encoding by
+/// RFC 8949, section 4.2.1.
+fn cbor_map(entries: Vec<(&str, ciborium::value::Value)>) -> ciborium::value::Value {
+    use ciborium::value::Value;
+
+    let mut entries: Vec<(Vec<u8>, Value, Value)> = entries
+        .into_iter()
+        .map(|(k, v)| {
+            let key = Value::Text(k.to_string());
+            let mut encoded = Vec::new();
+            ciborium::into_writer(&key, &mut encoded).expect("In cbor_map: Encoding failed.");
+            (encoded, key, v)
+        })
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    Value::Map(entries.into_iter().map(|(_, k, v)| (k, v)).collect())
+}
+
+/// Splits the CBOR map into the values of the given keys. Fails if the value is not a map or
+/// if the map holds other keys. Missing keys yield None.
+fn cbor_map_fields<const N: usize>(
+    value: ciborium::value::Value,
+    keys: [&str; N],
+) -> Result<[Option<ciborium::value::Value>; N]> {
+    let corrupted = || KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED);
+    let map =
+        value.into_map().map_err(|_| corrupted()).context("In cbor_map_fields: Expected a map.")?;
+    let mut fields = [(); N].map(|_| None);
+    for (k, v) in map {
+        match k.as_text().and_then(|k| keys.iter().position(|key| *key == k)) {
+            Some(i) => fields[i] = Some(v),
+            None => {
+                return Err(corrupted())
+                    .with_context(|| format!("In cbor_map_fields: Unexpected key {:?}.", k))
+            }
+        }
+    }
+    Ok(fields)
+}
+
+/// Encodes the primitive as CBOR integer or byte string.
+fn primitive_to_cbor(p: Primitive) -> ciborium::value::Value {
+    use ciborium::value::Value;
+
+    match p {
+        Primitive::I32(v) => Value::Integer(v.into()),
+        Primitive::I64(v) => Value::Integer(v.into()),
+        Primitive::Vec(v) => Value::Bytes(v),
+    }
+}
+
+impl KeyParameter {
+    /// Encodes this key parameter as CBOR map `{"value": value, "security_level": int}`,
+    /// where the value is encoded as by `KeyParameterValue::to_cbor` and the security level
+    /// by its primitive representation. The encoding is deterministic as defined by RFC 8949,
+    /// section 4.2.1: map keys are sorted and integers use their shortest form, so equal key
+    /// parameters always produce identical bytes.
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let level = primitive_to_cbor(self.security_level.to_primitive().into());
+        let map = cbor_map(vec![("value", self.value.to_cbor_value()), ("security_level", level)]);
+        let mut out = Vec::new();
+        // Writing into a Vec cannot fail.
+        ciborium::into_writer(&map, &mut out).expect("In KeyParameter::to_cbor: Encoding failed.");
+        out
+    }
+
+    /// Decodes a key parameter from its CBOR encoding as produced by `to_cbor`.
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self> {
+        let corrupted = || KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED);
+        let map = ciborium::from_reader(bytes)
+            .map_err(|_| corrupted())
+            .context("In KeyParameter::from_cbor: Failed to decode.")?;
+        let [value, level] = cbor_map_fields(map, ["value", "security_level"])
+            .context("In KeyParameter::from_cbor.")?;
+        let value = KeyParameterValue::from_cbor_value(
+            value.ok_or_else(corrupted).context("In KeyParameter::from_cbor: Missing value.")?,
+        )
+        .context("In KeyParameter::from_cbor.")?;
+        let security_level = level
+            .and_then(|l| l.as_integer())
+            .and_then(|l| i32::try_from(l).ok())
+            .map(SecurityLevel::from_primitive)
+            .ok_or_else(corrupted)
+            .context("In KeyParameter::from_cbor: Missing or invalid security level.")?;
+        Ok(Self::new(value, security_level))
+    }
+}
+
+impl KeyParameterValue {
+    /// Encodes the value as CBOR map `{"tag": int, "value": primitive}`. Enum values are
+    /// encoded as their integer representation, blobs as byte strings, and boolean
+    /// parameters as `true`. Invalid is encoded with its integer payload. The encoding is
+    /// deterministic, see `KeyParameter::to_cbor`.
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        // Writing into a Vec cannot fail.
+        ciborium::into_writer(&self.to_cbor_value(), &mut out)
+            .expect("In KeyParameterValue::to_cbor: Encoding failed.");
+        out
+    }
+
+    /// Decodes a value from its CBOR encoding as produced by `to_cbor`.
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self> {
+        let value = ciborium::from_reader(bytes)
+            .map_err(|_| KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+            .context("In KeyParameterValue::from_cbor: Failed to decode.")?;
+        Self::from_cbor_value(value).context("In KeyParameterValue::from_cbor.")
+    }
+
+    /// Returns the CBOR map encoded by `to_cbor`.
+    fn to_cbor_value(&self) -> ciborium::value::Value {
+        use ciborium::value::Value;
+
+        let mut entries = vec![("tag", Value::Integer(self.get_tag().0.into()))];
+        let value = match self.get_primitive() {
+            Some(p) => Some(primitive_to_cbor(p)),
+            None if self.tag_type() == TagType::BOOL => Some(Value::Bool(true)),
+            None => None,
+        };
+        if let Some(value) = value {
+            entries.push(("value", value));
+        }
+        cbor_map(entries)
+    }
+
+    /// Parses the CBOR map produced by `to_cbor_value`.
+    fn from_cbor_value(map: ciborium::value::Value) -> Result<Self> {
+        use ciborium::value::Value;
+
+        let corrupted = || KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED);
+        let [tag, value] = cbor_map_fields(map, ["tag", "value"])
+            .context("In KeyParameterValue::from_cbor_value.")?;
+        let tag = tag
+            .and_then(|t| t.as_integer())
+            .and_then(|t| i32::try_from(t).ok())
+            .map(Tag)
+            .ok_or_else(corrupted)
+            .context("In KeyParameterValue::from_cbor_value: Missing or invalid tag.")?;
+
+        let primitive = match (tag_type_of(tag), value) {
+            (_, None) | (TagType::BOOL, Some(Value::Bool(true))) => None,
+            (
+                TagType::INVALID
+                | TagType::ENUM
+                | TagType::ENUM_REP
+                | TagType::UINT
+                | TagType::UINT_REP,
+                Some(Value::Integer(v)),
+            ) => i32::try_from(v).ok().map(Primitive::I32),
+            (TagType::ULONG | TagType::ULONG_REP | TagType::DATE, Some(Value::Integer(v))) => {
+                i64::try_from(v).ok().map(Primitive::I64)
+            }
+            (TagType::BIGNUM | TagType::BYTES, Some(Value::Bytes(v))) => Some(Primitive::Vec(v)),
+            (_, Some(v)) => {
+                return Err(corrupted()).with_context(|| {
+                    format!(
+                        "In KeyParameterValue::from_cbor_value: Invalid value {:?} for {:?}.",
+                        v, tag
+                    )
+                })
+            }
+        };
+        Self::new_from_tag_optional_primitive(tag, primitive).map_err(|_| corrupted()).with_context(
+            || format!("In KeyParameterValue::from_cbor_value: Invalid value for {:?}.", tag),
+        )
+    }
+}
@@ -0,0 +1,199 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The cbor_tests module checks that every KeyParameter survives a round trip through
+//! `to_cbor` and `from_cbor` and that the encoding is deterministic. It also covers the
+//! language-neutral `{tag, value}` encoding of KeyParameterValue.
+
+use super::*;
+use proptest::prelude::*;
+
+/// Replaces the value of the given wire key parameter with one built from the given primitives,
+/// keeping the union field that the tag expects.
+fn with_value(kp: KmKeyParameter, i: i32, l: i64, b: Vec<u8>) -> KmKeyParameter {
+    let value = match kp.value {
+        KmKeyParameterValue::Algorithm(_) => KmKeyParameterValue::Algorithm(Algorithm(i)),
+        KmKeyParameterValue::BlockMode(_) => KmKeyParameterValue::BlockMode(BlockMode(i)),
+        KmKeyParameterValue::PaddingMode(_) => KmKeyParameterValue::PaddingMode(PaddingMode(i)),
+        KmKeyParameterValue::Digest(_) => KmKeyParameterValue::Digest(Digest(i)),
+        KmKeyParameterValue::EcCurve(_) => KmKeyParameterValue::EcCurve(EcCurve(i)),
+        KmKeyParameterValue::Origin(_) => KmKeyParameterValue::Origin(KeyOrigin(i)),
+        KmKeyParameterValue::KeyPurpose(_) => KmKeyParameterValue::KeyPurpose(KeyPurpose(i)),
+        KmKeyParameterValue::HardwareAuthenticatorType(_) => {
+            KmKeyParameterValue::HardwareAuthenticatorType(HardwareAuthenticatorType(i))
+        }
+        KmKeyParameterValue::SecurityLevel(_) => {
+            KmKeyParameterValue::SecurityLevel(SecurityLevel(i))
+        }
+        KmKeyParameterValue::Integer(_) => KmKeyParameterValue::Integer(i),
+        KmKeyParameterValue::LongInteger(_) => KmKeyParameterValue::LongInteger(l),
+        KmKeyParameterValue::DateTime(_) => KmKeyParameterValue::DateTime(l),
+        KmKeyParameterValue::Blob(_) => KmKeyParameterValue::Blob(b),
+        KmKeyParameterValue::BoolValue(_) => KmKeyParameterValue::BoolValue(true),
+        v => v,
+    };
+    KmKeyParameter { tag: kp.tag, value }
+}
+
+fn arb_key_parameter() -> impl Strategy<Value = KeyParameter> {
+    let templates = KeyParameterValue::make_field_matches_tag_type_test_vector();
+    (
+        0..templates.len(),
+        any::<i32>(),
+        any::<i64>(),
+        proptest::collection::vec(any::<u8>(), 0..64),
+        any::<i32>(),
+    )
+        .prop_map(move |(index, i, l, b, level)| {
+            KeyParameter::new(
+                KeyParameterValue::from_km_parameter_lossy(with_value(
+                    templates[index].clone(),
+                    i,
+                    l,
+                    b,
+                )),
+                SecurityLevel(level),
+            )
+        })
+}
+
+#[test]
+fn cbor_round_trip_defaults() {
+    for param in KeyParameterValue::make_key_parameter_defaults_vector() {
+        let encoded = param.to_cbor();
+        let decoded = KeyParameter::from_cbor(&encoded).expect("Failed to decode key parameter.");
+        assert_eq!(param, decoded);
+    }
+}
+
+#[test]
+fn cbor_round_trip_unknown_tag() {
+    let param = KeyParameter::new(
+        KeyParameterValue::Unknown {
+            tag: Tag(TagType::BYTES.0 | 0xfff),
+            value: KmKeyParameterValue::Blob(vec![1, 2, 3]),
+        },
+        SecurityLevel::STRONGBOX,
+    );
+    let decoded = KeyParameter::from_cbor(&param.to_cbor()).expect("Failed to decode.");
+    assert_eq!(param, decoded);
+}
+
+#[test]
+fn cbor_is_deterministic() {
+    let param = KeyParameter::new(
+        KeyParameterValue::ApplicationID(vec![0xde, 0xad]),
+        SecurityLevel::TRUSTED_ENVIRONMENT,
+    );
+    assert_eq!(param.to_cbor(), param.clone().to_cbor());
+    // The map keys are sorted by their encoded bytes: the encoding of "value" (0x65) sorts
+    // before the encoding of "security_level" (0x6e), and "tag" (0x63) before "value".
+    let encoded = param.to_cbor();
+    assert_eq!(encoded[0], 0xa2);
+    assert_eq!(&encoded[1..7], b"evalue");
+    assert_eq!(encoded[7], 0xa2);
+    assert_eq!(&encoded[8..12], b"ctag");
+}
+
+#[test]
+fn cbor_reuses_value_encoding() {
+    use ciborium::value::Value;
+
+    let value = KeyParameterValue::Algorithm(Algorithm::EC);
+    let param = KeyParameter::new(value.clone(), SecurityLevel::STRONGBOX);
+    let decoded: Value = ciborium::from_reader(&param.to_cbor()[..]).unwrap();
+    let value_encoding: Value = ciborium::from_reader(&value.to_cbor()[..]).unwrap();
+    assert_eq!(
+        decoded,
+        Value::Map(vec![
+            (Value::Text("value".to_string()), value_encoding),
+            (Value::Text("security_level".to_string()), Value::Integer(2.into())),
+        ])
+    );
+}
+
+#[test]
+fn cbor_rejects_garbage() {
+    assert!(KeyParameter::from_cbor(&[0xff, 0x00]).is_err());
+    assert!(KeyParameter::from_cbor(&[]).is_err());
+    // Maps with unexpected keys are rejected.
+    let mut encoded = Vec::new();
+    ciborium::into_writer(
+        &ciborium::value::Value::Map(vec![(
+            ciborium::value::Value::Text("level".to_string()),
+            ciborium::value::Value::Integer(1.into()),
+        )]),
+        &mut encoded,
+    )
+    .unwrap();
+    assert!(KeyParameter::from_cbor(&encoded).is_err());
+}
+
+proptest! {
+    #[test]
+    fn cbor_round_trip(param in arb_key_parameter()) {
+        let encoded = param.to_cbor();
+        prop_assert_eq!(KeyParameter::from_cbor(&encoded).unwrap(), param.clone());
+        prop_assert_eq!(encoded, param.to_cbor());
+    }
+}
+
+#[test]
+fn value_cbor_round_trip_per_field_type() {
+    let values = vec![
+        KeyParameterValue::KeySize(256),
+        KeyParameterValue::RSAPublicExponent(65537),
+        KeyParameterValue::ApplicationID(vec![0xde, 0xad, 0xbe, 0xef]),
+        KeyParameterValue::CallerNonce,
+        KeyParameterValue::CreationDateTime(1704067200000),
+        KeyParameterValue::Algorithm(Algorithm::EC),
+        KeyParameterValue::Invalid(7),
+    ];
+    for value in values {
+        let encoded = value.to_cbor();
+        assert_eq!(KeyParameterValue::from_cbor(&encoded).unwrap(), value);
+    }
+}
+
+#[test]
+fn value_cbor_is_language_neutral() {
+    use ciborium::value::Value;
+
+    let encoded = KeyParameterValue::Algorithm(Algorithm::EC).to_cbor();
+    let decoded: Value = ciborium::from_reader(&encoded[..]).unwrap();
+    assert_eq!(
+        decoded,
+        Value::Map(vec![
+            (Value::Text("tag".to_string()), Value::Integer(Tag::ALGORITHM.0.into())),
+            (Value::Text("value".to_string()), Value::Integer(Algorithm::EC.0.into())),
+        ])
+    );
+}
+
+#[test]
+fn value_cbor_rejects_mismatched_value() {
+    use ciborium::value::Value;
+
+    let mut encoded = Vec::new();
+    ciborium::into_writer(
+        &Value::Map(vec![
+            (Value::Text("tag".to_string()), Value::Integer(Tag::KEY_SIZE.0.into())),
+            (Value::Text("value".to_string()), Value::Bytes(vec![1])),
+        ]),
+        &mut encoded,
+    )
+    .unwrap();
+    assert!(KeyParameterValue::from_cbor(&encoded).is_err());
+    assert!(KeyParameterValue::from_cbor(&[0xff]).is_err());
+}
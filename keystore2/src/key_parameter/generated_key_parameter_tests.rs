@@ -83,6 +83,7 @@ fn key_parameter_value_field_matches_tag_type() {
     check_field_matches_tag_type(&KeyParameterValue::make_field_matches_tag_type_test_vector());
 }
 
+#[cfg(feature = "serde")]
 #[test]
 fn key_parameter_serialization_test() {
     let params = KeyParameterValue::make_key_parameter_defaults_vector();
@@ -93,3 +94,211 @@ fn key_parameter_serialization_test() {
             .expect("Failed to deserialize key parameters.");
     assert_eq!(params, deserialized_params);
 }
+
+#[cfg(feature = "serde")]
+#[test]
+fn key_parameter_serialization_unknown_tag_test() {
+    let params = vec![
+        KeyParameter::new(
+            KeyParameterValue::Unknown {
+                tag: Tag(TagType::BOOL.0 | 0xfff),
+                value: KmKeyParameterValue::BoolValue(true),
+            },
+            SecurityLevel::TRUSTED_ENVIRONMENT,
+        ),
+        KeyParameter::new(
+            KeyParameterValue::Unknown {
+                tag: Tag(TagType::BYTES.0 | 0xfff),
+                value: KmKeyParameterValue::Blob(vec![1, 2]),
+            },
+            SecurityLevel::TRUSTED_ENVIRONMENT,
+        ),
+    ];
+    let mut out_buffer: Vec<u8> = Default::default();
+    serde_cbor::to_writer(&mut out_buffer, &params).expect("Failed to serialize key parameters.");
+    let deserialized_params: Vec<KeyParameter> =
+        serde_cbor::from_reader(&mut out_buffer.as_slice())
+            .expect("Failed to deserialize key parameters.");
+    assert_eq!(params, deserialized_params);
+}
+
+#[test]
+fn key_parameter_value_tag_type_test() {
+    for param in KeyParameterValue::make_key_parameter_defaults_vector() {
+        let value = param.key_parameter_value();
+        let tag_type = value.tag_type();
+        assert_eq!(tag_type, TagType((value.get_tag().0 as u32 & 0xF0000000) as i32));
+        let km_param: KmKeyParameter = value.clone().into();
+        let expected: &[TagType] = match km_param.value {
+            KmKeyParameterValue::Invalid(_) => &[TagType::INVALID],
+            KmKeyParameterValue::Integer(_) => &[TagType::UINT, TagType::UINT_REP],
+            KmKeyParameterValue::LongInteger(_) => &[TagType::ULONG, TagType::ULONG_REP],
+            KmKeyParameterValue::DateTime(_) => &[TagType::DATE],
+            KmKeyParameterValue::BoolValue(_) => &[TagType::BOOL],
+            KmKeyParameterValue::Blob(_) => &[TagType::BYTES, TagType::BIGNUM],
+            _ => &[TagType::ENUM, TagType::ENUM_REP],
+        };
+        assert!(
+            expected.contains(&tag_type),
+            "{:?} has tag type {:?}, expected one of {:?}",
+            value,
+            tag_type,
+            expected
+        );
+    }
+}
+
+#[test]
+fn key_parameter_authorization_round_trip_test() {
+    let params = KeyParameterValue::make_key_parameter_defaults_vector();
+    for param in &params {
+        assert_eq!(
+            &KeyParameter::from_authorization_lossy(param.clone().into_authorization()),
+            param
+        );
+    }
+    let auths = KeyParameter::into_authorizations(params.clone());
+    assert_eq!(auths.len(), params.len());
+    for (auth, param) in auths.iter().zip(&params) {
+        assert_eq!(auth.securityLevel, param.security_level);
+        assert_eq!(auth.keyParameter, param.value.clone().into());
+    }
+    assert_eq!(KeyParameter::from_authorizations_lossy(auths), params);
+
+    let auths: Vec<Authorization> = params.iter().cloned().map(Authorization::from).collect();
+    let round_trip: Vec<KeyParameter> =
+        auths.into_iter().map(KeyParameter::from_authorization_lossy).collect();
+    assert_eq!(round_trip, params);
+}
+
+#[test]
+fn key_parameter_authorization_keeps_operation_parameter_levels() {
+    // Operation parameters are never key characteristics, so their security level has no
+    // meaning in an Authorization. It is kept anyway, so the round trip is deterministic.
+    let params = vec![
+        KeyParameter::new(KeyParameterValue::Nonce(vec![1, 2, 3]), SecurityLevel::STRONGBOX),
+        KeyParameter::new(KeyParameterValue::AssociatedData(vec![4]), SecurityLevel::KEYSTORE),
+        KeyParameter::new(KeyParameterValue::MacLength(128), SecurityLevel::SOFTWARE),
+    ];
+    let auths: Vec<Authorization> = params.iter().cloned().map(Authorization::from).collect();
+    assert_eq!(
+        auths.iter().map(|a| a.securityLevel).collect::<Vec<_>>(),
+        vec![SecurityLevel::STRONGBOX, SecurityLevel::KEYSTORE, SecurityLevel::SOFTWARE]
+    );
+    let round_trip: Vec<KeyParameter> =
+        auths.into_iter().map(KeyParameter::from_authorization_lossy).collect();
+    assert_eq!(round_trip, params);
+}
+
+#[test]
+fn key_parameter_value_ref_round_trip_test() {
+    for param in KeyParameterValue::make_key_parameter_defaults_vector() {
+        let value = param.key_parameter_value();
+        assert_eq!(&KeyParameterValue::from(value.as_ref()), value);
+        assert_eq!(KmKeyParameter::from(value.as_ref()), KmKeyParameter::from(value.clone()));
+    }
+}
+
+#[test]
+fn key_parameter_field_type_of_test() {
+    let primitives = [Primitive::I32(1), Primitive::I64(1), Primitive::Vec(vec![1])];
+    for km_param in KeyParameterValue::make_field_matches_tag_type_test_vector() {
+        let field_type = field_type_of(km_param.tag).expect("Tag should be known.");
+        let expected = match km_param.value {
+            KmKeyParameterValue::Invalid(_) => FieldType::Invalid,
+            KmKeyParameterValue::Algorithm(_) => FieldType::Algorithm,
+            KmKeyParameterValue::BlockMode(_) => FieldType::BlockMode,
+            KmKeyParameterValue::PaddingMode(_) => FieldType::PaddingMode,
+            KmKeyParameterValue::Digest(_) => FieldType::Digest,
+            KmKeyParameterValue::EcCurve(_) => FieldType::EcCurve,
+            KmKeyParameterValue::Origin(_) => FieldType::Origin,
+            KmKeyParameterValue::KeyPurpose(_) => FieldType::KeyPurpose,
+            KmKeyParameterValue::HardwareAuthenticatorType(_) => {
+                FieldType::HardwareAuthenticatorType
+            }
+            KmKeyParameterValue::SecurityLevel(_) => FieldType::SecurityLevel,
+            KmKeyParameterValue::Integer(_) => FieldType::Integer,
+            KmKeyParameterValue::LongInteger(_) => FieldType::LongInteger,
+            KmKeyParameterValue::DateTime(_) => FieldType::DateTime,
+            KmKeyParameterValue::Blob(_) => FieldType::Blob,
+            KmKeyParameterValue::BoolValue(_) => FieldType::BoolValue,
+        };
+        assert_eq!(field_type, expected);
+        for p in &primitives {
+            assert_eq!(
+                field_type.accepts(p),
+                !matches!(
+                    KeyParameterValue::new_from_tag_primitive_pair(km_param.tag, p.clone()),
+                    Err(KeyParameterError::InvalidPrimitive(PrimitiveError::TypeMismatch { .. }))
+                ),
+                "{:?} with {:?}",
+                km_param.tag,
+                p
+            );
+        }
+    }
+    assert_eq!(field_type_of(Tag(TagType::UINT.0 | 0xfff)), None);
+}
+
+#[test]
+fn key_parameter_all_known_tags_test() {
+    let defaults = KeyParameterValue::make_key_parameter_defaults_vector();
+    let infos: Vec<TagInfo> = all_known_tags().collect();
+    assert_eq!(infos.len(), defaults.len());
+    for (info, param) in infos.iter().zip(&defaults) {
+        let value = param.key_parameter_value();
+        assert_eq!(info.tag, value.get_tag());
+        assert_eq!(info.variant, value.variant_name());
+        assert_eq!(Some(info.field_type), field_type_of(info.tag));
+        assert_eq!(info.has_value, value.get_primitive().is_some(), "{:?}", info);
+    }
+    let algorithm = infos.iter().find(|info| info.tag == Tag::ALGORITHM).unwrap();
+    assert_eq!(algorithm.name, "ALGORITHM");
+    assert_eq!(algorithm.field_type, FieldType::Algorithm);
+    assert!(algorithm.has_value);
+}
+
+#[test]
+fn key_parameter_tag_category_test() {
+    assert_eq!(tag_category(Tag::ATTESTATION_ID_BRAND), TagCategory::Attestation);
+    assert_eq!(tag_category(Tag::ACTIVE_DATETIME), TagCategory::Lifecycle);
+    assert_eq!(tag_category(Tag::ALGORITHM), TagCategory::CryptoParams);
+    assert_eq!(tag_category(Tag::USER_SECURE_ID), TagCategory::AuthAndAccess);
+    assert_eq!(tag_category(Tag::OS_PATCHLEVEL), TagCategory::DeviceInfo);
+    assert_eq!(tag_category(Tag::INVALID), TagCategory::Other);
+    assert_eq!(tag_category(Tag(TagType::BYTES.0 | 0xfff)), TagCategory::Other);
+    // Every known tag except INVALID has a category.
+    let uncategorized: Vec<Tag> = all_known_tags()
+        .map(|info| info.tag)
+        .filter(|tag| tag_category(*tag) == TagCategory::Other)
+        .collect();
+    assert_eq!(uncategorized, vec![Tag::INVALID]);
+}
+
+/// Tags that are defined by the KeyMint AIDL spec but deliberately have no KeyParameterValue
+/// variant. Parameters with these tags are preserved as KeyParameterValue::Unknown.
+const UNSUPPORTED_TAGS: &[Tag] = &[
+    Tag::HARDWARE_TYPE,
+    Tag::DEVICE_UNIQUE_ATTESTATION,
+    Tag::IDENTITY_CREDENTIAL_KEY,
+    Tag::STORAGE_KEY,
+    Tag::MODULE_HASH,
+];
+
+#[test]
+fn every_tag_is_handled() {
+    let unhandled: Vec<Tag> = Tag::enum_values()
+        .into_iter()
+        .filter(|tag| !UNSUPPORTED_TAGS.contains(tag))
+        .filter(|tag| field_type_of(*tag).is_none())
+        .collect();
+    assert!(
+        unhandled.is_empty(),
+        "{:?} have no KeyParameterValue variant. Add variants or extend UNSUPPORTED_TAGS.",
+        unhandled
+    );
+    // Keep the allow-list tight: Once a variant is added, the tag must be removed from it.
+    for tag in UNSUPPORTED_TAGS {
+        assert_eq!(field_type_of(*tag), None, "{:?} is handled but listed as unsupported.", tag);
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module implements the JSON representation of key parameters. It is only built with the
+//! `json` feature.
+
+use super::{
+    enum_value_from_name, km_enum_value_name, tag_type_of, value_from_name, KeyParameter,
+    KeyParameterValue, KeystoreError, KmKeyParameter, KmKeyParameterValue, Primitive, ResponseCode,
+    SecurityLevel, Tag, TagType,
+};
+use anyhow::{Context, Result};
+
+/// Returns the JSON representation of the given KeyMint value. Enum values are given by their
+/// symbolic names, and blobs are base64 encoded.
+fn km_value_to_json(value: &KmKeyParameterValue) -> serde_json::Value {
+    use base64::Engine;
+
+    match value {
+        KmKeyParameterValue::Invalid(v) | KmKeyParameterValue::Integer(v) => (*v).into(),
+        KmKeyParameterValue::BoolValue(v) => (*v).into(),
+        KmKeyParameterValue::LongInteger(v) | KmKeyParameterValue::DateTime(v) => (*v).into(),
+        KmKeyParameterValue::Blob(v) => base64::engine::general_purpose::STANDARD.encode(v).into(),
+        // All remaining fields hold enum values.
+        _ => km_enum_value_name(value).into(),
+    }
+}
+
+/// Returns the string value of the given field of a JSON object.
+fn json_str_field<'a>(value: &'a serde_json::Value, name: &str) -> Result<&'a str> {
+    let field = value
+        .get(name)
+        .ok_or(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+        .with_context(|| format!("In json_str_field: Missing field \"{}\".", name))?;
+    field
+        .as_str()
+        .ok_or(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+        .with_context(|| format!("In json_str_field: Expected a string, found {}.", field))
+}
+
+impl KeyParameterValue {
+    /// Returns the JSON representation `{"tag": <name>, "value": <value>}`, e.g.,
+    /// `{"tag":"PURPOSE","value":"SIGN"}`. The tag is given by its AIDL name. The value is
+    /// typed as follows:
+    ///  * Enum values are strings holding the AIDL name of the value.
+    ///  * Integer, long integer, and date time values are numbers.
+    ///  * Blobs are base64 encoded strings using the standard alphabet with padding.
+    ///  * Boolean parameters have the value `true`.
+    ///  * Invalid has its integer payload as value. `null` is accepted as payload 0.
+    ///
+    /// Tags and enum values unknown to Keystore are given as decimal strings.
+    /// Unlike the generic serde implementation, this form is stable and readable by
+    /// non-Rust clients. As in the Display representation, the values of sensitive
+    /// parameters, see `is_sensitive`, are replaced with the string `<redacted>`, which
+    /// `from_json_value` does not accept.
+    pub fn to_json_value(&self) -> serde_json::Value {
+        let km_param: KmKeyParameter = self.clone().into();
+        let value = if self.is_sensitive() && self.tag_type() != TagType::BOOL {
+            serde_json::Value::from("<redacted>")
+        } else {
+            km_value_to_json(&km_param.value)
+        };
+        serde_json::json!({
+            "tag": format!("{:?}", km_param.tag),
+            "value": value,
+        })
+    }
+
+    /// Parses a value from the JSON representation produced by `to_json_value`. Symbolic
+    /// names of tags and enum values are resolved to their numeric values. Fields other than
+    /// "tag" and "value" are ignored.
+    pub fn from_json_value(v: &serde_json::Value) -> Result<Self> {
+        let corrupted = || KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED);
+
+        let tag_name =
+            json_str_field(v, "tag").context("In KeyParameterValue::from_json_value.")?;
+        let tag = value_from_name(&Tag::enum_values(), tag_name, |t| t.0)
+            .map(Tag)
+            .ok_or_else(corrupted)
+            .with_context(|| {
+                format!("In KeyParameterValue::from_json_value: Unknown tag {}.", tag_name)
+            })?;
+
+        let json_value = v
+            .get("value")
+            .ok_or_else(corrupted)
+            .context("In KeyParameterValue::from_json_value: Missing field \"value\".")?;
+        let mismatch = || {
+            Err(corrupted()).with_context(|| {
+                format!(
+                    "In KeyParameterValue::from_json_value: Invalid value {} for {:?}.",
+                    json_value, tag
+                )
+            })
+        };
+        let primitive = match (tag_type_of(tag), json_value) {
+            (TagType::INVALID, serde_json::Value::Null) => None,
+            (TagType::BOOL, serde_json::Value::Bool(true)) => None,
+            (TagType::ENUM | TagType::ENUM_REP, serde_json::Value::String(v)) => {
+                match enum_value_from_name(tag, v) {
+                    Some(v) => Some(Primitive::I32(v)),
+                    None => return mismatch(),
+                }
+            }
+            (
+                TagType::INVALID
+                | TagType::ENUM
+                | TagType::ENUM_REP
+                | TagType::UINT
+                | TagType::UINT_REP,
+                serde_json::Value::Number(v),
+            ) => match v.as_i64().and_then(|v| i32::try_from(v).ok()) {
+                Some(v) => Some(Primitive::I32(v)),
+                None => return mismatch(),
+            },
+            (TagType::ULONG | TagType::ULONG_REP | TagType::DATE, serde_json::Value::Number(v)) => {
+                match v.as_i64() {
+                    Some(v) => Some(Primitive::I64(v)),
+                    None => return mismatch(),
+                }
+            }
+            (TagType::BIGNUM | TagType::BYTES, serde_json::Value::String(v)) => {
+                use base64::Engine;
+                match base64::engine::general_purpose::STANDARD.decode(v) {
+                    Ok(v) => Some(Primitive::Vec(v)),
+                    Err(_) => return mismatch(),
+                }
+            }
+            _ => return mismatch(),
+        };
+        Self::new_from_tag_optional_primitive(tag, primitive)
+            .context("In KeyParameterValue::from_json_value.")
+    }
+}
+
+impl KeyParameter {
+    /// Returns a human readable JSON representation for debugging and dumpsys output, e.g.,
+    /// `{"tag":"PURPOSE","value":"SIGN","securityLevel":"TRUSTED_ENVIRONMENT"}`.
+    /// Unlike the compact numeric form produced by the serde implementation, tags, enum values,
+    /// and security levels are given by their symbolic names. See
+    /// `KeyParameterValue::to_json_value` for the representation of the values.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut json = self.value.to_json_value();
+        json["securityLevel"] = format!("{:?}", self.security_level).into();
+        json
+    }
+
+    /// Parses a key parameter from the JSON representation produced by `to_json`. Symbolic
+    /// names of tags, enum values, and security levels are resolved to their numeric values.
+    /// Values that have no symbolic name, e.g., tags unknown to Keystore, may be given as
+    /// decimal strings.
+    pub fn from_json(value: &serde_json::Value) -> Result<Self> {
+        let level_name =
+            json_str_field(value, "securityLevel").context("In KeyParameter::from_json.")?;
+        let security_level = value_from_name(&SecurityLevel::enum_values(), level_name, |l| l.0)
+            .map(SecurityLevel)
+            .ok_or(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+            .with_context(|| {
+                format!("In KeyParameter::from_json: Unknown security level {}.", level_name)
+            })?;
+        let value =
+            KeyParameterValue::from_json_value(value).context("In KeyParameter::from_json.")?;
+        Ok(Self::new(value, security_level))
+    }
+}
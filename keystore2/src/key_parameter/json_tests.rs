@@ -0,0 +1,245 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The json_tests module checks the symbolic JSON representation of key parameters and that
+//! it can be parsed back.
+
+use super::*;
+use serde_json::json;
+
+#[test]
+fn to_json_algorithm() {
+    let param = KeyParameter::new(
+        KeyParameterValue::Algorithm(Algorithm::EC),
+        SecurityLevel::TRUSTED_ENVIRONMENT,
+    );
+    assert_eq!(
+        param.to_json().to_string(),
+        r#"{"securityLevel":"TRUSTED_ENVIRONMENT","tag":"ALGORITHM","value":"EC"}"#
+    );
+}
+
+#[test]
+fn to_json_value_types() {
+    let level = SecurityLevel::STRONGBOX;
+    assert_eq!(
+        KeyParameter::new(KeyParameterValue::KeySize(256), level).to_json(),
+        json!({"tag": "KEY_SIZE", "value": 256, "securityLevel": "STRONGBOX"})
+    );
+    assert_eq!(
+        KeyParameter::new(KeyParameterValue::CreationDateTime(1704067200000), level).to_json(),
+        json!({"tag": "CREATION_DATETIME", "value": 1704067200000i64, "securityLevel": "STRONGBOX"})
+    );
+    assert_eq!(
+        KeyParameter::new(
+            KeyParameterValue::CertificateSubject(vec![0xde, 0xad, 0xbe, 0xef]),
+            level
+        )
+        .to_json(),
+        json!({"tag": "CERTIFICATE_SUBJECT", "value": "3q2+7w==", "securityLevel": "STRONGBOX"})
+    );
+    assert_eq!(
+        KeyParameter::new(KeyParameterValue::CallerNonce, level).to_json(),
+        json!({"tag": "CALLER_NONCE", "value": true, "securityLevel": "STRONGBOX"})
+    );
+}
+
+#[test]
+fn from_json_round_trip() {
+    let params = vec![
+        KeyParameter::new(KeyParameterValue::Algorithm(Algorithm::EC), SecurityLevel::STRONGBOX),
+        KeyParameter::new(KeyParameterValue::Digest(Digest::NONE), SecurityLevel::STRONGBOX),
+        KeyParameter::new(
+            KeyParameterValue::PaddingMode(PaddingMode::NONE),
+            SecurityLevel::KEYSTORE,
+        ),
+        KeyParameter::new(KeyParameterValue::KeySize(256), SecurityLevel::TRUSTED_ENVIRONMENT),
+        KeyParameter::new(
+            KeyParameterValue::CreationDateTime(1704067200000),
+            SecurityLevel::KEYSTORE,
+        ),
+        KeyParameter::new(
+            KeyParameterValue::CertificateSerial(vec![1, 2, 3]),
+            SecurityLevel::SOFTWARE,
+        ),
+        KeyParameter::new(KeyParameterValue::NoAuthRequired, SecurityLevel::TRUSTED_ENVIRONMENT),
+        KeyParameter::new(KeyParameterValue::Algorithm(Algorithm(99)), SecurityLevel::STRONGBOX),
+    ];
+    for param in params {
+        assert_eq!(KeyParameter::from_json(&param.to_json()).unwrap(), param);
+    }
+}
+
+#[test]
+fn from_json_hand_authored() {
+    assert_eq!(
+        KeyParameter::from_json(
+            &json!({"tag": "KEY_SIZE", "value": 256, "securityLevel": "TRUSTED_ENVIRONMENT"})
+        )
+        .unwrap(),
+        KeyParameter::new(KeyParameterValue::KeySize(256), SecurityLevel::TRUSTED_ENVIRONMENT)
+    );
+    assert_eq!(
+        KeyParameter::from_json(
+            &json!({"tag": "PURPOSE", "value": "SIGN", "securityLevel": "KEYSTORE"})
+        )
+        .unwrap(),
+        KeyParameter::new(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN), SecurityLevel::KEYSTORE)
+    );
+}
+
+#[test]
+fn from_json_errors() {
+    let err = KeyParameter::from_json(
+        &json!({"tag": "NO_SUCH_TAG", "value": 1, "securityLevel": "KEYSTORE"}),
+    )
+    .unwrap_err();
+    assert!(format!("{:?}", err).contains("Unknown tag NO_SUCH_TAG"));
+
+    let err = KeyParameter::from_json(
+        &json!({"tag": "KEY_SIZE", "value": "256", "securityLevel": "KEYSTORE"}),
+    )
+    .unwrap_err();
+    assert!(format!("{:?}", err).contains("Invalid value \"256\" for KEY_SIZE"));
+
+    assert!(KeyParameter::from_json(
+        &json!({"tag": "ALGORITHM", "value": "NOT_AN_ALGORITHM", "securityLevel": "KEYSTORE"})
+    )
+    .is_err());
+    assert!(KeyParameter::from_json(&json!({"tag": "KEY_SIZE", "value": 256})).is_err());
+}
+
+#[test]
+fn json_value_tag_names_match_aidl() {
+    for param in KeyParameterValue::make_key_parameter_defaults_vector() {
+        let value = param.key_parameter_value();
+        let json = value.to_json_value();
+        let tag_name = json["tag"].as_str().expect("Tag is not a string.");
+        let tag = Tag::enum_values()
+            .into_iter()
+            .find(|t| format!("{:?}", t) == tag_name)
+            .unwrap_or_else(|| panic!("{} is not an AIDL tag name.", tag_name));
+        assert_eq!(tag, value.get_tag());
+        if !value.is_sensitive() {
+            assert_eq!(&KeyParameterValue::from_json_value(&json).unwrap(), value);
+        }
+    }
+}
+
+#[test]
+fn json_redacts_sensitive_values() {
+    let param =
+        KeyParameter::new(KeyParameterValue::ApplicationID(vec![1, 2, 3]), SecurityLevel::SOFTWARE);
+    assert_eq!(
+        param.to_json(),
+        json!({"tag": "APPLICATION_ID", "value": "<redacted>", "securityLevel": "SOFTWARE"})
+    );
+    assert!(KeyParameter::from_json(&param.to_json()).is_err());
+    assert_eq!(
+        KeyParameterValue::IncludeUniqueID.to_json_value(),
+        json!({"tag": "INCLUDE_UNIQUE_ID", "value": true})
+    );
+}
+
+#[test]
+fn json_value_blobs_are_base64() {
+    use base64::Engine;
+
+    let blob: Vec<u8> = (0..=255).collect();
+    let json = KeyParameterValue::CertificateSerial(blob.clone()).to_json_value();
+    assert_eq!(json["tag"], "CERTIFICATE_SERIAL");
+    let encoded = json["value"].as_str().expect("Blob is not a string.");
+    assert_eq!(base64::engine::general_purpose::STANDARD.decode(encoded).unwrap(), blob);
+    assert_eq!(
+        KeyParameterValue::from_json_value(&json).unwrap(),
+        KeyParameterValue::CertificateSerial(blob)
+    );
+
+    let json = json!({"tag": "CERTIFICATE_SERIAL", "value": "not base64!"});
+    assert!(KeyParameterValue::from_json_value(&json).is_err());
+}
+
+#[test]
+fn json_value_types() {
+    assert_eq!(
+        KeyParameterValue::KeySize(256).to_json_value(),
+        json!({"tag": "KEY_SIZE", "value": 256})
+    );
+    assert_eq!(
+        KeyParameterValue::NoAuthRequired.to_json_value(),
+        json!({"tag": "NO_AUTH_REQUIRED", "value": true})
+    );
+    assert_eq!(
+        KeyParameterValue::Invalid(0).to_json_value(),
+        json!({"tag": "INVALID", "value": 0})
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn symbolic_serde_uses_names() {
+    let value = SymbolicKeyParameterValue(KeyParameterValue::Algorithm(Algorithm::EC));
+    assert_eq!(
+        serde_json::to_value(&value).unwrap(),
+        json!({"tag": "ALGORITHM", "value": {"Name": "EC"}})
+    );
+    assert_eq!(
+        serde_json::to_value(SymbolicKeyParameterValue(KeyParameterValue::KeySize(256))).unwrap(),
+        json!({"tag": "KEY_SIZE", "value": {"I32": 256}})
+    );
+    assert_eq!(
+        serde_json::to_value(SymbolicKeyParameterValue(KeyParameterValue::NoAuthRequired)).unwrap(),
+        json!({"tag": "NO_AUTH_REQUIRED", "value": null})
+    );
+    let parsed: SymbolicKeyParameterValue =
+        serde_json::from_value(json!({"tag": "PURPOSE", "value": {"Name": "SIGN"}})).unwrap();
+    assert_eq!(parsed.0, KeyParameterValue::KeyPurpose(KeyPurpose::SIGN));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn symbolic_serde_round_trip() {
+    let mut values: Vec<KeyParameterValue> =
+        KeyParameterValue::make_key_parameter_defaults_vector()
+            .into_iter()
+            .map(|p| p.key_parameter_value().clone())
+            .collect();
+    values.push(KeyParameterValue::Algorithm(Algorithm(99)));
+    values.push(KeyParameterValue::Unknown {
+        tag: Tag((TagType::ENUM.0 as u32 | 9999) as i32),
+        value: KmKeyParameterValue::Integer(7),
+    });
+    for value in values {
+        let json = serde_json::to_string(&SymbolicKeyParameterValue(value.clone())).unwrap();
+        let parsed: SymbolicKeyParameterValue = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.0, value, "{}", json);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn symbolic_serde_errors() {
+    assert!(serde_json::from_value::<SymbolicKeyParameterValue>(
+        json!({"tag": "NO_SUCH_TAG", "value": {"I32": 1}})
+    )
+    .is_err());
+    assert!(serde_json::from_value::<SymbolicKeyParameterValue>(
+        json!({"tag": "ALGORITHM", "value": {"Name": "NOT_AN_ALGORITHM"}})
+    )
+    .is_err());
+    assert!(serde_json::from_value::<SymbolicKeyParameterValue>(
+        json!({"tag": "KEY_SIZE", "value": {"Name": "EC"}})
+    )
+    .is_err());
+}
@@ -0,0 +1,186 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module implements the Parcelable trait for KeyParameter, which writes key parameters
+//! to Binder parcels directly, without converting them to an Authorization first.
+
+use super::{
+    tag_type_of, KeyParameter, KeyParameterValue, KeystoreError, Primitive, ResponseCode,
+    SecurityLevel, Tag, TagType,
+};
+use anyhow::{Context, Result};
+use binder::{binder_impl::BorrowedParcel, Parcelable, StatusCode};
+
+/// The maximum length of a blob value written to or read from a Binder parcel by the
+/// Parcelable implementation of KeyParameter.
+pub const MAX_PARCEL_BLOB_SIZE: usize = 64 * 1024;
+
+/// Reads a byte array from the parcel. The length prefix is checked against
+/// MAX_PARCEL_BLOB_SIZE and against the unread data of the parcel before the array is read, so
+/// that no memory is allocated for oversized blobs. A byte array is written as its length
+/// followed by the bytes padded to a multiple of four, so the bytes are read as i32 words in
+/// native byte order.
+fn read_parcel_blob(parcel: &BorrowedParcel<'_>, tag: Tag) -> Result<Vec<u8>> {
+    let len: i32 = parcel
+        .read()
+        .map_err(KeystoreError::BinderTransaction)
+        .context("In read_parcel_blob: Failed to read length.")?;
+    let remaining = parcel.get_data_size().saturating_sub(parcel.get_data_position());
+    let len = match usize::try_from(len) {
+        Ok(len) if len > MAX_PARCEL_BLOB_SIZE => {
+            return Err(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED)).with_context(|| {
+                format!(
+                    "In read_parcel_blob: Blob of {} bytes for {:?} exceeds {} bytes.",
+                    len, tag, MAX_PARCEL_BLOB_SIZE
+                )
+            });
+        }
+        Ok(len) if len <= usize::try_from(remaining).unwrap_or(0) => len,
+        _ => {
+            return Err(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED)).with_context(|| {
+                format!(
+                    "In read_parcel_blob: Invalid length {} for {:?} with {} bytes left.",
+                    len, tag, remaining
+                )
+            });
+        }
+    };
+    let mut blob = Vec::with_capacity(len.next_multiple_of(4));
+    for _ in 0..len.div_ceil(4) {
+        let word: i32 = parcel
+            .read()
+            .map_err(KeystoreError::BinderTransaction)
+            .context("In read_parcel_blob: Failed to read blob.")?;
+        blob.extend_from_slice(&word.to_ne_bytes());
+    }
+    blob.truncate(len);
+    Ok(blob)
+}
+
+impl KeyParameter {
+    /// Writes the key parameter directly to a Binder parcel, without converting it to an
+    /// Authorization first. The tag and the security level are written as i32, followed by the
+    /// value in the representation implied by the tag type: i32 for integer and enum values,
+    /// i64 for long integers and date times, a byte array for blobs, and nothing for boolean
+    /// parameters. Fails with ResponseCode::INVALID_ARGUMENT if the value does not match its
+    /// tag type or if a blob is longer than MAX_PARCEL_BLOB_SIZE.
+    pub(super) fn to_binder_parcel(&self, parcel: &mut BorrowedParcel<'_>) -> Result<()> {
+        let tag = self.get_tag();
+        parcel
+            .write(&tag.0)
+            .map_err(KeystoreError::BinderTransaction)
+            .context("In to_binder_parcel: Failed to write tag.")?;
+        parcel
+            .write(&self.security_level.0)
+            .map_err(KeystoreError::BinderTransaction)
+            .context("In to_binder_parcel: Failed to write security level.")?;
+        let mismatch = || {
+            anyhow::Error::new(KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT))
+                .context(format!("In to_binder_parcel: Value does not match {:?}.", tag))
+        };
+        match tag_type_of(tag) {
+            TagType::BOOL => Ok(()),
+            TagType::INVALID => match self.value {
+                KeyParameterValue::Invalid(v) => parcel.write(&v),
+                _ => return Err(mismatch()),
+            },
+            TagType::ENUM | TagType::ENUM_REP | TagType::UINT | TagType::UINT_REP => {
+                parcel.write(&i32::try_from(&self.value).map_err(|_| mismatch())?)
+            }
+            TagType::ULONG | TagType::ULONG_REP | TagType::DATE => {
+                parcel.write(&i64::try_from(&self.value).map_err(|_| mismatch())?)
+            }
+            TagType::BIGNUM | TagType::BYTES => {
+                let blob = self.value.blob_value().ok_or_else(mismatch)?;
+                if blob.len() > MAX_PARCEL_BLOB_SIZE {
+                    return Err(KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT)).with_context(
+                        || {
+                            format!(
+                                "In to_binder_parcel: Blob of {} bytes for {:?} exceeds {} bytes.",
+                                blob.len(),
+                                tag,
+                                MAX_PARCEL_BLOB_SIZE
+                            )
+                        },
+                    );
+                }
+                parcel.write(blob)
+            }
+            _ => return Err(mismatch()),
+        }
+        .map_err(KeystoreError::BinderTransaction)
+        .context("In to_binder_parcel: Failed to write value.")
+    }
+
+    /// Reads a key parameter written by `to_binder_parcel` from the current position of the
+    /// parcel. Fails with ResponseCode::VALUE_CORRUPTED if a blob is longer than
+    /// MAX_PARCEL_BLOB_SIZE or if the value is not valid for the tag.
+    pub(super) fn from_binder_parcel(parcel: &BorrowedParcel<'_>) -> Result<Self> {
+        let tag = Tag(parcel
+            .read()
+            .map_err(KeystoreError::BinderTransaction)
+            .context("In from_binder_parcel: Failed to read tag.")?);
+        let security_level = SecurityLevel(
+            parcel
+                .read()
+                .map_err(KeystoreError::BinderTransaction)
+                .context("In from_binder_parcel: Failed to read security level.")?,
+        );
+        let read_failed = |e| {
+            anyhow::Error::new(KeystoreError::BinderTransaction(e))
+                .context("In from_binder_parcel: Failed to read value.")
+        };
+        let primitive = match tag_type_of(tag) {
+            TagType::INVALID
+            | TagType::ENUM
+            | TagType::ENUM_REP
+            | TagType::UINT
+            | TagType::UINT_REP => Some(Primitive::I32(parcel.read().map_err(read_failed)?)),
+            TagType::ULONG | TagType::ULONG_REP | TagType::DATE => {
+                Some(Primitive::I64(parcel.read().map_err(read_failed)?))
+            }
+            TagType::BIGNUM | TagType::BYTES => Some(Primitive::Vec(
+                read_parcel_blob(parcel, tag).context("In from_binder_parcel.")?,
+            )),
+            _ => None,
+        };
+        let value = KeyParameterValue::new_from_tag_optional_primitive(tag, primitive)
+            .map_err(|_| KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+            .with_context(|| format!("In from_binder_parcel: Invalid value for {:?}.", tag))?;
+        Ok(Self::new(value, security_level))
+    }
+}
+
+/// Maps an error of `KeyParameter::to_binder_parcel` or `KeyParameter::from_binder_parcel`
+/// onto the status code returned by the Parcelable implementation. Binder errors are passed
+/// through, and all other errors become StatusCode::BAD_VALUE.
+fn parcel_status_code(e: anyhow::Error) -> StatusCode {
+    match e.root_cause().downcast_ref::<KeystoreError>() {
+        Some(KeystoreError::BinderTransaction(status)) => *status,
+        _ => StatusCode::BAD_VALUE,
+    }
+}
+
+/// Key parameters are written to Binder parcels directly, without converting them to an
+/// Authorization first. See `KeyParameter::to_binder_parcel` for the format.
+impl Parcelable for KeyParameter {
+    fn write_to_parcel(&self, parcel: &mut BorrowedParcel<'_>) -> Result<(), StatusCode> {
+        self.to_binder_parcel(parcel).map_err(parcel_status_code)
+    }
+
+    fn read_from_parcel(&mut self, parcel: &BorrowedParcel<'_>) -> Result<(), StatusCode> {
+        *self = Self::from_binder_parcel(parcel).map_err(parcel_status_code)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+// Copyright 2020, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The parcel_tests module checks that key parameters of every value family survive an
+//! in-process round trip through the Parcelable implementation of KeyParameter, and that
+//! oversized blobs are rejected in both directions.
+
+use super::*;
+use binder::{binder_impl::Parcel, Parcelable, StatusCode};
+
+/// Writes the key parameters to a fresh parcel and rewinds it for reading.
+fn write_to_parcel(params: &[KeyParameter]) -> Result<Parcel> {
+    let mut parcel = Parcel::new();
+    for p in params {
+        p.to_binder_parcel(&mut parcel.borrowed())?;
+    }
+    // SAFETY: Position 0 is always within the data of the parcel.
+    unsafe { parcel.set_data_position(0) }.expect("Failed to rewind parcel.");
+    Ok(parcel)
+}
+
+fn assert_rc<T>(result: Result<T>, rc: ResponseCode) {
+    let err = result.err().expect("Expected an error.");
+    assert_eq!(err.root_cause().downcast_ref::<KeystoreError>(), Some(&KeystoreError::Rc(rc)));
+}
+
+#[test]
+fn parcel_round_trip_for_each_value_family() {
+    let params = vec![
+        KeyParameter::new(KeyParameterValue::Invalid(5), SecurityLevel::SOFTWARE),
+        KeyParameter::new(KeyParameterValue::NoAuthRequired, SecurityLevel::TRUSTED_ENVIRONMENT),
+        KeyParameter::new(KeyParameterValue::Algorithm(Algorithm::EC), SecurityLevel::STRONGBOX),
+        KeyParameter::new(KeyParameterValue::KeySize(256), SecurityLevel::TRUSTED_ENVIRONMENT),
+        KeyParameter::new(KeyParameterValue::UserSecureID(-7), SecurityLevel::KEYSTORE),
+        KeyParameter::new(
+            KeyParameterValue::CreationDateTime(1704067200000),
+            SecurityLevel::KEYSTORE,
+        ),
+        KeyParameter::new(KeyParameterValue::ApplicationID(vec![]), SecurityLevel::KEYSTORE),
+        KeyParameter::new(
+            KeyParameterValue::AttestationChallenge(vec![1, 2, 3, 4, 5]),
+            SecurityLevel::TRUSTED_ENVIRONMENT,
+        ),
+        KeyParameter::new(
+            KeyParameterValue::Unknown {
+                tag: Tag(TagType::BYTES.0 | 0xfff),
+                value: KmKeyParameterValue::Blob(vec![9; MAX_PARCEL_BLOB_SIZE]),
+            },
+            SecurityLevel::TRUSTED_ENVIRONMENT,
+        ),
+    ];
+    let parcel = write_to_parcel(&params).unwrap();
+    for expected in &params {
+        assert_eq!(&KeyParameter::from_binder_parcel(parcel.borrowed_ref()).unwrap(), expected);
+    }
+    assert!(KeyParameter::from_binder_parcel(parcel.borrowed_ref()).is_err());
+}
+
+#[test]
+fn parcelable_round_trip() {
+    let params = vec![
+        KeyParameter::new(KeyParameterValue::Algorithm(Algorithm::EC), SecurityLevel::STRONGBOX),
+        KeyParameter::new(
+            KeyParameterValue::AttestationChallenge(vec![1, 2, 3]),
+            SecurityLevel::TRUSTED_ENVIRONMENT,
+        ),
+    ];
+    let mut parcel = Parcel::new();
+    for p in &params {
+        p.write_to_parcel(&mut parcel.borrowed()).unwrap();
+    }
+    // SAFETY: Position 0 is always within the data of the parcel.
+    unsafe { parcel.set_data_position(0) }.unwrap();
+    for expected in &params {
+        let mut actual = KeyParameter::default();
+        actual.read_from_parcel(parcel.borrowed_ref()).unwrap();
+        assert_eq!(&actual, expected);
+    }
+
+    let oversized = KeyParameter::new(
+        KeyParameterValue::AttestationChallenge(vec![0; MAX_PARCEL_BLOB_SIZE + 1]),
+        SecurityLevel::TRUSTED_ENVIRONMENT,
+    );
+    assert_eq!(
+        oversized.write_to_parcel(&mut Parcel::new().borrowed()),
+        Err(StatusCode::BAD_VALUE)
+    );
+}
+
+#[test]
+fn parcel_rejects_oversized_blobs() {
+    let oversized = vec![0u8; MAX_PARCEL_BLOB_SIZE + 1];
+    let param = KeyParameter::new(
+        KeyParameterValue::AttestationChallenge(oversized.clone()),
+        SecurityLevel::TRUSTED_ENVIRONMENT,
+    );
+    assert_rc(write_to_parcel(&[param]), ResponseCode::INVALID_ARGUMENT);
+
+    let mut parcel = Parcel::new();
+    parcel.write(&Tag::ATTESTATION_CHALLENGE.0).unwrap();
+    parcel.write(&SecurityLevel::TRUSTED_ENVIRONMENT.0).unwrap();
+    parcel.write(&oversized).unwrap();
+    // SAFETY: Position 0 is always within the data of the parcel.
+    unsafe { parcel.set_data_position(0) }.unwrap();
+    assert_rc(
+        KeyParameter::from_binder_parcel(parcel.borrowed_ref()),
+        ResponseCode::VALUE_CORRUPTED,
+    );
+
+    // The length prefix is checked before the blob is read, so a prefix that exceeds the
+    // limit is rejected even if the parcel does not hold that much data.
+    let mut parcel = Parcel::new();
+    parcel.write(&Tag::ATTESTATION_CHALLENGE.0).unwrap();
+    parcel.write(&SecurityLevel::TRUSTED_ENVIRONMENT.0).unwrap();
+    parcel.write(&i32::MAX).unwrap();
+    // SAFETY: Position 0 is always within the data of the parcel.
+    unsafe { parcel.set_data_position(0) }.unwrap();
+    assert_rc(
+        KeyParameter::from_binder_parcel(parcel.borrowed_ref()),
+        ResponseCode::VALUE_CORRUPTED,
+    );
+
+    // Lengths within the limit are still rejected if they are negative or exceed the data
+    // left in the parcel.
+    for len in [-1i32, 8] {
+        let mut parcel = Parcel::new();
+        parcel.write(&Tag::ATTESTATION_CHALLENGE.0).unwrap();
+        parcel.write(&SecurityLevel::TRUSTED_ENVIRONMENT.0).unwrap();
+        parcel.write(&len).unwrap();
+        parcel.write(&0i32).unwrap();
+        // SAFETY: Position 0 is always within the data of the parcel.
+        unsafe { parcel.set_data_position(0) }.unwrap();
+        assert_rc(
+            KeyParameter::from_binder_parcel(parcel.borrowed_ref()),
+            ResponseCode::VALUE_CORRUPTED,
+        );
+    }
+}
+
+#[test]
+fn parcel_round_trips_unaligned_blobs() {
+    for len in 0..=9u8 {
+        let expected = KeyParameter::new(
+            KeyParameterValue::AttestationChallenge((1..=len).collect()),
+            SecurityLevel::TRUSTED_ENVIRONMENT,
+        );
+        let mut parcel = Parcel::new();
+        expected.write_to_parcel(&mut parcel.borrowed()).unwrap();
+        parcel.write(&Tag::NO_AUTH_REQUIRED.0).unwrap();
+        // SAFETY: Position 0 is always within the data of the parcel.
+        unsafe { parcel.set_data_position(0) }.unwrap();
+        let mut actual = KeyParameter::default();
+        actual.read_from_parcel(parcel.borrowed_ref()).unwrap();
+        assert_eq!(actual, expected);
+        // The padding after the blob was consumed.
+        assert_eq!(parcel.borrowed_ref().read::<i32>(), Ok(Tag::NO_AUTH_REQUIRED.0));
+    }
+}
+
+#[test]
+fn parcel_rejects_values_not_matching_the_tag() {
+    let param = KeyParameter::new(
+        KeyParameterValue::Unknown {
+            tag: Tag(TagType::ULONG.0 | 0xfff),
+            value: KmKeyParameterValue::Integer(1),
+        },
+        SecurityLevel::TRUSTED_ENVIRONMENT,
+    );
+    assert_rc(write_to_parcel(&[param]), ResponseCode::INVALID_ARGUMENT);
+
+    let mut parcel = Parcel::new();
+    // The tag type 0xb is not defined by KeyMint, so no value can be read for this tag.
+    parcel.write(&(0xb000_0001u32 as i32)).unwrap();
+    parcel.write(&SecurityLevel::TRUSTED_ENVIRONMENT.0).unwrap();
+    // SAFETY: Position 0 is always within the data of the parcel.
+    unsafe { parcel.set_data_position(0) }.unwrap();
+    assert_rc(
+        KeyParameter::from_binder_parcel(parcel.borrowed_ref()),
+        ResponseCode::VALUE_CORRUPTED,
+    );
+}
@@ -0,0 +1,123 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module implements the protobuf encoding of key parameter values. It is only built with
+//! the `protobuf` feature.
+
+use super::{tag_type_of, KeyParameterValue, KeystoreError, Primitive, ResponseCode, Tag, TagType};
+use anyhow::{Context, Result};
+
+/// Returns the protobuf field number used for the given tag, i.e., the tag without its type
+/// bits. Returns None if the result is not a valid field number, which is the case for
+/// Tag::INVALID.
+fn protobuf_field_number(tag: Tag) -> Option<u32> {
+    match tag.0 as u32 & 0x0FFFFFFF {
+        0 => None,
+        n => Some(n),
+    }
+}
+
+impl KeyParameterValue {
+    /// Encodes the value as protobuf message with a single field. The field number is the tag
+    /// without its type bits and the field is encoded as int32, int64, bytes, or bool depending
+    /// on the tag type. Tag::INVALID has no field number, so Invalid is encoded as empty
+    /// message and its payload is not preserved.
+    pub fn to_protobuf(&self) -> Vec<u8> {
+        use prost::encoding;
+
+        let mut buf = Vec::new();
+        let field = match protobuf_field_number(self.get_tag()) {
+            Some(field) => field,
+            None => return buf,
+        };
+        match self.get_primitive() {
+            Some(Primitive::I32(v)) => encoding::int32::encode(field, &v, &mut buf),
+            Some(Primitive::I64(v)) => encoding::int64::encode(field, &v, &mut buf),
+            Some(Primitive::Vec(v)) => encoding::bytes::encode(field, &v, &mut buf),
+            None if self.tag_type() == TagType::BOOL => {
+                encoding::bool::encode(field, &true, &mut buf)
+            }
+            None => {}
+        }
+        buf
+    }
+
+    /// Decodes the value of a parameter with the given tag from a protobuf message as
+    /// produced by `to_protobuf`. Fields with other field numbers are ignored. If the field
+    /// occurs more than once, the last occurrence wins. Boolean parameters are implicitly true,
+    /// so a boolean field that is false is rejected.
+    pub fn new_from_protobuf(tag: Tag, bytes: &[u8]) -> Result<Self> {
+        use prost::bytes::Buf;
+        use prost::encoding::{self, DecodeContext};
+
+        let field = match protobuf_field_number(tag) {
+            Some(field) => field,
+            None => return Ok(Self::Invalid(0)),
+        };
+        let mut buf = bytes;
+        let mut primitive: Option<Primitive> = None;
+        let mut present = false;
+        let mut bool_value = true;
+        while buf.has_remaining() {
+            let (number, wire_type) = encoding::decode_key(&mut buf)
+                .map_err(|_| KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+                .context("In KeyParameterValue::new_from_protobuf: Malformed field key.")?;
+            let ctx = DecodeContext::default();
+            let result = if number != field {
+                encoding::skip_field(wire_type, number, &mut buf, ctx)
+            } else {
+                present = true;
+                match tag_type_of(tag) {
+                    TagType::ENUM | TagType::ENUM_REP | TagType::UINT | TagType::UINT_REP => {
+                        let mut v = 0i32;
+                        encoding::int32::merge(wire_type, &mut v, &mut buf, ctx)
+                            .map(|_| primitive = Some(v.into()))
+                    }
+                    TagType::ULONG | TagType::ULONG_REP | TagType::DATE => {
+                        let mut v = 0i64;
+                        encoding::int64::merge(wire_type, &mut v, &mut buf, ctx)
+                            .map(|_| primitive = Some(v.into()))
+                    }
+                    TagType::BIGNUM | TagType::BYTES => {
+                        let mut v: Vec<u8> = Vec::new();
+                        encoding::bytes::merge(wire_type, &mut v, &mut buf, ctx)
+                            .map(|_| primitive = Some(v.into()))
+                    }
+                    TagType::BOOL => {
+                        let mut v = false;
+                        encoding::bool::merge(wire_type, &mut v, &mut buf, ctx)
+                            .map(|_| bool_value = v)
+                    }
+                    _ => return Ok(Self::Invalid(0)),
+                }
+            };
+            result.map_err(|_| KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED)).with_context(
+                || format!("In KeyParameterValue::new_from_protobuf: Malformed field {}.", number),
+            )?;
+        }
+
+        if !present {
+            return Err(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED)).with_context(|| {
+                format!("In KeyParameterValue::new_from_protobuf: Missing value for {:?}.", tag)
+            });
+        }
+        if !bool_value {
+            return Err(KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED)).with_context(|| {
+                format!("In KeyParameterValue::new_from_protobuf: {:?} is false.", tag)
+            });
+        }
+        Self::new_from_tag_optional_primitive(tag, primitive)
+            .context("In KeyParameterValue::new_from_protobuf.")
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The protobuf_tests module checks that every KeyParameterValue survives a round trip
+//! through `to_protobuf` and `new_from_protobuf`.
+
+use super::*;
+
+fn round_trip(value: &KeyParameterValue) -> KeyParameterValue {
+    KeyParameterValue::new_from_protobuf(value.get_tag(), &value.to_protobuf())
+        .expect("Failed to decode key parameter value.")
+}
+
+#[test]
+fn protobuf_round_trip_defaults() {
+    for param in KeyParameterValue::make_key_parameter_defaults_vector() {
+        let value = param.key_parameter_value();
+        assert_eq!(&round_trip(value), value);
+    }
+}
+
+#[test]
+fn protobuf_round_trip_values() {
+    for value in [
+        KeyParameterValue::Algorithm(Algorithm::EC),
+        KeyParameterValue::KeySize(-1),
+        KeyParameterValue::RSAPublicExponent(65537),
+        KeyParameterValue::CreationDateTime(1704067200000),
+        KeyParameterValue::ApplicationID(vec![1, 2, 3]),
+        KeyParameterValue::UserSecureID(i64::MIN),
+        KeyParameterValue::CallerNonce,
+        KeyParameterValue::Unknown {
+            tag: Tag(TagType::BYTES.0 | 0xfff),
+            value: KmKeyParameterValue::Blob(vec![4, 5]),
+        },
+        KeyParameterValue::Unknown {
+            tag: Tag(TagType::BOOL.0 | 0xfff),
+            value: KmKeyParameterValue::BoolValue(true),
+        },
+    ] {
+        assert_eq!(round_trip(&value), value);
+    }
+}
+
+#[test]
+fn protobuf_wire_format() {
+    // KEY_SIZE is UINT | 3, i.e., field 3 with wire type varint.
+    assert_eq!(KeyParameterValue::KeySize(2048).to_protobuf(), vec![0x18, 0x80, 0x10]);
+    // APPLICATION_ID is BYTES | 601, i.e., field 601 with wire type length delimited.
+    assert_eq!(
+        KeyParameterValue::ApplicationID(vec![0xaa]).to_protobuf(),
+        vec![0xca, 0x25, 0x01, 0xaa]
+    );
+    assert!(KeyParameterValue::Invalid(0).to_protobuf().is_empty());
+}
+
+#[test]
+fn protobuf_ignores_other_fields() {
+    let mut bytes = KeyParameterValue::KeySize(256).to_protobuf();
+    bytes.extend(KeyParameterValue::Algorithm(Algorithm::AES).to_protobuf());
+    assert_eq!(
+        KeyParameterValue::new_from_protobuf(Tag::ALGORITHM, &bytes).unwrap(),
+        KeyParameterValue::Algorithm(Algorithm::AES)
+    );
+}
+
+#[test]
+fn protobuf_rejects_malformed_input() {
+    // Missing field.
+    assert!(KeyParameterValue::new_from_protobuf(Tag::KEY_SIZE, &[]).is_err());
+    // Truncated varint.
+    assert!(KeyParameterValue::new_from_protobuf(Tag::KEY_SIZE, &[0x18, 0x80]).is_err());
+    // Wrong wire type for the tag type.
+    let bytes = KeyParameterValue::ApplicationID(vec![1]).to_protobuf();
+    assert!(KeyParameterValue::new_from_protobuf(Tag(TagType::UINT.0 | 601), &bytes).is_err());
+    // Boolean parameters are implicitly true, so false is not a valid value.
+    assert!(KeyParameterValue::new_from_protobuf(Tag::CALLER_NONCE, &[0x38, 0x00]).is_err());
+    assert_eq!(
+        KeyParameterValue::new_from_protobuf(Tag::CALLER_NONCE, &[0x38, 0x00, 0x38, 0x01]).unwrap(),
+        KeyParameterValue::CallerNonce
+    );
+}
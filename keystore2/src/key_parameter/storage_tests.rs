@@ -124,6 +124,53 @@ fn test_to_sql_enum_i32() -> Result<()> {
     Ok(())
 }
 
+/// Test that the estimated serialized size matches the data produced by `to_sql`.
+#[test]
+fn test_estimated_serialized_size_matches_to_sql() -> Result<()> {
+    use rusqlite::types::{ToSqlOutput, Value, ValueRef};
+
+    let mut params = KeyParameterValue::make_key_parameter_defaults_vector();
+    params.push(KeyParameter::new(
+        KeyParameterValue::ApplicationID(vec![0; 37]),
+        SecurityLevel::SOFTWARE,
+    ));
+    for param in &params {
+        let value = param.key_parameter_value();
+        let size = value.estimated_serialized_size();
+        if let KeyParameterValue::Invalid(_) = value {
+            assert_eq!(size, 0);
+            continue;
+        }
+        match value.to_sql()? {
+            ToSqlOutput::Owned(Value::Integer(v)) => {
+                if size == 4 {
+                    assert!(i32::try_from(v).is_ok(), "{:?}", value);
+                } else {
+                    assert_eq!(size, 8, "{:?}", value);
+                }
+            }
+            ToSqlOutput::Owned(Value::Blob(b)) => assert_eq!(size, b.len(), "{:?}", value),
+            ToSqlOutput::Borrowed(ValueRef::Blob(b)) => assert_eq!(size, b.len(), "{:?}", value),
+            ToSqlOutput::Owned(Value::Null) => assert_eq!(size, 1, "{:?}", value),
+            output => panic!("Unexpected output {:?} for {:?}.", output, value),
+        }
+    }
+    assert_eq!(
+        total_size(&[
+            KeyParameter::new(KeyParameterValue::KeySize(256), SecurityLevel::STRONGBOX),
+            KeyParameter::new(KeyParameterValue::CreationDateTime(0), SecurityLevel::KEYSTORE),
+            KeyParameter::new(
+                KeyParameterValue::ApplicationID(vec![1, 2, 3]),
+                SecurityLevel::SOFTWARE
+            ),
+            KeyParameter::new(KeyParameterValue::NoAuthRequired, SecurityLevel::STRONGBOX),
+            KeyParameter::new(KeyParameterValue::Invalid(0), SecurityLevel::STRONGBOX),
+        ]),
+        4 + 8 + 3 + 1
+    );
+    Ok(())
+}
+
 /// Test storing a KeyParameter (with key parameter value which is of i32) in the database
 #[test]
 fn test_to_sql_i32() -> Result<()> {
@@ -188,6 +235,17 @@ fn test_invalid_tag() -> Result<()> {
     insert_into_keyparameter(&db, 1, 0, &123, 1)?;
     let key_param = query_from_keyparameter(&db)?;
     assert_eq!(Tag::INVALID, key_param.get_tag());
+    assert_eq!(*key_param.key_parameter_value(), KeyParameterValue::Invalid(123));
+    Ok(())
+}
+
+/// Rows for Tag::INVALID written before Invalid carried a payload store NULL.
+#[test]
+fn test_invalid_tag_null_payload() -> Result<()> {
+    let db = init_db()?;
+    insert_into_keyparameter(&db, 1, 0, &rusqlite::types::Null, 1)?;
+    let key_param = query_from_keyparameter(&db)?;
+    assert_eq!(*key_param.key_parameter_value(), KeyParameterValue::Invalid(0));
     Ok(())
 }
 
@@ -200,18 +258,250 @@ fn test_non_existing_enum_variant() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_unknown_tag() -> Result<()> {
+    let db = init_db()?;
+    let unknown_tag = Tag(TagType::BYTES.0 | 0xfff);
+    insert_into_keyparameter(&db, 1, unknown_tag.0, &vec![1u8, 2, 3], 1)?;
+    let key_param = query_from_keyparameter(&db)?;
+    assert_eq!(unknown_tag, key_param.get_tag());
+    assert_eq!(
+        *key_param.key_parameter_value(),
+        KeyParameterValue::Unknown {
+            tag: unknown_tag,
+            value: KmKeyParameterValue::Blob(vec![1, 2, 3])
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_to_sql_unknown_tag() -> Result<()> {
+    let db = init_db()?;
+    let kp = KeyParameter::new(
+        KeyParameterValue::Unknown {
+            tag: Tag(TagType::ULONG.0 | 0xfff),
+            value: KmKeyParameterValue::LongInteger(i64::MAX),
+        },
+        SecurityLevel::STRONGBOX,
+    );
+    store_keyparameter(&db, 1, &kp)?;
+    let key_param = query_from_keyparameter(&db)?;
+    assert_eq!(kp, key_param);
+    Ok(())
+}
+
 #[test]
 fn test_invalid_conversion_from_sql() -> Result<()> {
     let db = init_db()?;
     insert_into_keyparameter(&db, 1, Tag::ALGORITHM.0, &Null, 1)?;
     tests::check_result_contains_error_string(
         query_from_keyparameter(&db),
-        "Failed to read sql data for tag: ALGORITHM.",
+        "Failed to read sql data for tag: ALGORITHM, column type: Null.",
+    );
+    Ok(())
+}
+
+#[test]
+fn test_new_from_sql_returns_structured_error() -> Result<()> {
+    let db = init_db()?;
+    insert_into_keyparameter(&db, 1, Tag::KEY_SIZE.0, &"text", 1)?;
+    let mut stmt = db.prepare("SELECT tag, data, security_level FROM persistent.keyparameter;")?;
+    let mut rows = stmt.query([])?;
+    let row = rows.next()?.unwrap();
+    assert_eq!(
+        KeyParameterValue::new_from_sql(Tag::KEY_SIZE, &SqlField::new(1, row)),
+        Err(KeyParameterError::SqlCorrupted(Tag::KEY_SIZE))
+    );
+    Ok(())
+}
+
+/// Test that a type mismatch names the tag and the column type found in the error chain, while
+/// the root cause remains VALUE_CORRUPTED.
+#[test]
+fn test_type_mismatch_from_sql_names_tag_and_column_type() -> Result<()> {
+    let db = init_db()?;
+    insert_into_keyparameter(&db, 1, Tag::KEY_SIZE.0, &vec![1u8, 2], 1)?;
+    let err = query_from_keyparameter(&db).unwrap_err();
+    assert_eq!(
+        err.root_cause().downcast_ref::<KeystoreError>(),
+        Some(&KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
     );
+    let chain = format!("{:#}", err);
+    assert!(chain.contains("KEY_SIZE"), "{}", chain);
+    assert!(chain.contains("column type: Blob"), "{}", chain);
+
+    let db = init_db()?;
+    let unknown = Tag(TagType::ULONG.0 | 0xfff);
+    insert_into_keyparameter(&db, 1, unknown.0, &"text", 1)?;
+    let chain = format!("{:#}", query_from_keyparameter(&db).unwrap_err());
+    assert!(chain.contains(&format!("{:?}", unknown)), "{}", chain);
+    assert!(chain.contains("column type: Text"), "{}", chain);
     Ok(())
 }
 
+/// Test that every storable KeyParameterValue variant survives a round trip through `to_sql`
+/// and `new_from_sql`. Variants that are not storable are skipped.
+#[test]
+fn test_sql_round_trip_all_storable_variants() -> Result<()> {
+    let db = init_db()?;
+    let params: Vec<KeyParameter> = KeyParameterValue::make_key_parameter_defaults_vector()
+        .into_iter()
+        .filter(|p| p.key_parameter_value().is_storable())
+        .collect();
+    for (key_id, kp) in params.iter().enumerate() {
+        store_keyparameter(&db, key_id as i64, kp)?;
+    }
+    let mut stmt = db.prepare(
+        "SELECT tag, data, security_level FROM persistent.keyparameter ORDER BY keyentryid;",
+    )?;
+    let mut rows = stmt.query([])?;
+    for expected in &params {
+        let row = rows.next()?.unwrap();
+        let tag = Tag(row.get(0)?);
+        let actual =
+            KeyParameter::new_from_sql(tag, &SqlField::new(1, row), SecurityLevel(row.get(2)?))
+                .with_context(|| format!("Failed to read {:?}.", expected.get_tag()))?;
+        assert_eq!(&actual, expected, "{:?} did not survive the round trip.", expected.get_tag());
+    }
+    assert!(rows.next()?.is_none());
+    Ok(())
+}
+
+/// Test reading a known set of key parameters back with `from_sql_row`.
+#[test]
+fn test_from_sql_row() -> Result<()> {
+    let db = init_db()?;
+    let params = KeyParameterBuilder::new(SecurityLevel::STRONGBOX)
+        .algorithm(Algorithm::RSA)
+        .key_size(2048)
+        .purpose(KeyPurpose::SIGN)
+        .value(KeyParameterValue::RSAPublicExponent(65537))
+        .value(KeyParameterValue::NoAuthRequired)
+        .at_level(SecurityLevel::KEYSTORE)
+        .value(KeyParameterValue::CreationDateTime(1704067200000))
+        .value(KeyParameterValue::CertificateSubject(vec![0x30, 0x00]))
+        .build();
+    for kp in &params {
+        store_keyparameter(&db, 1, kp)?;
+    }
+    let mut stmt = db.prepare(
+        "SELECT tag, data, security_level FROM persistent.keyparameter WHERE keyentryid = ?;",
+    )?;
+    let mut rows = stmt.query(params![1])?;
+    let mut loaded = Vec::new();
+    while let Some(row) = rows.next()? {
+        loaded.push(KeyParameter::from_sql_row(row)?);
+    }
+    assert_eq!(loaded, params);
+    Ok(())
+}
+
+/// Test that `from_sql_row` reports a row with the wrong column layout.
+#[test]
+fn test_from_sql_row_bad_security_level() -> Result<()> {
+    let db = init_db()?;
+    let kp = KeyParameter::new(KeyParameterValue::KeySize(256), SecurityLevel::STRONGBOX);
+    store_keyparameter(&db, 1, &kp)?;
+    let mut stmt = db.prepare("SELECT tag, data, 'not a level' FROM persistent.keyparameter;")?;
+    let mut rows = stmt.query([])?;
+    tests::check_result_contains_error_string(
+        KeyParameter::from_sql_row(rows.next()?.unwrap()),
+        "Failed to read security level.",
+    );
+    Ok(())
+}
+
+/// Test that exactly the variants documented in `KeyParameter::new_from_sql` are not storable.
+#[test]
+fn test_not_stored_variants() {
+    let not_stored: Vec<Tag> = KeyParameterValue::make_key_parameter_defaults_vector()
+        .iter()
+        .map(KeyParameter::key_parameter_value)
+        .filter(|v| !v.is_storable())
+        .map(KeyParameterValue::get_tag)
+        .collect();
+    assert_eq!(
+        not_stored,
+        vec![
+            Tag::ROOT_OF_TRUST,
+            Tag::ATTESTATION_CHALLENGE,
+            Tag::ATTESTATION_APPLICATION_ID,
+            Tag::ATTESTATION_ID_BRAND,
+            Tag::ATTESTATION_ID_DEVICE,
+            Tag::ATTESTATION_ID_PRODUCT,
+            Tag::ATTESTATION_ID_SERIAL,
+            Tag::ATTESTATION_ID_IMEI,
+            Tag::ATTESTATION_ID_SECOND_IMEI,
+            Tag::ATTESTATION_ID_MEID,
+            Tag::ATTESTATION_ID_MANUFACTURER,
+            Tag::ATTESTATION_ID_MODEL,
+            Tag::ASSOCIATED_DATA,
+            Tag::NONCE,
+            Tag::RESET_SINCE_ID_ROTATION,
+            Tag::CONFIRMATION_TOKEN,
+        ]
+    );
+}
+
 /// Helper method to init database table for key parameter
+/// Test that a set of 60 key parameters of all primitive types, including a parameter with an
+/// unknown tag, survives a round trip through `encode_blob` and `decode_blob`.
+#[test]
+fn test_blob_round_trip() -> Result<()> {
+    let mut params: Vec<KeyParameter> =
+        KeyParameterValue::make_key_parameter_defaults_vector().into_iter().take(59).collect();
+    params.push(KeyParameter::new(
+        KeyParameterValue::Unknown {
+            tag: Tag(TagType::BYTES.0 | 0xfff),
+            value: KmKeyParameterValue::Blob(vec![1, 2, 3]),
+        },
+        SecurityLevel::KEYSTORE,
+    ));
+    assert_eq!(params.len(), 60);
+    assert_eq!(decode_blob(&encode_blob(&params))?, params);
+    assert_eq!(decode_blob(&encode_blob(&[]))?, vec![]);
+    Ok(())
+}
+
+/// Test that truncated, extended, and unversioned blobs are rejected with VALUE_CORRUPTED.
+#[test]
+fn test_decode_blob_rejects_corrupted_input() {
+    let params = KeyParameterBuilder::new(SecurityLevel::STRONGBOX)
+        .algorithm(Algorithm::EC)
+        .value(KeyParameterValue::RSAPublicExponent(65537))
+        .value(KeyParameterValue::CallerNonce)
+        .value(KeyParameterValue::ApplicationID(vec![0xaa; 16]))
+        .build();
+    let blob = encode_blob(&params);
+    let assert_corrupted = |bytes: &[u8]| {
+        let err = decode_blob(bytes).unwrap_err();
+        assert_eq!(
+            err.root_cause().downcast_ref::<KeystoreError>(),
+            Some(&KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED)),
+            "{:?}",
+            err
+        );
+    };
+    for len in 0..blob.len() {
+        assert_corrupted(&blob[..len]);
+    }
+
+    let mut extended = blob.clone();
+    extended.push(0);
+    assert_corrupted(&extended);
+
+    let mut wrong_version = blob.clone();
+    wrong_version[0] = 2;
+    assert_corrupted(&wrong_version);
+
+    // The first parameter is the algorithm, whose primitive kind byte follows the version,
+    // the count, the tag, and the security level.
+    let mut wrong_kind = blob;
+    wrong_kind[13] = 2;
+    assert_corrupted(&wrong_kind);
+}
+
 fn init_db() -> Result<Connection> {
     let db = Connection::open_in_memory().context("Failed to initialize sqlite connection.")?;
     db.execute("ATTACH DATABASE ? as 'persistent';", params![""])
@@ -259,5 +549,96 @@ fn query_from_keyparameter(db: &Connection) -> Result<KeyParameter> {
     let mut stmt = db.prepare("SELECT tag, data, security_level FROM persistent.keyparameter")?;
     let mut rows = stmt.query([])?;
     let row = rows.next()?.unwrap();
-    KeyParameter::new_from_sql(Tag(row.get(0)?), &SqlField::new(1, row), SecurityLevel(row.get(2)?))
+    KeyParameter::from_sql_row(row)
+}
+
+/// Test that ApplicationID and ApplicationData blobs of exactly the maximum size are read from
+/// the database, while blobs one byte over are rejected with BlobTooLarge.
+#[test]
+fn test_new_from_sql_enforces_application_blob_sizes() -> Result<()> {
+    for (tag, max) in [
+        (Tag::APPLICATION_ID, KeyParameterValue::APPLICATION_ID_MAX_SIZE),
+        (Tag::APPLICATION_DATA, KeyParameterValue::APPLICATION_DATA_MAX_SIZE),
+    ] {
+        for size in [max, max + 1] {
+            let db = init_db()?;
+            insert_into_keyparameter(&db, 1, tag.0, &vec![0u8; size], 1)?;
+            let mut stmt =
+                db.prepare("SELECT tag, data, security_level FROM persistent.keyparameter;")?;
+            let mut rows = stmt.query([])?;
+            let row = rows.next()?.unwrap();
+            let result = KeyParameterValue::new_from_sql(tag, &SqlField::new(1, row));
+            if size == max {
+                assert_eq!(result?.get_tag(), tag);
+            } else {
+                assert_eq!(result, Err(BlobTooLargeError { tag, size, max }.into()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Test that `decode_rows` decodes a mixed sequence of rows and stops at a corrupted one. The
+/// rows are modeled as columns of a single result row, so that all SqlFields are alive at once.
+#[test]
+fn test_decode_rows() -> Result<()> {
+    let db = Connection::open_in_memory()?;
+    let mut stmt = db.prepare("SELECT ?, ?, ?, ?;")?;
+    let mut rows = stmt.query(params![Algorithm::RSA.0, 2048, b"app".to_vec(), "corrupted"])?;
+    let row = rows.next()?.unwrap();
+    let tee = SecurityLevel::TRUSTED_ENVIRONMENT;
+    let raw_rows = || {
+        vec![
+            (Tag::ALGORITHM, SqlField::new(0, row), tee),
+            (Tag::KEY_SIZE, SqlField::new(1, row), tee),
+            (Tag::APPLICATION_ID, SqlField::new(2, row), SecurityLevel::KEYSTORE),
+            (Tag::NO_AUTH_REQUIRED, SqlField::new(0, row), tee),
+        ]
+    };
+    assert_eq!(
+        decode_rows(raw_rows().into_iter())?,
+        vec![
+            KeyParameter::new(KeyParameterValue::Algorithm(Algorithm::RSA), tee),
+            KeyParameter::new(KeyParameterValue::KeySize(2048), tee),
+            KeyParameter::new(
+                KeyParameterValue::ApplicationID(b"app".to_vec()),
+                SecurityLevel::KEYSTORE
+            ),
+            KeyParameter::new(KeyParameterValue::NoAuthRequired, tee),
+        ]
+    );
+
+    let mut corrupted = raw_rows();
+    corrupted.insert(2, (Tag::MIN_MAC_LENGTH, SqlField::new(3, row), tee));
+    let err = decode_rows(corrupted.into_iter()).unwrap_err();
+    assert_eq!(
+        err.root_cause().downcast_ref::<KeystoreError>(),
+        Some(&KeystoreError::Rc(ResponseCode::VALUE_CORRUPTED))
+    );
+    let chain = format!("{:#}", err);
+    assert!(chain.contains("row 2 with tag MIN_MAC_LENGTH"), "{}", chain);
+    Ok(())
+}
+
+proptest::proptest! {
+    /// Test that generated key characteristics survive a round trip through the
+    /// keyparameter table.
+    #[test]
+    fn test_sql_round_trip_of_valid_characteristics(
+        params in super::testing::any_valid_characteristics()
+    ) {
+        let db = init_db().unwrap();
+        for kp in &params {
+            store_keyparameter(&db, 1, kp).unwrap();
+        }
+        let mut stmt = db
+            .prepare("SELECT tag, data, security_level FROM persistent.keyparameter;")
+            .unwrap();
+        let loaded = stmt
+            .query_map([], |row| Ok(KeyParameter::from_sql_row(row)))
+            .unwrap()
+            .map(|r| r.unwrap().unwrap())
+            .collect::<Vec<_>>();
+        proptest::prop_assert_eq!(loaded, params);
+    }
 }
@@ -0,0 +1,275 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Proptest strategies for property based tests that need random key parameters. The module
+//! is available in tests and, for other crates, with the `testing` feature.
+//! `arb_key_parameter_value` generates parameters independently of each other, whereas
+//! `any_valid_characteristics` generates whole parameter sets that KeyMint could have
+//! returned for a generated key.
+
+use super::{
+    all_known_tags, Algorithm, BlockMode, Digest, EcCurve, FieldType, HardwareAuthenticatorType,
+    KeyOrigin, KeyParameter, KeyParameterValue, KeyPurpose, PaddingMode, Primitive, SecurityLevel,
+    Tag, TagInfo,
+};
+use proptest::prelude::*;
+use proptest::sample::{select, subsequence};
+
+/// Upper bound for the length of generated blobs.
+const MAX_BLOB_LEN: usize = 64;
+
+/// The values generated for KEY_SIZE.
+const KEY_SIZES: &[i32] = &[64, 128, 256, 512, 1024, 2048, 4096, 8192];
+
+/// Upper bound for generated date times, 2100-01-01T00:00:00Z.
+const MAX_DATE_TIME: i64 = 4102444800000;
+
+/// Selects one of the constants of an AIDL enum, given by their numeric values.
+fn enum_primitive(values: Vec<i32>) -> BoxedStrategy<Option<Primitive>> {
+    select(values).prop_map(|v| Some(Primitive::I32(v))).boxed()
+}
+
+/// Returns a strategy for the primitive of a value with the given tag. Enum values are
+/// constants defined by the AIDL spec, KEY_SIZE is a power of two, other integers and date
+/// times are not negative, and blobs are at most MAX_BLOB_LEN bytes long.
+fn arb_primitive(info: TagInfo) -> BoxedStrategy<Option<Primitive>> {
+    match info.field_type {
+        FieldType::Algorithm => enum_primitive(Algorithm::enum_values().map(|v| v.0).to_vec()),
+        FieldType::BlockMode => enum_primitive(BlockMode::enum_values().map(|v| v.0).to_vec()),
+        FieldType::PaddingMode => enum_primitive(PaddingMode::enum_values().map(|v| v.0).to_vec()),
+        FieldType::Digest => enum_primitive(Digest::enum_values().map(|v| v.0).to_vec()),
+        FieldType::EcCurve => enum_primitive(EcCurve::enum_values().map(|v| v.0).to_vec()),
+        FieldType::Origin => enum_primitive(KeyOrigin::enum_values().map(|v| v.0).to_vec()),
+        FieldType::KeyPurpose => enum_primitive(KeyPurpose::enum_values().map(|v| v.0).to_vec()),
+        FieldType::HardwareAuthenticatorType => {
+            enum_primitive(HardwareAuthenticatorType::enum_values().map(|v| v.0).to_vec())
+        }
+        FieldType::SecurityLevel => {
+            enum_primitive(SecurityLevel::enum_values().map(|v| v.0).to_vec())
+        }
+        FieldType::Integer if info.tag == Tag::KEY_SIZE => enum_primitive(KEY_SIZES.to_vec()),
+        FieldType::Integer | FieldType::Invalid => {
+            (0..=i32::MAX).prop_map(|v| Some(Primitive::I32(v))).boxed()
+        }
+        FieldType::LongInteger | FieldType::DateTime => {
+            (0..=i64::MAX).prop_map(|v| Some(Primitive::I64(v))).boxed()
+        }
+        FieldType::Blob => proptest::collection::vec(any::<u8>(), 0..=MAX_BLOB_LEN)
+            .prop_map(|v| Some(Primitive::Vec(v)))
+            .boxed(),
+        FieldType::BoolValue => Just(None).boxed(),
+    }
+}
+
+/// Returns a strategy that generates every variant of KeyParameterValue known to Keystore
+/// with equal probability. The wrapped values are generated as described for `arb_primitive`,
+/// e.g., digests are always defined Digest constants. Unknown values are not generated.
+pub fn arb_key_parameter_value() -> impl Strategy<Value = KeyParameterValue> {
+    select(all_known_tags().collect::<Vec<_>>()).prop_flat_map(|info| {
+        arb_primitive(info).prop_map(move |p| {
+            KeyParameterValue::new_from_tag_optional_primitive(info.tag, p)
+                .expect("The primitive always matches the tag.")
+        })
+    })
+}
+
+/// Returns a strategy that generates key parameters with values from
+/// `arb_key_parameter_value` at any of the security levels defined by the AIDL spec.
+pub fn arb_key_parameter() -> impl Strategy<Value = KeyParameter> {
+    (arb_key_parameter_value(), select(SecurityLevel::enum_values().to_vec()))
+        .prop_map(|(value, security_level)| KeyParameter::new(value, security_level))
+}
+
+/// Returns a strategy for the purposes, digests, and paddings of an RSA key.
+fn rsa_params() -> impl Strategy<Value = Vec<KeyParameterValue>> {
+    (
+        select(&[1024, 2048, 3072, 4096][..]),
+        select(&[3i64, 65537][..]),
+        subsequence(
+            &[KeyPurpose::ENCRYPT, KeyPurpose::DECRYPT, KeyPurpose::SIGN, KeyPurpose::VERIFY][..],
+            1..=4,
+        ),
+        subsequence(&[Digest::NONE, Digest::SHA1, Digest::SHA_2_256, Digest::SHA_2_512][..], 0..=4),
+        subsequence(
+            &[
+                PaddingMode::NONE,
+                PaddingMode::RSA_OAEP,
+                PaddingMode::RSA_PSS,
+                PaddingMode::RSA_PKCS1_1_5_ENCRYPT,
+                PaddingMode::RSA_PKCS1_1_5_SIGN,
+            ][..],
+            0..=5,
+        ),
+    )
+        .prop_map(|(key_size, exponent, purposes, mut digests, paddings)| {
+            let mut values = vec![
+                KeyParameterValue::Algorithm(Algorithm::RSA),
+                KeyParameterValue::KeySize(key_size),
+                KeyParameterValue::RSAPublicExponent(exponent),
+            ];
+            if purposes.contains(&KeyPurpose::SIGN) {
+                digests.retain(|d| *d != Digest::NONE);
+            }
+            values.extend(purposes.into_iter().map(KeyParameterValue::KeyPurpose));
+            values.extend(digests.into_iter().map(KeyParameterValue::Digest));
+            values.extend(paddings.into_iter().map(KeyParameterValue::PaddingMode));
+            values
+        })
+}
+
+/// Returns a strategy for an EC key with a NIST curve and the matching key size.
+fn ec_params() -> impl Strategy<Value = Vec<KeyParameterValue>> {
+    (
+        select(
+            &[
+                (EcCurve::P_224, 224),
+                (EcCurve::P_256, 256),
+                (EcCurve::P_384, 384),
+                (EcCurve::P_521, 521),
+            ][..],
+        ),
+        subsequence(&[KeyPurpose::SIGN, KeyPurpose::VERIFY, KeyPurpose::AGREE_KEY][..], 1..=3),
+        subsequence(&[Digest::NONE, Digest::SHA_2_256, Digest::SHA_2_384][..], 0..=3),
+    )
+        .prop_map(|((curve, key_size), purposes, mut digests)| {
+            let mut values = vec![
+                KeyParameterValue::Algorithm(Algorithm::EC),
+                KeyParameterValue::EcCurve(curve),
+                KeyParameterValue::KeySize(key_size),
+            ];
+            if purposes.contains(&KeyPurpose::SIGN) {
+                digests.retain(|d| *d != Digest::NONE);
+            }
+            values.extend(purposes.into_iter().map(KeyParameterValue::KeyPurpose));
+            values.extend(digests.into_iter().map(KeyParameterValue::Digest));
+            values
+        })
+}
+
+/// Returns a strategy for an AES key. MIN_MAC_LENGTH is only given if GCM is among the
+/// block modes.
+fn aes_params() -> impl Strategy<Value = Vec<KeyParameterValue>> {
+    (
+        select(&[128, 192, 256][..]),
+        subsequence(&[KeyPurpose::ENCRYPT, KeyPurpose::DECRYPT][..], 1..=2),
+        subsequence(&[BlockMode::ECB, BlockMode::CBC, BlockMode::CTR, BlockMode::GCM][..], 1..=4),
+        subsequence(&[PaddingMode::NONE, PaddingMode::PKCS7][..], 0..=2),
+        select(&[96, 104, 112, 120, 128][..]),
+    )
+        .prop_map(|(key_size, purposes, block_modes, paddings, min_mac_length)| {
+            let mut values = vec![
+                KeyParameterValue::Algorithm(Algorithm::AES),
+                KeyParameterValue::KeySize(key_size),
+            ];
+            if block_modes.contains(&BlockMode::GCM) {
+                values.push(KeyParameterValue::MinMacLength(min_mac_length));
+            }
+            values.extend(purposes.into_iter().map(KeyParameterValue::KeyPurpose));
+            values.extend(block_modes.into_iter().map(KeyParameterValue::BlockMode));
+            values.extend(paddings.into_iter().map(KeyParameterValue::PaddingMode));
+            values
+        })
+}
+
+/// Returns a strategy for a 3DES key.
+fn triple_des_params() -> impl Strategy<Value = Vec<KeyParameterValue>> {
+    (
+        subsequence(&[KeyPurpose::ENCRYPT, KeyPurpose::DECRYPT][..], 1..=2),
+        subsequence(&[BlockMode::ECB, BlockMode::CBC][..], 1..=2),
+        subsequence(&[PaddingMode::NONE, PaddingMode::PKCS7][..], 0..=2),
+    )
+        .prop_map(|(purposes, block_modes, paddings)| {
+            let mut values = vec![
+                KeyParameterValue::Algorithm(Algorithm::TRIPLE_DES),
+                KeyParameterValue::KeySize(168),
+            ];
+            values.extend(purposes.into_iter().map(KeyParameterValue::KeyPurpose));
+            values.extend(block_modes.into_iter().map(KeyParameterValue::BlockMode));
+            values.extend(paddings.into_iter().map(KeyParameterValue::PaddingMode));
+            values
+        })
+}
+
+/// Returns a strategy for an HMAC key with a single digest. The key size is a multiple of 8
+/// between 64 and 512, and MIN_MAC_LENGTH does not exceed the output of the shortest digest.
+fn hmac_params() -> impl Strategy<Value = Vec<KeyParameterValue>> {
+    (
+        (8..=64).prop_map(|n: i32| n * 8),
+        subsequence(&[KeyPurpose::SIGN, KeyPurpose::VERIFY][..], 1..=2),
+        select(&[Digest::SHA_2_224, Digest::SHA_2_256, Digest::SHA_2_384, Digest::SHA_2_512][..]),
+        (8..=28).prop_map(|n: i32| n * 8),
+    )
+        .prop_map(|(key_size, purposes, digest, min_mac_length)| {
+            let mut values = vec![
+                KeyParameterValue::Algorithm(Algorithm::HMAC),
+                KeyParameterValue::KeySize(key_size),
+                KeyParameterValue::Digest(digest),
+                KeyParameterValue::MinMacLength(min_mac_length),
+            ];
+            values.extend(purposes.into_iter().map(KeyParameterValue::KeyPurpose));
+            values
+        })
+}
+
+/// Returns a strategy for the authentication parameters of a key, i.e., either
+/// NO_AUTH_REQUIRED, or a secure user id with an authenticator type and a timeout.
+fn auth_params() -> impl Strategy<Value = Vec<KeyParameterValue>> {
+    prop_oneof![
+        Just(vec![KeyParameterValue::NoAuthRequired]),
+        (
+            any::<i64>(),
+            select(
+                &[HardwareAuthenticatorType::PASSWORD, HardwareAuthenticatorType::FINGERPRINT][..]
+            ),
+            1..=3600i32,
+        )
+            .prop_map(|(secure_id, authenticator, timeout)| vec![
+                KeyParameterValue::UserSecureID(secure_id),
+                KeyParameterValue::HardwareAuthenticatorType(authenticator),
+                KeyParameterValue::AuthTimeout(timeout),
+            ]),
+    ]
+}
+
+/// Returns a strategy that generates internally consistent key characteristics of a
+/// generated key. The algorithm specific parameters match the algorithm, e.g., an EC curve
+/// comes with the matching key size, and RSA key sizes are those supported by KeyMint.
+/// The generated sets have all tags required for the algorithm, no conflicting values for
+/// single valued tags, no mutually exclusive or inapplicable tags, and no Digest::NONE on
+/// signing keys, so they are accepted
+/// by `KeyParameterSetBuilder::build`. All parameters are enforced by the same hardware
+/// security level, except for CREATION_DATETIME, which is enforced by Keystore. The order of
+/// the parameters is random.
+pub fn any_valid_characteristics() -> impl Strategy<Value = Vec<KeyParameter>> {
+    (
+        prop_oneof![rsa_params(), ec_params(), aes_params(), triple_des_params(), hmac_params()],
+        auth_params(),
+        select(&[SecurityLevel::TRUSTED_ENVIRONMENT, SecurityLevel::STRONGBOX][..]),
+        0..=MAX_DATE_TIME,
+    )
+        .prop_map(|(algorithm_values, auth_values, security_level, creation_date_time)| {
+            let mut params: Vec<KeyParameter> = algorithm_values
+                .into_iter()
+                .chain(auth_values)
+                .chain([KeyParameterValue::KeyOrigin(KeyOrigin::GENERATED)])
+                .map(|value| KeyParameter::new(value, security_level))
+                .collect();
+            params.push(KeyParameter::new(
+                KeyParameterValue::CreationDateTime(creation_date_time),
+                SecurityLevel::KEYSTORE,
+            ));
+            params
+        })
+        .prop_shuffle()
+}
@@ -24,13 +24,20 @@ use crate::key_parameter::*;
 /// unit tests for to conversions
 #[test]
 fn test_convert_to_wire_invalid() {
-    let kp = KeyParameter::new(KeyParameterValue::Invalid, SecurityLevel::STRONGBOX);
+    let kp = KeyParameter::new(KeyParameterValue::Invalid(0), SecurityLevel::STRONGBOX);
     assert_eq!(
         KmKeyParameter { tag: Tag::INVALID, value: KmKeyParameterValue::Invalid(0) },
         kp.value.into()
     );
 }
 #[test]
+fn test_invalid_payload_round_trip() {
+    let aidl_kp = KmKeyParameter { tag: Tag::INVALID, value: KmKeyParameterValue::Invalid(7) };
+    let value = KeyParameterValue::from_km_parameter_lossy(aidl_kp.clone());
+    assert_eq!(KeyParameterValue::Invalid(7), value);
+    assert_eq!(aidl_kp, value.into());
+}
+#[test]
 fn test_convert_to_wire_bool() {
     let kp = KeyParameter::new(KeyParameterValue::CallerNonce, SecurityLevel::STRONGBOX);
     assert_eq!(
@@ -82,13 +89,13 @@ fn test_convert_to_wire_blob() {
 #[test]
 fn test_convert_from_wire_invalid() {
     let aidl_kp = KmKeyParameter { tag: Tag::INVALID, ..Default::default() };
-    assert_eq!(KeyParameterValue::Invalid, aidl_kp.into());
+    assert_eq!(KeyParameterValue::Invalid(0), KeyParameterValue::from_km_parameter_lossy(aidl_kp));
 }
 #[test]
 fn test_convert_from_wire_bool() {
     let aidl_kp =
         KmKeyParameter { tag: Tag::CALLER_NONCE, value: KmKeyParameterValue::BoolValue(true) };
-    assert_eq!(KeyParameterValue::CallerNonce, aidl_kp.into());
+    assert_eq!(KeyParameterValue::CallerNonce, KeyParameterValue::from_km_parameter_lossy(aidl_kp));
 }
 #[test]
 fn test_convert_from_wire_integer() {
@@ -96,7 +103,10 @@ fn test_convert_from_wire_integer() {
         tag: Tag::PURPOSE,
         value: KmKeyParameterValue::KeyPurpose(KeyPurpose::ENCRYPT),
     };
-    assert_eq!(KeyParameterValue::KeyPurpose(KeyPurpose::ENCRYPT), aidl_kp.into());
+    assert_eq!(
+        KeyParameterValue::KeyPurpose(KeyPurpose::ENCRYPT),
+        KeyParameterValue::from_km_parameter_lossy(aidl_kp)
+    );
 }
 #[test]
 fn test_convert_from_wire_long_integer() {
@@ -104,7 +114,10 @@ fn test_convert_from_wire_long_integer() {
         tag: Tag::USER_SECURE_ID,
         value: KmKeyParameterValue::LongInteger(i64::MAX),
     };
-    assert_eq!(KeyParameterValue::UserSecureID(i64::MAX), aidl_kp.into());
+    assert_eq!(
+        KeyParameterValue::UserSecureID(i64::MAX),
+        KeyParameterValue::from_km_parameter_lossy(aidl_kp)
+    );
 }
 #[test]
 fn test_convert_from_wire_blob() {
@@ -114,6 +127,293 @@ fn test_convert_from_wire_blob() {
     };
     assert_eq!(
         KeyParameterValue::ConfirmationToken(String::from("ConfirmationToken").into_bytes()),
-        aidl_kp.into()
+        KeyParameterValue::from_km_parameter_lossy(aidl_kp)
+    );
+}
+
+/// unit tests for the fallible from conversion
+#[test]
+fn test_try_convert_from_wire() {
+    let aidl_kp = KmKeyParameter {
+        tag: Tag::ALGORITHM,
+        value: KmKeyParameterValue::Algorithm(Algorithm::EC),
+    };
+    assert_eq!(
+        Ok(KeyParameterValue::Algorithm(Algorithm::EC)),
+        KeyParameterValue::try_from_km_parameter(aidl_kp)
+    );
+    let aidl_kp =
+        KmKeyParameter { tag: Tag::CALLER_NONCE, value: KmKeyParameterValue::BoolValue(true) };
+    assert_eq!(
+        Ok(KeyParameterValue::CallerNonce),
+        KeyParameterValue::try_from_km_parameter(aidl_kp)
+    );
+}
+#[test]
+fn test_try_convert_from_wire_field_type_mismatch() {
+    let aidl_kp =
+        KmKeyParameter { tag: Tag::KEY_SIZE, value: KmKeyParameterValue::LongInteger(256) };
+    assert_eq!(
+        Err(KeyParameterConversionError::FieldTypeMismatch { tag: Tag::KEY_SIZE }),
+        KeyParameterValue::try_from_km_parameter(aidl_kp.clone())
+    );
+    // The lossy conversion still maps the parameter to Invalid.
+    assert_eq!(KeyParameterValue::Invalid(0), KeyParameterValue::from_km_parameter_lossy(aidl_kp));
+}
+#[test]
+fn test_try_convert_from_wire_unknown_tag() {
+    let aidl_kp = KmKeyParameter {
+        tag: Tag(TagType::UINT.0 | 0xfff),
+        value: KmKeyParameterValue::Integer(1),
+    };
+    assert_eq!(
+        Err(KeyParameterConversionError::UnknownTag { tag: Tag(TagType::UINT.0 | 0xfff) }),
+        KeyParameterValue::try_from_km_parameter(aidl_kp)
     );
 }
+#[test]
+fn test_try_convert_from_wire_undefined_enum_value() {
+    let aidl_kp =
+        KmKeyParameter { tag: Tag::DIGEST, value: KmKeyParameterValue::Digest(Digest(0x7fff)) };
+    assert_eq!(
+        Err(KeyParameterConversionError::InvalidValue { tag: Tag::DIGEST, value: 0x7fff }),
+        KeyParameterValue::try_from_km_parameter(aidl_kp.clone())
+    );
+    // The lossy conversion keeps the undefined value.
+    assert_eq!(
+        KeyParameterValue::Digest(Digest(0x7fff)),
+        KeyParameterValue::from_km_parameter_lossy(aidl_kp)
+    );
+
+    // A parameter with the INVALID tag is converted, so it is not confused with the failures.
+    let aidl_kp = KmKeyParameter { tag: Tag::INVALID, value: KmKeyParameterValue::Invalid(3) };
+    assert_eq!(
+        Ok(KeyParameterValue::Invalid(3)),
+        KeyParameterValue::try_from_km_parameter(aidl_kp)
+    );
+}
+#[test]
+fn test_from_km_key_parameter_value_with_tag() {
+    assert_eq!(
+        KeyParameterValue::Algorithm(Algorithm::EC),
+        KeyParameterValue::from_km_key_parameter_value_with_tag(
+            Tag::ALGORITHM,
+            KmKeyParameterValue::Algorithm(Algorithm::EC)
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        KeyParameterValue::Invalid(3),
+        KeyParameterValue::from_km_key_parameter_value_with_tag(
+            Tag::INVALID,
+            KmKeyParameterValue::Invalid(3)
+        )
+        .unwrap()
+    );
+
+    let err = KeyParameterValue::from_km_key_parameter_value_with_tag(
+        Tag::ALGORITHM,
+        KmKeyParameterValue::LongInteger(1),
+    )
+    .unwrap_err();
+    assert_eq!(
+        Some(&KeyParameterError::TypeMismatch(Tag::ALGORITHM)),
+        err.root_cause().downcast_ref::<KeyParameterError>()
+    );
+
+    let unknown = Tag(TagType::BYTES.0 | 0xfff);
+    let err = KeyParameterValue::from_km_key_parameter_value_with_tag(
+        unknown,
+        KmKeyParameterValue::Blob(vec![1]),
+    )
+    .unwrap_err();
+    assert_eq!(
+        Some(&KeyParameterError::UnsupportedTag(unknown)),
+        err.root_cause().downcast_ref::<KeyParameterError>()
+    );
+
+    let err = KeyParameterValue::from_km_key_parameter_value_with_tag(
+        Tag::ALGORITHM,
+        KmKeyParameterValue::Algorithm(Algorithm(99)),
+    )
+    .unwrap_err();
+    assert_eq!(
+        Some(&KeyParameterConversionError::InvalidValue { tag: Tag::ALGORITHM, value: 99 }),
+        err.root_cause().downcast_ref::<KeyParameterConversionError>()
+    );
+}
+#[test]
+fn test_convert_from_wire_checked_rejects_oversized_blob() {
+    let limits = BlobLimits::default();
+    let max = BlobLimits::ATTESTATION_CHALLENGE_MAX_SIZE;
+    let challenge = |len| KmKeyParameter {
+        tag: Tag::ATTESTATION_CHALLENGE,
+        value: KmKeyParameterValue::Blob(vec![0xaa; len]),
+    };
+    assert_eq!(
+        Ok(KeyParameterValue::AttestationChallenge(vec![0xaa; max])),
+        KeyParameterValue::from_km_param_checked(challenge(max), &limits)
+    );
+    assert_eq!(
+        Err(KeyParameterConversionError::BlobTooLarge(BlobTooLargeError {
+            tag: Tag::ATTESTATION_CHALLENGE,
+            size: 1 << 20,
+            max
+        })),
+        KeyParameterValue::from_km_param_checked(challenge(1 << 20), &limits)
+    );
+    assert!(KeyParameterValue::from_km_param_checked(challenge(1 << 20), &BlobLimits::unlimited())
+        .is_ok());
+
+    // Tags without a limit are not bounded, and other errors are reported as before.
+    let mut limits = BlobLimits::unlimited();
+    limits.set_limit(Tag::APPLICATION_ID, 4);
+    let aidl_kp =
+        KmKeyParameter { tag: Tag::APPLICATION_DATA, value: KmKeyParameterValue::Blob(vec![0; 8]) };
+    assert_eq!(
+        Ok(KeyParameterValue::ApplicationData(vec![0; 8])),
+        KeyParameterValue::from_km_param_checked(aidl_kp, &limits)
+    );
+    let aidl_kp =
+        KmKeyParameter { tag: Tag::APPLICATION_ID, value: KmKeyParameterValue::Blob(vec![0; 5]) };
+    assert_eq!(
+        Err(KeyParameterConversionError::BlobTooLarge(BlobTooLargeError {
+            tag: Tag::APPLICATION_ID,
+            size: 5,
+            max: 4
+        })),
+        KeyParameterValue::from_km_param_checked(aidl_kp, &limits)
+    );
+    let aidl_kp =
+        KmKeyParameter { tag: Tag::APPLICATION_ID, value: KmKeyParameterValue::Integer(5) };
+    assert_eq!(
+        Err(KeyParameterConversionError::FieldTypeMismatch { tag: Tag::APPLICATION_ID }),
+        KeyParameterValue::from_km_param_checked(aidl_kp, &limits)
+    );
+}
+
+/// unit tests for parameters with tags unknown to Keystore
+#[test]
+fn test_convert_from_wire_unknown_tag() {
+    let aidl_kp = KmKeyParameter {
+        tag: Tag(TagType::UINT.0 | 0xfff),
+        value: KmKeyParameterValue::Integer(7),
+    };
+    let value = KeyParameterValue::from_km_parameter_lossy(aidl_kp.clone());
+    assert_eq!(
+        KeyParameterValue::Unknown {
+            tag: Tag(TagType::UINT.0 | 0xfff),
+            value: KmKeyParameterValue::Integer(7)
+        },
+        value
+    );
+    assert_eq!(Tag(TagType::UINT.0 | 0xfff), value.get_tag());
+    assert_eq!(aidl_kp, value.into());
+}
+#[test]
+fn test_convert_from_wire_known_tag_wrong_field() {
+    let aidl_kp = KmKeyParameter { tag: Tag::KEY_SIZE, value: KmKeyParameterValue::LongInteger(1) };
+    assert_eq!(KeyParameterValue::Invalid(0), KeyParameterValue::from_km_parameter_lossy(aidl_kp));
+}
+#[test]
+fn test_bulk_conversion_moves_blobs() {
+    let params: Vec<KmKeyParameter> = (0..100u8)
+        .map(|i| KmKeyParameter {
+            tag: Tag::ATTESTATION_APPLICATION_ID,
+            value: KmKeyParameterValue::Blob(vec![i; 4096]),
+        })
+        .collect();
+    let blob_ptrs: Vec<*const u8> = params
+        .iter()
+        .map(|kp| match &kp.value {
+            KmKeyParameterValue::Blob(b) => b.as_ptr(),
+            _ => unreachable!(),
+        })
+        .collect();
+
+    let values = KeyParameterValue::from_km_params_lossy(params);
+    assert_eq!(values.len(), 100);
+    for (i, (value, ptr)) in values.iter().zip(&blob_ptrs).enumerate() {
+        match value {
+            KeyParameterValue::AttestationApplicationID(b) => {
+                assert_eq!(b, &vec![i as u8; 4096]);
+                assert_eq!(b.as_ptr(), *ptr, "Blob {} was copied.", i);
+            }
+            v => panic!("Unexpected value {:?}.", v.redacted()),
+        }
+    }
+
+    let params = KeyParameterValue::into_km_params(values);
+    assert_eq!(params.len(), 100);
+    for (kp, ptr) in params.iter().zip(&blob_ptrs) {
+        match &kp.value {
+            KmKeyParameterValue::Blob(b) => assert_eq!(b.as_ptr(), *ptr),
+            v => panic!("Unexpected value {:?}.", v),
+        }
+    }
+}
+#[test]
+fn test_value_ref_from_km_parameter_lossy() {
+    let params = vec![
+        KmKeyParameter {
+            tag: Tag::ALGORITHM,
+            value: KmKeyParameterValue::Algorithm(Algorithm::EC),
+        },
+        KmKeyParameter { tag: Tag::CALLER_NONCE, value: KmKeyParameterValue::BoolValue(true) },
+        KmKeyParameter { tag: Tag::KEY_SIZE, value: KmKeyParameterValue::LongInteger(1) },
+        KmKeyParameter { tag: Tag::NONCE, value: KmKeyParameterValue::Blob(vec![0xaa; 4096]) },
+        KmKeyParameter {
+            tag: Tag(TagType::UINT.0 | 0xfff),
+            value: KmKeyParameterValue::Integer(7),
+        },
+    ];
+    for kp in &params {
+        let value_ref = KeyParameterValueRef::from_km_parameter_lossy(kp);
+        assert_eq!(
+            KeyParameterValue::from(value_ref),
+            KeyParameterValue::from_km_parameter_lossy(kp.clone())
+        );
+    }
+    match (KeyParameterValueRef::from_km_parameter_lossy(&params[3]), &params[3].value) {
+        (KeyParameterValueRef::Nonce(data), KmKeyParameterValue::Blob(blob)) => {
+            assert_eq!(data.as_ptr(), blob.as_ptr(), "Blob was copied.")
+        }
+        (v, _) => panic!("Unexpected value {:?}.", v),
+    }
+}
+#[test]
+fn test_value_ref_borrows_blobs() {
+    let value = KeyParameterValue::AssociatedData(vec![0xaa; 4096]);
+    let value_ref = value.as_ref();
+    match value_ref {
+        KeyParameterValueRef::AssociatedData(data) => {
+            assert_eq!(data.as_ptr(), value.blob_value().unwrap().as_ptr())
+        }
+        v => panic!("Unexpected value {:?}.", v),
+    }
+    assert_eq!(KeyParameterValue::from(value_ref), value);
+    assert_eq!(KmKeyParameter::from(value_ref), KmKeyParameter::from(value.clone()));
+
+    assert_eq!(
+        KeyParameterValue::Algorithm(Algorithm::EC).as_ref(),
+        KeyParameterValueRef::Algorithm(Algorithm::EC)
+    );
+    assert_eq!(KeyParameterValue::CallerNonce.as_ref(), KeyParameterValueRef::CallerNonce);
+}
+
+proptest::proptest! {
+    #[test]
+    fn test_wire_round_trip_of_generated_values(
+        value in super::testing::arb_key_parameter_value()
+    ) {
+        let km_param: KmKeyParameter = value.clone().into();
+        proptest::prop_assert_eq!(
+            KeyParameterValue::try_from_km_parameter(km_param),
+            Ok(value.clone())
+        );
+        proptest::prop_assert!(value.validate_enum_ranges().is_ok());
+        if let KeyParameterValue::KeySize(size) = value {
+            proptest::prop_assert!(size > 0 && (size & (size - 1)) == 0);
+        }
+    }
+}
@@ -0,0 +1,575 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module implements a builder for sets of key parameters, that checks the combination
+//! of parameters before it is passed to KeyMint for key generation or import.
+//! KeyMint rejects many invalid combinations with an opaque error code, and some invalid
+//! combinations are silently accepted. The builder catches these mistakes early and reports
+//! all offending tags at once. `validate_combination` performs only the cross-tag checks on
+//! an existing list of key parameters; the builder runs the same checks.
+//!
+//! The builder produces a `KeyParameterSet`, a list of key parameters that is free of
+//! duplicates and can be brought into a canonical order.
+//!
+//! This module also merges the hardware and software enforced characteristics returned by
+//! KeyMint into a single set, either by policy or by the precedence of the security levels.
+
+use crate::error::{Error as KeystoreError, ResponseCode};
+use crate::key_parameter::{
+    check_cardinality, contains_tag, find_all_tags, is_repeatable_tag, Algorithm, BlockMode,
+    Digest, KeyParameter, KeyParameterValue, KeyPurpose, Primitive, SecurityLevel, Tag,
+};
+use anyhow::Context;
+use anyhow::Result;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Deref;
+
+#[cfg(test)]
+mod tests;
+
+/// Pairs of tags that must not be present in the same key parameter set.
+const MUTUALLY_EXCLUSIVE_TAGS: &[(Tag, Tag)] = &[
+    (Tag::NO_AUTH_REQUIRED, Tag::USER_SECURE_ID),
+    (Tag::NO_AUTH_REQUIRED, Tag::USER_AUTH_TYPE),
+    (Tag::NO_AUTH_REQUIRED, Tag::AUTH_TIMEOUT),
+    (Tag::NO_AUTH_REQUIRED, Tag::TRUSTED_USER_PRESENCE_REQUIRED),
+    (Tag::NO_AUTH_REQUIRED, Tag::ALLOW_WHILE_ON_BODY),
+    (Tag::EARLY_BOOT_ONLY, Tag::UNLOCKED_DEVICE_REQUIRED),
+    (Tag::BOOTLOADER_ONLY, Tag::EARLY_BOOT_ONLY),
+];
+
+/// Describes a problem with the combination of key parameters found by
+/// `KeyParameterSetBuilder::build` or `validate_combination`.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The ALGORITHM tag is missing.
+    #[error("Missing tag ALGORITHM.")]
+    MissingAlgorithm,
+    /// A tag required by the algorithm of the key is missing.
+    #[error("Missing tag {tag:?} required by algorithm {algorithm:?}.")]
+    MissingTag {
+        /// The missing tag.
+        tag: Tag,
+        /// The algorithm requiring the tag.
+        algorithm: Algorithm,
+    },
+    /// The purpose cannot be used with the algorithm of the key.
+    #[error("Purpose {purpose:?} is not supported by algorithm {algorithm:?}.")]
+    IncompatiblePurpose {
+        /// The algorithm of the key.
+        algorithm: Algorithm,
+        /// The offending purpose.
+        purpose: KeyPurpose,
+    },
+    /// The digest cannot be used with the purpose, e.g., Digest::NONE for signing.
+    #[error("Digest {digest:?} cannot be used for purpose {purpose:?}.")]
+    IncompatibleDigest {
+        /// The purpose of the key.
+        purpose: KeyPurpose,
+        /// The offending digest.
+        digest: Digest,
+    },
+    /// The tag has no meaning for the algorithm of the key.
+    #[error("Tag {tag:?} is not applicable to algorithm {algorithm:?}.")]
+    InapplicableTag {
+        /// The algorithm of the key.
+        algorithm: Algorithm,
+        /// The offending tag.
+        tag: Tag,
+    },
+    /// Both tags are present, but they must not be combined.
+    #[error("Tags {0:?} and {1:?} are mutually exclusive.")]
+    MutuallyExclusive(Tag, Tag),
+    /// The tag occurs more than once with differing values at the same security level, but it
+    /// is not repeatable. See `check_cardinality`.
+    #[error("Tag {tag:?} must not have differing values at security level {security_level:?}.")]
+    DuplicateTag {
+        /// The repeated tag.
+        tag: Tag,
+        /// The security level at which the tag is repeated.
+        security_level: SecurityLevel,
+    },
+    /// The value is outside the bounds defined by the KeyMint specification.
+    #[error("Value {value:?} of tag {tag:?} is out of range, expected {allowed}.")]
+    OutOfRange {
+        /// The offending tag.
+        tag: Tag,
+        /// The offending value.
+        value: Primitive,
+        /// Describes the allowed values.
+        allowed: &'static str,
+    },
+    /// MIN_MAC_LENGTH is given for an AES key that does not use the GCM block mode.
+    #[error("Tag MIN_MAC_LENGTH requires block mode GCM for AES keys.")]
+    MinMacLengthWithoutGcm,
+}
+
+impl ValidationError {
+    /// Returns the tags involved in the problem.
+    pub fn tags(&self) -> Vec<Tag> {
+        match self {
+            Self::MissingAlgorithm => vec![Tag::ALGORITHM],
+            Self::MissingTag { tag, .. } | Self::DuplicateTag { tag, .. } => vec![*tag],
+            Self::OutOfRange { tag, .. } => vec![*tag],
+            Self::IncompatiblePurpose { .. } => vec![Tag::ALGORITHM, Tag::PURPOSE],
+            Self::IncompatibleDigest { .. } => vec![Tag::PURPOSE, Tag::DIGEST],
+            Self::InapplicableTag { tag, .. } => vec![Tag::ALGORITHM, *tag],
+            Self::MutuallyExclusive(a, b) => vec![*a, *b],
+            Self::MinMacLengthWithoutGcm => vec![Tag::MIN_MAC_LENGTH, Tag::BLOCK_MODE],
+        }
+    }
+}
+
+/// Returns the purposes that can be used with keys of the given algorithm.
+fn supported_purposes(algorithm: Algorithm) -> &'static [KeyPurpose] {
+    match algorithm {
+        Algorithm::RSA => &[
+            KeyPurpose::ENCRYPT,
+            KeyPurpose::DECRYPT,
+            KeyPurpose::SIGN,
+            KeyPurpose::VERIFY,
+            KeyPurpose::WRAP_KEY,
+            KeyPurpose::ATTEST_KEY,
+        ],
+        Algorithm::EC => {
+            &[KeyPurpose::SIGN, KeyPurpose::VERIFY, KeyPurpose::AGREE_KEY, KeyPurpose::ATTEST_KEY]
+        }
+        Algorithm::AES => &[KeyPurpose::ENCRYPT, KeyPurpose::DECRYPT, KeyPurpose::WRAP_KEY],
+        Algorithm::TRIPLE_DES => &[KeyPurpose::ENCRYPT, KeyPurpose::DECRYPT],
+        Algorithm::HMAC => &[KeyPurpose::SIGN, KeyPurpose::VERIFY],
+        _ => &[],
+    }
+}
+
+/// Returns true if the given tag is applicable to keys of the given algorithm.
+fn is_applicable(algorithm: Algorithm, tag: Tag) -> bool {
+    match tag {
+        Tag::EC_CURVE => algorithm == Algorithm::EC,
+        Tag::RSA_PUBLIC_EXPONENT | Tag::RSA_OAEP_MGF_DIGEST => algorithm == Algorithm::RSA,
+        Tag::BLOCK_MODE => matches!(algorithm, Algorithm::AES | Algorithm::TRIPLE_DES),
+        Tag::MIN_MAC_LENGTH => matches!(algorithm, Algorithm::AES | Algorithm::HMAC),
+        _ => true,
+    }
+}
+
+/// RSA key sizes supported by KeyMint.
+const RSA_KEY_SIZES: &[i32] = &[1024, 2048, 3072, 4096];
+
+/// The length of the GCM authentication tag in bits, which bounds MIN_MAC_LENGTH of AES keys.
+const GCM_TAG_BITS: i32 = 128;
+
+/// Returns the output size of the digest in bits, which bounds MIN_MAC_LENGTH of HMAC keys.
+fn digest_bits(digest: Digest) -> Option<i32> {
+    match digest {
+        Digest::MD5 => Some(128),
+        Digest::SHA1 => Some(160),
+        Digest::SHA_2_224 => Some(224),
+        Digest::SHA_2_256 => Some(256),
+        Digest::SHA_2_384 => Some(384),
+        Digest::SHA_2_512 => Some(512),
+        _ => None,
+    }
+}
+
+/// Accumulates key parameters and validates their combination when the set is built.
+#[derive(Debug, Default, Clone)]
+pub struct KeyParameterSetBuilder {
+    params: Vec<KeyParameter>,
+}
+
+impl KeyParameterSetBuilder {
+    /// Creates an empty builder.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds the given key parameter to the set.
+    pub fn add(&mut self, param: KeyParameter) -> &mut Self {
+        self.params.push(param);
+        self
+    }
+
+    /// Validates the accumulated key parameters and returns them as `KeyParameterSet` if they
+    /// form a consistent set. Otherwise, all problems found are returned.
+    pub fn build(&self) -> Result<KeyParameterSet, Vec<ValidationError>> {
+        let errors = self.validate();
+        if errors.is_empty() {
+            Ok(self.params.clone().into())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        let algorithm = self.params.iter().find_map(|kp| match kp.key_parameter_value() {
+            KeyParameterValue::Algorithm(a) => Some(*a),
+            _ => None,
+        });
+
+        match algorithm {
+            Some(algorithm) => {
+                let purposes = supported_purposes(algorithm);
+                let max_mac_length = match algorithm {
+                    Algorithm::AES => Some((GCM_TAG_BITS, "at most 128 for AES keys")),
+                    Algorithm::HMAC => self
+                        .params
+                        .iter()
+                        .filter_map(|kp| match kp.key_parameter_value() {
+                            KeyParameterValue::Digest(d) => digest_bits(*d),
+                            _ => None,
+                        })
+                        .max()
+                        .map(|bits| (bits, "at most the output size of the digest of HMAC keys")),
+                    _ => None,
+                };
+                for kp in &self.params {
+                    match kp.key_parameter_value() {
+                        KeyParameterValue::KeyPurpose(purpose) if !purposes.contains(purpose) => {
+                            errors.push(ValidationError::IncompatiblePurpose {
+                                algorithm,
+                                purpose: *purpose,
+                            });
+                        }
+                        KeyParameterValue::KeySize(size)
+                            if algorithm == Algorithm::RSA && !RSA_KEY_SIZES.contains(size) =>
+                        {
+                            errors.push(ValidationError::OutOfRange {
+                                tag: Tag::KEY_SIZE,
+                                value: Primitive::I32(*size),
+                                allowed: "1024, 2048, 3072, or 4096 for RSA keys",
+                            });
+                        }
+                        KeyParameterValue::MinMacLength(length) => match max_mac_length {
+                            Some((max, allowed)) if *length > max => {
+                                errors.push(ValidationError::OutOfRange {
+                                    tag: Tag::MIN_MAC_LENGTH,
+                                    value: Primitive::I32(*length),
+                                    allowed,
+                                });
+                            }
+                            _ => {}
+                        },
+                        _ => {}
+                    }
+                }
+                for tag in KeyParameterValue::required_for_algorithm(algorithm) {
+                    if !contains_tag(&self.params, tag) {
+                        errors.push(ValidationError::MissingTag { tag, algorithm });
+                    }
+                }
+            }
+            None => errors.push(ValidationError::MissingAlgorithm),
+        }
+
+        if let Err(e) = validate_combination(&self.params) {
+            errors.extend(e);
+        }
+
+        errors.extend(
+            self.params
+                .iter()
+                .filter_map(|kp| kp.key_parameter_value().validate_value_range().err()),
+        );
+
+        if let Err(e) = check_cardinality(&self.params) {
+            errors.extend(e.duplicates.into_iter().map(|(tag, security_level)| {
+                ValidationError::DuplicateTag { tag, security_level }
+            }));
+        }
+
+        errors
+    }
+}
+
+/// Checks the key parameters for contradicting tags, i.e., tags that exclude each other,
+/// tags that are not applicable to the algorithm of the key, Digest::NONE on signing keys,
+/// and MIN_MAC_LENGTH without the GCM block mode on AES keys. Unlike
+/// `KeyParameterSetBuilder::build`, this does not check for missing tags or purposes. It is
+/// meant as a sanity check before calling `generateKey`. All violations are returned.
+pub fn validate_combination(params: &[KeyParameter]) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+
+    for (a, b) in MUTUALLY_EXCLUSIVE_TAGS {
+        if contains_tag(params, *a) && contains_tag(params, *b) {
+            errors.push(ValidationError::MutuallyExclusive(*a, *b));
+        }
+    }
+
+    let signs = params
+        .iter()
+        .any(|kp| kp.key_parameter_value() == &KeyParameterValue::KeyPurpose(KeyPurpose::SIGN));
+    let digest_none = params
+        .iter()
+        .any(|kp| kp.key_parameter_value() == &KeyParameterValue::Digest(Digest::NONE));
+    if signs && digest_none {
+        errors.push(ValidationError::IncompatibleDigest {
+            purpose: KeyPurpose::SIGN,
+            digest: Digest::NONE,
+        });
+    }
+
+    let algorithm = params.iter().find_map(|kp| match kp.key_parameter_value() {
+        KeyParameterValue::Algorithm(a) => Some(*a),
+        _ => None,
+    });
+    if let Some(algorithm) = algorithm {
+        for kp in params {
+            let tag = kp.get_tag();
+            if !is_applicable(algorithm, tag) {
+                errors.push(ValidationError::InapplicableTag { algorithm, tag });
+            }
+        }
+        let gcm = params
+            .iter()
+            .any(|kp| kp.key_parameter_value() == &KeyParameterValue::BlockMode(BlockMode::GCM));
+        if algorithm == Algorithm::AES && contains_tag(params, Tag::MIN_MAC_LENGTH) && !gcm {
+            errors.push(ValidationError::MinMacLengthWithoutGcm);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Decides which value is kept by `merge_key_parameter_sets` when a tag that may occur only
+/// once has different values in the hardware and the software enforced sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// Keep the hardware enforced value.
+    HwWins,
+    /// Keep the software enforced value.
+    SwWins,
+    /// Fail with `MergeConflictError`.
+    ErrorOnConflict,
+}
+
+/// Returned by `merge_key_parameter_sets` if the policy is `MergePolicy::ErrorOnConflict`
+/// and the sets disagree.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error(
+    "Conflicting key parameters for tags {:?}.",
+    .conflicts.iter().map(|(hw, _)| hw.get_tag()).collect::<Vec<_>>()
+)]
+pub struct MergeConflictError {
+    /// Every conflicting pair as (hardware enforced, software enforced) parameter.
+    pub conflicts: Vec<(KeyParameter, KeyParameter)>,
+}
+
+/// Merges the hardware enforced characteristics `hw` and the software enforced
+/// characteristics `sw` into one set. Software enforced parameters that repeat a hardware
+/// enforced value are dropped. Repeatable tags are merged as union. If a tag that may occur
+/// only once has different values in both sets, `policy` decides the outcome.
+/// The hardware enforced parameters come first in the result, followed by the software
+/// enforced ones, each in their original order.
+pub fn merge_key_parameter_sets(
+    hw: Vec<KeyParameter>,
+    sw: Vec<KeyParameter>,
+    policy: MergePolicy,
+) -> Result<Vec<KeyParameter>> {
+    let mut conflicts = Vec::new();
+    let mut overridden_hw_tags = HashSet::new();
+    let mut merged_sw = Vec::new();
+
+    for s in sw {
+        let tag = s.get_tag();
+        let hw_same_tag: Vec<&KeyParameter> = find_all_tags(&hw, tag).collect();
+        if hw_same_tag.iter().any(|h| h.value_eq(&s)) {
+            continue;
+        }
+        if hw_same_tag.is_empty() || is_repeatable_tag(tag) {
+            merged_sw.push(s);
+            continue;
+        }
+        match policy {
+            MergePolicy::HwWins => {}
+            MergePolicy::SwWins => {
+                overridden_hw_tags.insert(tag);
+                merged_sw.push(s);
+            }
+            MergePolicy::ErrorOnConflict => {
+                conflicts.extend(hw_same_tag.into_iter().map(|h| (h.clone(), s.clone())))
+            }
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(MergeConflictError { conflicts }.into());
+    }
+    Ok(hw
+        .into_iter()
+        .filter(|h| !overridden_hw_tags.contains(&h.get_tag()))
+        .chain(merged_sw)
+        .collect())
+}
+
+/// Returns the precedence of the security level used by `merge_by_precedence`. The order is
+/// TRUSTED_ENVIRONMENT > STRONGBOX > SOFTWARE > KEYSTORE > any other security level.
+fn enforcement_precedence(level: SecurityLevel) -> u8 {
+    match level {
+        SecurityLevel::TRUSTED_ENVIRONMENT => 4,
+        SecurityLevel::STRONGBOX => 3,
+        SecurityLevel::SOFTWARE => 2,
+        SecurityLevel::KEYSTORE => 1,
+        _ => 0,
+    }
+}
+
+/// Merges two characteristic sets, e.g., the TEE enforced and the software enforced set.
+/// If a tag that may occur only once is present in both sets, the parameter whose security
+/// level has the higher precedence wins. The precedence is TRUSTED_ENVIRONMENT, then
+/// STRONGBOX, then SOFTWARE, then KEYSTORE, then any other security level. On equal
+/// precedence, the parameter from `a` wins. Repeatable tags such as PURPOSE are merged as
+/// union, and a value present in both sets is kept once with the security level of higher
+/// precedence. The result keeps the order of `a`, where a parameter of `b` that wins takes
+/// the place of the parameter it replaces, followed by the parameters of `b` whose tag or
+/// value does not occur in `a`.
+pub fn merge_by_precedence(a: Vec<KeyParameter>, b: Vec<KeyParameter>) -> Vec<KeyParameter> {
+    let mut merged: Vec<KeyParameter> = Vec::with_capacity(a.len() + b.len());
+    for kp in a.into_iter().chain(b) {
+        let existing = if is_repeatable_tag(kp.get_tag()) {
+            merged.iter().position(|m| m.value_eq(&kp))
+        } else {
+            merged.iter().position(|m| m.get_tag() == kp.get_tag())
+        };
+        match existing {
+            Some(i) => {
+                if enforcement_precedence(*kp.security_level())
+                    > enforcement_precedence(*merged[i].security_level())
+                {
+                    merged[i] = kp;
+                }
+            }
+            None => merged.push(kp),
+        }
+    }
+    merged
+}
+
+/// A list of key parameters without exact duplicates, as produced by
+/// `KeyParameterSetBuilder::build`. Two parameters are duplicates if both their values and
+/// their security levels are equal. The parameters are indexed by tag, so that the duplicate
+/// check on insertion only compares parameters with the same tag.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct KeyParameterSet {
+    params: Vec<KeyParameter>,
+    index: BTreeMap<Tag, Vec<usize>>,
+}
+
+impl KeyParameterSet {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Appends the key parameter unless the set already holds an exact duplicate.
+    /// Returns true if the parameter was inserted.
+    pub fn insert(&mut self, kp: KeyParameter) -> bool {
+        let positions = self.index.entry(kp.get_tag()).or_default();
+        if positions.iter().any(|i| self.params[*i] == kp) {
+            return false;
+        }
+        positions.push(self.params.len());
+        self.params.push(kp);
+        true
+    }
+
+    /// Sorts the parameters by tag, and parameters with the same tag by value and security
+    /// level. Sets holding the same parameters are equal after normalization, regardless of
+    /// the order in which the parameters were inserted.
+    pub fn normalized(mut self) -> Self {
+        self.params.sort_by(|a, b| a.get_tag().cmp(&b.get_tag()).then_with(|| a.cmp(b)));
+        self.index.clear();
+        for (i, kp) in self.params.iter().enumerate() {
+            self.index.entry(kp.get_tag()).or_default().push(i);
+        }
+        self
+    }
+
+    /// Returns the wrapped key parameters.
+    pub fn into_inner(self) -> Vec<KeyParameter> {
+        self.params
+    }
+
+    /// Updates the OS, vendor, and boot patch levels after a key upgrade. Existing patch level
+    /// parameters keep their security level, and absent ones are added as software enforced
+    /// parameters. Fails with ResponseCode::INVALID_ARGUMENT and leaves the set untouched if
+    /// any of the new patch levels is lower than the stored one.
+    pub fn upgrade_patch_levels(&mut self, os: i32, vendor: i32, boot: i32) -> Result<()> {
+        let new_values = [
+            KeyParameterValue::OSPatchLevel(os),
+            KeyParameterValue::VendorPatchLevel(vendor),
+            KeyParameterValue::BootPatchLevel(boot),
+        ];
+        for new_value in &new_values {
+            let new_level = new_value.integer_value();
+            if let Some(current) = find_all_tags(&self.params, new_value.get_tag())
+                .find(|p| p.key_parameter_value().integer_value() > new_level)
+            {
+                return Err(KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT)).with_context(|| {
+                    format!(
+                        "In upgrade_patch_levels: Cannot downgrade {:?} to {:?}.",
+                        current.key_parameter_value(),
+                        new_value
+                    )
+                });
+            }
+        }
+        // Rebuild the set, because updated parameters may become duplicates of each other.
+        let mut params = std::mem::take(self).into_inner();
+        for new_value in new_values {
+            let mut found = false;
+            for p in params.iter_mut().filter(|p| p.get_tag() == new_value.get_tag()) {
+                *p = KeyParameter::new(new_value.clone(), *p.security_level());
+                found = true;
+            }
+            if !found {
+                params.push(KeyParameter::new(new_value, SecurityLevel::SOFTWARE));
+            }
+        }
+        *self = params.into();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for KeyParameterSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.params.serialize(serializer)
+    }
+}
+
+impl From<Vec<KeyParameter>> for KeyParameterSet {
+    fn from(params: Vec<KeyParameter>) -> Self {
+        let mut set = Self::new();
+        for kp in params {
+            set.insert(kp);
+        }
+        set
+    }
+}
+
+impl Deref for KeyParameterSet {
+    type Target = [KeyParameter];
+
+    fn deref(&self) -> &Self::Target {
+        &self.params
+    }
+}
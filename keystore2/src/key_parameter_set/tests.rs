@@ -0,0 +1,630 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Key parameter set builder tests.
+
+use super::*;
+use crate::key_parameter::EcCurve;
+
+fn kp(value: KeyParameterValue) -> KeyParameter {
+    KeyParameter::new(value, SecurityLevel::TRUSTED_ENVIRONMENT)
+}
+
+#[test]
+fn build_valid_ec_key_parameters() {
+    let params = KeyParameterSetBuilder::new()
+        .add(kp(KeyParameterValue::Algorithm(Algorithm::EC)))
+        .add(kp(KeyParameterValue::EcCurve(EcCurve::P_256)))
+        .add(kp(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)))
+        .add(kp(KeyParameterValue::NoAuthRequired))
+        .build()
+        .expect("Valid parameters were rejected.");
+    assert_eq!(params.len(), 4);
+}
+
+#[test]
+fn build_rejects_missing_algorithm() {
+    assert_eq!(
+        KeyParameterSetBuilder::new().add(kp(KeyParameterValue::KeySize(256))).build(),
+        Err(vec![ValidationError::MissingAlgorithm])
+    );
+    assert_eq!(ValidationError::MissingAlgorithm.to_string(), "Missing tag ALGORITHM.");
+    assert_eq!(ValidationError::MissingAlgorithm.tags(), vec![Tag::ALGORITHM]);
+}
+
+#[test]
+fn build_rejects_curve_for_rsa_key() {
+    assert_eq!(
+        KeyParameterSetBuilder::new()
+            .add(kp(KeyParameterValue::Algorithm(Algorithm::RSA)))
+            .add(kp(KeyParameterValue::KeySize(2048)))
+            .add(kp(KeyParameterValue::RSAPublicExponent(65537)))
+            .add(kp(KeyParameterValue::EcCurve(EcCurve::P_256)))
+            .build(),
+        Err(vec![ValidationError::InapplicableTag {
+            algorithm: Algorithm::RSA,
+            tag: Tag::EC_CURVE
+        }])
+    );
+}
+
+#[test]
+fn build_rejects_incompatible_purpose_and_missing_tags() {
+    assert_eq!(
+        KeyParameterSetBuilder::new()
+            .add(kp(KeyParameterValue::Algorithm(Algorithm::AES)))
+            .add(kp(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)))
+            .build(),
+        Err(vec![
+            ValidationError::IncompatiblePurpose {
+                algorithm: Algorithm::AES,
+                purpose: KeyPurpose::SIGN
+            },
+            ValidationError::MissingTag { tag: Tag::KEY_SIZE, algorithm: Algorithm::AES },
+        ])
+    );
+}
+
+#[test]
+fn build_rejects_user_secure_id_without_auth() {
+    assert_eq!(
+        KeyParameterSetBuilder::new()
+            .add(kp(KeyParameterValue::Algorithm(Algorithm::AES)))
+            .add(kp(KeyParameterValue::KeySize(256)))
+            .add(kp(KeyParameterValue::NoAuthRequired))
+            .add(kp(KeyParameterValue::UserSecureID(42)))
+            .build(),
+        Err(vec![ValidationError::MutuallyExclusive(Tag::NO_AUTH_REQUIRED, Tag::USER_SECURE_ID)])
+    );
+}
+
+#[test]
+fn build_rejects_digest_none_for_signing() {
+    let ec = |digest| {
+        KeyParameterSetBuilder::new()
+            .add(kp(KeyParameterValue::Algorithm(Algorithm::EC)))
+            .add(kp(KeyParameterValue::EcCurve(EcCurve::P_256)))
+            .add(kp(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)))
+            .add(kp(KeyParameterValue::Digest(digest)))
+            .build()
+    };
+    assert!(ec(Digest::SHA_2_256).is_ok());
+    let errors = ec(Digest::NONE).unwrap_err();
+    assert_eq!(
+        errors,
+        vec![ValidationError::IncompatibleDigest {
+            purpose: KeyPurpose::SIGN,
+            digest: Digest::NONE
+        }]
+    );
+    assert_eq!(errors[0].tags(), vec![Tag::PURPOSE, Tag::DIGEST]);
+}
+
+#[test]
+fn build_rejects_mutually_exclusive_boolean_tags() {
+    let errors = KeyParameterSetBuilder::new()
+        .add(kp(KeyParameterValue::Algorithm(Algorithm::AES)))
+        .add(kp(KeyParameterValue::KeySize(256)))
+        .add(kp(KeyParameterValue::NoAuthRequired))
+        .add(kp(KeyParameterValue::TrustedUserPresenceRequired))
+        .add(kp(KeyParameterValue::EarlyBootOnly))
+        .add(kp(KeyParameterValue::UnlockedDeviceRequired))
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            ValidationError::MutuallyExclusive(
+                Tag::NO_AUTH_REQUIRED,
+                Tag::TRUSTED_USER_PRESENCE_REQUIRED
+            ),
+            ValidationError::MutuallyExclusive(Tag::EARLY_BOOT_ONLY, Tag::UNLOCKED_DEVICE_REQUIRED),
+        ]
+    );
+}
+
+#[test]
+fn build_rejects_min_mac_length_without_gcm() {
+    let errors = KeyParameterSetBuilder::new()
+        .add(kp(KeyParameterValue::Algorithm(Algorithm::AES)))
+        .add(kp(KeyParameterValue::KeySize(128)))
+        .add(kp(KeyParameterValue::BlockMode(BlockMode::CBC)))
+        .add(kp(KeyParameterValue::MinMacLength(128)))
+        .build()
+        .unwrap_err();
+    assert_eq!(errors, vec![ValidationError::MinMacLengthWithoutGcm]);
+    assert_eq!(errors[0].tags(), vec![Tag::MIN_MAC_LENGTH, Tag::BLOCK_MODE]);
+}
+
+#[test]
+fn build_rejects_duplicate_singleton_tags() {
+    assert_eq!(
+        KeyParameterSetBuilder::new()
+            .add(kp(KeyParameterValue::Algorithm(Algorithm::AES)))
+            .add(kp(KeyParameterValue::KeySize(128)))
+            .add(kp(KeyParameterValue::KeySize(256)))
+            .add(kp(KeyParameterValue::KeySize(256)))
+            .add(kp(KeyParameterValue::BlockMode(BlockMode::GCM)))
+            .add(kp(KeyParameterValue::BlockMode(BlockMode::CBC)))
+            .add(kp(KeyParameterValue::KeyPurpose(KeyPurpose::ENCRYPT)))
+            .add(kp(KeyParameterValue::KeyPurpose(KeyPurpose::DECRYPT)))
+            .build(),
+        Err(vec![ValidationError::DuplicateTag {
+            tag: Tag::KEY_SIZE,
+            security_level: SecurityLevel::TRUSTED_ENVIRONMENT
+        }])
+    );
+}
+
+#[test]
+fn build_rejects_out_of_range_values() {
+    assert_eq!(
+        KeyParameterSetBuilder::new()
+            .add(kp(KeyParameterValue::Algorithm(Algorithm::RSA)))
+            .add(kp(KeyParameterValue::KeySize(1000)))
+            .add(kp(KeyParameterValue::RSAPublicExponent(65537)))
+            .add(kp(KeyParameterValue::MaxUsesPerBoot(0)))
+            .add(kp(KeyParameterValue::ActiveDateTime(-1)))
+            .build(),
+        Err(vec![
+            ValidationError::OutOfRange {
+                tag: Tag::KEY_SIZE,
+                value: Primitive::I32(1000),
+                allowed: "1024, 2048, 3072, or 4096 for RSA keys",
+            },
+            ValidationError::OutOfRange {
+                tag: Tag::MAX_USES_PER_BOOT,
+                value: Primitive::I32(0),
+                allowed: "a positive value",
+            },
+            ValidationError::OutOfRange {
+                tag: Tag::ACTIVE_DATETIME,
+                value: Primitive::I64(-1),
+                allowed: "a non-negative date time",
+            },
+        ])
+    );
+}
+
+#[test]
+fn build_bounds_min_mac_length_by_digest() {
+    let hmac = |digest, length| {
+        KeyParameterSetBuilder::new()
+            .add(kp(KeyParameterValue::Algorithm(Algorithm::HMAC)))
+            .add(kp(KeyParameterValue::KeySize(256)))
+            .add(kp(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)))
+            .add(kp(KeyParameterValue::Digest(digest)))
+            .add(kp(KeyParameterValue::MinMacLength(length)))
+            .build()
+    };
+    assert!(hmac(Digest::SHA_2_256, 256).is_ok());
+    assert!(hmac(Digest::SHA_2_512, 512).is_ok());
+    assert_eq!(
+        hmac(Digest::SHA_2_256, 264),
+        Err(vec![ValidationError::OutOfRange {
+            tag: Tag::MIN_MAC_LENGTH,
+            value: Primitive::I32(264),
+            allowed: "at most the output size of the digest of HMAC keys",
+        }])
+    );
+
+    let aes = |length| {
+        KeyParameterSetBuilder::new()
+            .add(kp(KeyParameterValue::Algorithm(Algorithm::AES)))
+            .add(kp(KeyParameterValue::KeySize(128)))
+            .add(kp(KeyParameterValue::KeyPurpose(KeyPurpose::ENCRYPT)))
+            .add(kp(KeyParameterValue::BlockMode(BlockMode::GCM)))
+            .add(kp(KeyParameterValue::MinMacLength(length)))
+            .build()
+    };
+    assert!(aes(128).is_ok());
+    assert_eq!(
+        aes(136),
+        Err(vec![ValidationError::OutOfRange {
+            tag: Tag::MIN_MAC_LENGTH,
+            value: Primitive::I32(136),
+            allowed: "at most 128 for AES keys",
+        }])
+    );
+}
+
+#[test]
+fn build_accepts_rsa_key_sizes() {
+    for size in [1024, 2048, 3072, 4096] {
+        KeyParameterSetBuilder::new()
+            .add(kp(KeyParameterValue::Algorithm(Algorithm::RSA)))
+            .add(kp(KeyParameterValue::KeySize(size)))
+            .add(kp(KeyParameterValue::RSAPublicExponent(65537)))
+            .build()
+            .unwrap_or_else(|e| panic!("Key size {} was rejected: {:?}", size, e));
+    }
+    for size in [512, 1023, 1025, 4097, 8192] {
+        assert!(KeyParameterSetBuilder::new()
+            .add(kp(KeyParameterValue::Algorithm(Algorithm::RSA)))
+            .add(kp(KeyParameterValue::KeySize(size)))
+            .add(kp(KeyParameterValue::RSAPublicExponent(65537)))
+            .build()
+            .is_err());
+    }
+}
+
+fn hw(value: KeyParameterValue) -> KeyParameter {
+    KeyParameter::new(value, SecurityLevel::TRUSTED_ENVIRONMENT)
+}
+
+fn sw(value: KeyParameterValue) -> KeyParameter {
+    KeyParameter::new(value, SecurityLevel::KEYSTORE)
+}
+
+fn overlapping_sets() -> (Vec<KeyParameter>, Vec<KeyParameter>) {
+    (
+        vec![
+            hw(KeyParameterValue::Algorithm(Algorithm::EC)),
+            hw(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+            hw(KeyParameterValue::OSPatchLevel(202301)),
+        ],
+        vec![
+            sw(KeyParameterValue::Algorithm(Algorithm::EC)),
+            sw(KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY)),
+            sw(KeyParameterValue::OSPatchLevel(202401)),
+            sw(KeyParameterValue::CreationDateTime(1704067200000)),
+        ],
+    )
+}
+
+#[test]
+fn merge_without_conflicts() {
+    let hw_params = vec![hw(KeyParameterValue::Algorithm(Algorithm::EC))];
+    let sw_params = vec![
+        sw(KeyParameterValue::Algorithm(Algorithm::EC)),
+        sw(KeyParameterValue::CreationDateTime(1)),
+    ];
+    for policy in [MergePolicy::HwWins, MergePolicy::SwWins, MergePolicy::ErrorOnConflict] {
+        assert_eq!(
+            merge_key_parameter_sets(hw_params.clone(), sw_params.clone(), policy).unwrap(),
+            vec![
+                hw(KeyParameterValue::Algorithm(Algorithm::EC)),
+                sw(KeyParameterValue::CreationDateTime(1)),
+            ]
+        );
+    }
+}
+
+#[test]
+fn merge_hw_wins() {
+    let (hw_params, sw_params) = overlapping_sets();
+    assert_eq!(
+        merge_key_parameter_sets(hw_params, sw_params, MergePolicy::HwWins).unwrap(),
+        vec![
+            hw(KeyParameterValue::Algorithm(Algorithm::EC)),
+            hw(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+            hw(KeyParameterValue::OSPatchLevel(202301)),
+            sw(KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY)),
+            sw(KeyParameterValue::CreationDateTime(1704067200000)),
+        ]
+    );
+}
+
+#[test]
+fn merge_sw_wins() {
+    let (hw_params, sw_params) = overlapping_sets();
+    assert_eq!(
+        merge_key_parameter_sets(hw_params, sw_params, MergePolicy::SwWins).unwrap(),
+        vec![
+            hw(KeyParameterValue::Algorithm(Algorithm::EC)),
+            hw(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+            sw(KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY)),
+            sw(KeyParameterValue::OSPatchLevel(202401)),
+            sw(KeyParameterValue::CreationDateTime(1704067200000)),
+        ]
+    );
+}
+
+#[test]
+fn merge_error_on_conflict() {
+    let (mut hw_params, mut sw_params) = overlapping_sets();
+    hw_params.push(hw(KeyParameterValue::KeySize(256)));
+    sw_params.push(sw(KeyParameterValue::KeySize(384)));
+    let err =
+        merge_key_parameter_sets(hw_params, sw_params, MergePolicy::ErrorOnConflict).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<MergeConflictError>(),
+        Some(&MergeConflictError {
+            conflicts: vec![
+                (
+                    hw(KeyParameterValue::OSPatchLevel(202301)),
+                    sw(KeyParameterValue::OSPatchLevel(202401))
+                ),
+                (hw(KeyParameterValue::KeySize(256)), sw(KeyParameterValue::KeySize(384))),
+            ]
+        })
+    );
+    assert_eq!(err.to_string(), "Conflicting key parameters for tags [OS_PATCHLEVEL, KEY_SIZE].");
+}
+
+fn strongbox(value: KeyParameterValue) -> KeyParameter {
+    KeyParameter::new(value, SecurityLevel::STRONGBOX)
+}
+
+#[test]
+fn merge_by_precedence_single_value_conflict() {
+    let merged = merge_by_precedence(
+        vec![
+            sw(KeyParameterValue::KeySize(128)),
+            sw(KeyParameterValue::CreationDateTime(1704067200000)),
+        ],
+        vec![
+            hw(KeyParameterValue::KeySize(256)),
+            strongbox(KeyParameterValue::CreationDateTime(1)),
+        ],
+    );
+    assert_eq!(
+        merged,
+        vec![
+            hw(KeyParameterValue::KeySize(256)),
+            strongbox(KeyParameterValue::CreationDateTime(1)),
+        ]
+    );
+
+    // TRUSTED_ENVIRONMENT takes precedence over STRONGBOX.
+    assert_eq!(
+        merge_by_precedence(
+            vec![strongbox(KeyParameterValue::OSPatchLevel(202301))],
+            vec![hw(KeyParameterValue::OSPatchLevel(202401))],
+        ),
+        vec![hw(KeyParameterValue::OSPatchLevel(202401))]
+    );
+
+    // A winning parameter of the second set takes the place of the one it replaces.
+    assert_eq!(
+        merge_by_precedence(
+            vec![sw(KeyParameterValue::KeySize(128)), sw(KeyParameterValue::NoAuthRequired)],
+            vec![
+                hw(KeyParameterValue::EcCurve(EcCurve::P_256)),
+                hw(KeyParameterValue::KeySize(256))
+            ],
+        ),
+        vec![
+            hw(KeyParameterValue::KeySize(256)),
+            sw(KeyParameterValue::NoAuthRequired),
+            hw(KeyParameterValue::EcCurve(EcCurve::P_256)),
+        ]
+    );
+
+    // On equal precedence, the parameter from the first set wins.
+    assert_eq!(
+        merge_by_precedence(
+            vec![hw(KeyParameterValue::KeySize(256))],
+            vec![hw(KeyParameterValue::KeySize(384))],
+        ),
+        vec![hw(KeyParameterValue::KeySize(256))]
+    );
+}
+
+#[test]
+fn merge_by_precedence_repeatable_tags() {
+    let merged = merge_by_precedence(
+        vec![
+            hw(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+            hw(KeyParameterValue::Algorithm(Algorithm::EC)),
+        ],
+        vec![
+            sw(KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY)),
+            sw(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+            sw(KeyParameterValue::Algorithm(Algorithm::EC)),
+        ],
+    );
+    assert_eq!(
+        merged,
+        vec![
+            hw(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+            hw(KeyParameterValue::Algorithm(Algorithm::EC)),
+            sw(KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY)),
+        ]
+    );
+
+    // A repeated value takes the security level of higher precedence.
+    assert_eq!(
+        merge_by_precedence(
+            vec![sw(KeyParameterValue::Digest(Digest::SHA_2_256))],
+            vec![hw(KeyParameterValue::Digest(Digest::SHA_2_256))],
+        ),
+        vec![hw(KeyParameterValue::Digest(Digest::SHA_2_256))]
+    );
+}
+
+#[test]
+fn key_parameter_set_rejects_duplicates() {
+    let mut set = KeyParameterSet::new();
+    assert!(set.insert(hw(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN))));
+    assert!(!set.insert(hw(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN))));
+    assert!(set.insert(sw(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN))));
+    assert!(set.insert(hw(KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY))));
+    assert_eq!(set.len(), 3);
+
+    let set = KeyParameterSet::from(vec![
+        hw(KeyParameterValue::KeySize(256)),
+        hw(KeyParameterValue::KeySize(256)),
+    ]);
+    assert_eq!(&*set, &[hw(KeyParameterValue::KeySize(256))]);
+
+    let mut set = set.normalized();
+    assert!(!set.insert(hw(KeyParameterValue::KeySize(256))));
+    assert!(set.insert(sw(KeyParameterValue::KeySize(256))));
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn key_parameter_set_normalized_is_order_independent() {
+    let params = vec![
+        sw(KeyParameterValue::CreationDateTime(1704067200000)),
+        hw(KeyParameterValue::KeyPurpose(KeyPurpose::VERIFY)),
+        hw(KeyParameterValue::Algorithm(Algorithm::EC)),
+        hw(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+        hw(KeyParameterValue::EcCurve(EcCurve::P_256)),
+        sw(KeyParameterValue::KeyPurpose(KeyPurpose::SIGN)),
+    ];
+    let mut reversed = params.clone();
+    reversed.reverse();
+    let mut rotated = params.clone();
+    rotated.rotate_left(2);
+
+    let normalized = KeyParameterSet::from(params).normalized();
+    assert_eq!(normalized, KeyParameterSet::from(reversed).normalized());
+    assert_eq!(normalized, KeyParameterSet::from(rotated.clone()).normalized());
+
+    let tags: Vec<Tag> = normalized.iter().map(|kp| kp.get_tag()).collect();
+    let mut sorted_tags = tags.clone();
+    sorted_tags.sort();
+    assert_eq!(tags, sorted_tags);
+
+    #[cfg(feature = "serde")]
+    {
+        let serialized = serde_cbor::to_vec(&normalized).expect("Failed to serialize.");
+        let serialized_rotated = serde_cbor::to_vec(&KeyParameterSet::from(rotated).normalized())
+            .expect("Failed to serialize.");
+        assert_eq!(serialized, serialized_rotated);
+    }
+}
+
+#[test]
+fn validate_combination_accepts_consistent_parameters() {
+    assert_eq!(
+        validate_combination(&[
+            kp(KeyParameterValue::Algorithm(Algorithm::AES)),
+            kp(KeyParameterValue::BlockMode(BlockMode::GCM)),
+            kp(KeyParameterValue::MinMacLength(128)),
+            kp(KeyParameterValue::UserSecureID(42)),
+        ]),
+        Ok(())
+    );
+    // Missing tags are not the concern of validate_combination.
+    assert_eq!(validate_combination(&[kp(KeyParameterValue::KeySize(256))]), Ok(()));
+}
+
+#[test]
+fn validate_combination_reports_all_violations() {
+    let errors = validate_combination(&[
+        kp(KeyParameterValue::Algorithm(Algorithm::AES)),
+        kp(KeyParameterValue::BlockMode(BlockMode::CBC)),
+        kp(KeyParameterValue::EcCurve(EcCurve::P_256)),
+        kp(KeyParameterValue::MinMacLength(128)),
+        kp(KeyParameterValue::NoAuthRequired),
+        kp(KeyParameterValue::UserSecureID(42)),
+    ])
+    .unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            ValidationError::MutuallyExclusive(Tag::NO_AUTH_REQUIRED, Tag::USER_SECURE_ID),
+            ValidationError::InapplicableTag { algorithm: Algorithm::AES, tag: Tag::EC_CURVE },
+            ValidationError::MinMacLengthWithoutGcm,
+        ]
+    );
+    assert_eq!(errors[0].tags(), vec![Tag::NO_AUTH_REQUIRED, Tag::USER_SECURE_ID]);
+    assert_eq!(errors[1].tags(), vec![Tag::ALGORITHM, Tag::EC_CURVE]);
+    assert_eq!(errors[2].tags(), vec![Tag::MIN_MAC_LENGTH, Tag::BLOCK_MODE]);
+}
+
+#[test]
+fn validate_combination_rejects_min_mac_length_for_ec() {
+    assert_eq!(
+        validate_combination(&[
+            kp(KeyParameterValue::Algorithm(Algorithm::EC)),
+            kp(KeyParameterValue::MinMacLength(128)),
+        ]),
+        Err(vec![ValidationError::InapplicableTag {
+            algorithm: Algorithm::EC,
+            tag: Tag::MIN_MAC_LENGTH
+        }])
+    );
+}
+
+#[test]
+fn key_parameter_set_upgrade_patch_levels() {
+    let tee = SecurityLevel::TRUSTED_ENVIRONMENT;
+    let mut set = KeyParameterSet::from(vec![
+        KeyParameter::new(KeyParameterValue::Algorithm(Algorithm::EC), tee),
+        KeyParameter::new(KeyParameterValue::OSPatchLevel(202107), tee),
+        KeyParameter::new(KeyParameterValue::VendorPatchLevel(20210705), tee),
+    ]);
+    set.upgrade_patch_levels(202201, 20220105, 20220110).unwrap();
+    assert_eq!(
+        set.into_inner(),
+        vec![
+            KeyParameter::new(KeyParameterValue::Algorithm(Algorithm::EC), tee),
+            KeyParameter::new(KeyParameterValue::OSPatchLevel(202201), tee),
+            KeyParameter::new(KeyParameterValue::VendorPatchLevel(20220105), tee),
+            KeyParameter::new(KeyParameterValue::BootPatchLevel(20220110), SecurityLevel::SOFTWARE),
+        ]
+    );
+
+    // Patch levels stored at several security levels are all updated, and parameters that
+    // become identical are merged.
+    let mut set = KeyParameterSet::from(vec![
+        KeyParameter::new(KeyParameterValue::OSPatchLevel(202107), tee),
+        KeyParameter::new(KeyParameterValue::OSPatchLevel(202201), SecurityLevel::KEYSTORE),
+        KeyParameter::new(KeyParameterValue::OSPatchLevel(202110), tee),
+    ]);
+    set.upgrade_patch_levels(202201, 20220105, 20220110).unwrap();
+    assert_eq!(
+        set.into_inner(),
+        vec![
+            KeyParameter::new(KeyParameterValue::OSPatchLevel(202201), tee),
+            KeyParameter::new(KeyParameterValue::OSPatchLevel(202201), SecurityLevel::KEYSTORE),
+            KeyParameter::new(
+                KeyParameterValue::VendorPatchLevel(20220105),
+                SecurityLevel::SOFTWARE
+            ),
+            KeyParameter::new(KeyParameterValue::BootPatchLevel(20220110), SecurityLevel::SOFTWARE),
+        ]
+    );
+}
+
+#[test]
+fn key_parameter_set_upgrade_patch_levels_rejects_downgrade() {
+    let tee = SecurityLevel::TRUSTED_ENVIRONMENT;
+    let mut set = KeyParameterSet::from(vec![
+        KeyParameter::new(KeyParameterValue::OSPatchLevel(202201), tee),
+        KeyParameter::new(KeyParameterValue::VendorPatchLevel(20220105), tee),
+    ]);
+    // Upgrading to the current patch levels is not a downgrade.
+    set.upgrade_patch_levels(202201, 20220105, 20220110).unwrap();
+
+    let before = set.clone();
+    let err = set.upgrade_patch_levels(202301, 20220101, 20230110).unwrap_err();
+    assert_eq!(
+        err.root_cause().downcast_ref::<KeystoreError>(),
+        Some(&KeystoreError::Rc(ResponseCode::INVALID_ARGUMENT))
+    );
+    assert_eq!(set, before);
+}
+
+proptest::proptest! {
+    #[test]
+    fn generated_characteristics_are_valid(
+        params in crate::key_parameter::testing::any_valid_characteristics()
+    ) {
+        let mut builder = KeyParameterSetBuilder::new();
+        for kp in &params {
+            builder.add(kp.clone());
+        }
+        proptest::prop_assert_eq!(
+            builder.build().map(KeyParameterSet::into_inner),
+            Ok(params.clone())
+        );
+        proptest::prop_assert_eq!(validate_combination(&params), Ok(()));
+        proptest::prop_assert!(crate::key_parameter::check_cardinality(&params).is_ok());
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module checks the key characteristics returned by KeyMint after key generation
+//! against the key parameters of the generation request, so that Keystore notices if KeyMint
+//! silently dropped a restriction or attached one that nobody asked for.
+
+use crate::key_parameter::{KeyParameter, KeyParameterValue, Tag};
+use anyhow::Result;
+
+#[cfg(test)]
+mod tests;
+
+/// Tags that KeyMint adds to the characteristics of a generated key by itself. EC_CURVE and
+/// KEY_SIZE are included, because KeyMint derives each of them from the other for EC keys.
+const KM_GENERATED_TAGS: &[Tag] = &[
+    Tag::ORIGIN,
+    Tag::OS_VERSION,
+    Tag::OS_PATCHLEVEL,
+    Tag::VENDOR_PATCHLEVEL,
+    Tag::BOOT_PATCHLEVEL,
+    Tag::CREATION_DATETIME,
+    Tag::EC_CURVE,
+    Tag::KEY_SIZE,
+];
+
+/// Returned by `validate_key_parameters_against_km_response` if the key characteristics do
+/// not reflect the generation request.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error(
+    "Key characteristics do not match the request: missing {missing:?}, rejected {rejected:?}."
+)]
+pub struct KeyCharacteristicsMismatch {
+    /// Tags of request parameters whose value is absent from the characteristics.
+    pub missing: Vec<Tag>,
+    /// Tags of characteristics that were neither requested nor generated by KeyMint.
+    pub rejected: Vec<Tag>,
+}
+
+/// Checks that every key parameter of the generation request `request` is reflected by the
+/// combined hardware and software enforced characteristics returned by KeyMint, regardless of
+/// the security level. Request parameters that are not characteristics, i.e., operation,
+/// attestation, and certificate parameters for which `KeyParameterValue::is_characteristic`
+/// returns false, are not checked. Conversely, every characteristic must either have
+/// a tag present in the request or be one that KeyMint generates itself, such as ORIGIN or
+/// the patch levels. Fails with `KeyCharacteristicsMismatch` listing the tags of all
+/// violations in order of first occurrence.
+pub fn validate_key_parameters_against_km_response(
+    request: &[KeyParameter],
+    response_hw: &[KeyParameter],
+    response_sw: &[KeyParameter],
+) -> Result<()> {
+    let response: Vec<&KeyParameter> = response_hw.iter().chain(response_sw).collect();
+
+    let mut missing = Vec::new();
+    for kp in request {
+        let tag = kp.get_tag();
+        if !KeyParameterValue::is_characteristic(tag) {
+            continue;
+        }
+        if !response.iter().any(|r| r.value_eq(kp)) && !missing.contains(&tag) {
+            missing.push(tag);
+        }
+    }
+
+    let mut rejected = Vec::new();
+    for kp in response {
+        let tag = kp.get_tag();
+        if KM_GENERATED_TAGS.contains(&tag) || request.iter().any(|r| r.get_tag() == tag) {
+            continue;
+        }
+        if !rejected.contains(&tag) {
+            rejected.push(tag);
+        }
+    }
+
+    if missing.is_empty() && rejected.is_empty() {
+        Ok(())
+    } else {
+        Err(KeyCharacteristicsMismatch { missing, rejected }.into())
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright 2026, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Key validation tests.
+
+use super::*;
+use crate::key_parameter::{
+    Algorithm, BlockMode, KeyOrigin, KeyParameterBuilder, KeyParameterValue, KeyPurpose,
+    PaddingMode, SecurityLevel,
+};
+
+/// The parameters of a simple AES key generation request.
+fn aes_request() -> Vec<KeyParameter> {
+    KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+        .algorithm(Algorithm::AES)
+        .key_size(256)
+        .purpose(KeyPurpose::ENCRYPT)
+        .purpose(KeyPurpose::DECRYPT)
+        .block_mode(BlockMode::GCM)
+        .padding(PaddingMode::NONE)
+        .value(KeyParameterValue::MinMacLength(128))
+        .value(KeyParameterValue::NoAuthRequired)
+        .value(KeyParameterValue::ApplicationID(vec![1, 2, 3]))
+        .build()
+}
+
+/// The hardware enforced characteristics KeyMint returns for `aes_request`. KeyMint adds
+/// the origin and the patch levels, and it does not return the application id.
+fn aes_response_hw() -> Vec<KeyParameter> {
+    let mut hw: Vec<KeyParameter> =
+        aes_request().into_iter().filter(|kp| kp.get_tag() != Tag::APPLICATION_ID).collect();
+    hw.extend(
+        KeyParameterBuilder::new(SecurityLevel::TRUSTED_ENVIRONMENT)
+            .value(KeyParameterValue::KeyOrigin(KeyOrigin::GENERATED))
+            .value(KeyParameterValue::OSVersion(150000))
+            .value(KeyParameterValue::OSPatchLevel(202601))
+            .build(),
+    );
+    hw
+}
+
+fn mismatch(result: Result<()>) -> KeyCharacteristicsMismatch {
+    result
+        .unwrap_err()
+        .downcast::<KeyCharacteristicsMismatch>()
+        .expect("Expected KeyCharacteristicsMismatch.")
+}
+
+#[test]
+fn matching_response_passes() {
+    let sw = vec![KeyParameter::new(
+        KeyParameterValue::CreationDateTime(1767225600000),
+        SecurityLevel::KEYSTORE,
+    )];
+    validate_key_parameters_against_km_response(&aes_request(), &aes_response_hw(), &sw)
+        .expect("Validation should pass.");
+}
+
+#[test]
+fn security_level_is_ignored() {
+    let (hw, sw): (Vec<KeyParameter>, Vec<KeyParameter>) =
+        aes_response_hw().into_iter().partition(|kp| kp.get_tag() == Tag::ALGORITHM);
+    let sw: Vec<KeyParameter> = sw
+        .into_iter()
+        .map(|kp| KeyParameter::new(kp.key_parameter_value().clone(), SecurityLevel::SOFTWARE))
+        .collect();
+    validate_key_parameters_against_km_response(&aes_request(), &hw, &sw)
+        .expect("Validation should pass.");
+}
+
+#[test]
+fn missing_key_size_fails() {
+    let hw: Vec<KeyParameter> =
+        aes_response_hw().into_iter().filter(|kp| kp.get_tag() != Tag::KEY_SIZE).collect();
+    assert_eq!(
+        mismatch(validate_key_parameters_against_km_response(&aes_request(), &hw, &[])),
+        KeyCharacteristicsMismatch { missing: vec![Tag::KEY_SIZE], rejected: vec![] }
+    );
+}
+
+#[test]
+fn missing_repeated_value_and_unrequested_tags_fail() {
+    let mut hw: Vec<KeyParameter> = aes_response_hw()
+        .into_iter()
+        .filter(|kp| {
+            kp.key_parameter_value() != &KeyParameterValue::KeyPurpose(KeyPurpose::DECRYPT)
+        })
+        .collect();
+    hw.push(KeyParameter::new(KeyParameterValue::CallerNonce, SecurityLevel::TRUSTED_ENVIRONMENT));
+    let sw = vec![KeyParameter::new(KeyParameterValue::UserID(10), SecurityLevel::KEYSTORE)];
+    assert_eq!(
+        mismatch(validate_key_parameters_against_km_response(&aes_request(), &hw, &sw)),
+        KeyCharacteristicsMismatch {
+            missing: vec![Tag::PURPOSE],
+            rejected: vec![Tag::CALLER_NONCE, Tag::USER_ID]
+        }
+    );
+}
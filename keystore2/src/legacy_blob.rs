@@ -17,7 +17,7 @@
 use crate::ks_err;
 use crate::{
     error::{Error as KsError, ResponseCode},
-    key_parameter::{KeyParameter, KeyParameterValue},
+    key_parameter::{KeyParameter, KeyParameterError, KeyParameterValue},
     utils::uid_to_android_user,
     utils::AesGcm,
 };
@@ -544,6 +544,9 @@ impl LegacyBlobLoader {
     /// | 32 bit blob_length     |    Length of the indirect payload in bytes.
     /// | 32 bit indirect_offset |    Offset from the beginning of the indirect section.
     /// +------------------------+
+    ///
+    /// Parameters that cannot be represented are skipped. A blob that exceeds
+    /// `KeyParameterValue::max_blob_size` fails the whole list.
     pub fn read_key_parameters(stream: &mut &[u8]) -> Result<Vec<KeyParameterValue>> {
         let indirect_size = read_ne_u32(stream).context(ks_err!("While reading indirect size."))?;
 
@@ -571,14 +574,14 @@ impl LegacyBlobLoader {
             let tag = Tag(read_ne_i32(&mut element_stream).context(ks_err!())?);
             let param = match Self::tag_type(tag) {
                 TagType::ENUM | TagType::ENUM_REP | TagType::UINT | TagType::UINT_REP => {
-                    KeyParameterValue::new_from_tag_primitive_pair(
+                    KeyParameterValue::new_from_tag_primitive_pair_without_range_check(
                         tag,
                         read_ne_i32(&mut element_stream).context("While reading integer.")?,
                     )
                     .context("Trying to construct integer/enum KeyParameterValue.")
                 }
                 TagType::ULONG | TagType::ULONG_REP | TagType::DATE => {
-                    KeyParameterValue::new_from_tag_primitive_pair(
+                    KeyParameterValue::new_from_tag_primitive_pair_without_range_check(
                         tag,
                         read_ne_i64(&mut element_stream).context("While reading long integer.")?,
                     )
@@ -586,7 +589,7 @@ impl LegacyBlobLoader {
                 }
                 TagType::BOOL => {
                     if read_bool(&mut element_stream).context("While reading long integer.")? {
-                        KeyParameterValue::new_from_tag_primitive_pair(tag, 1)
+                        KeyParameterValue::new_from_tag_primitive_pair_without_range_check(tag, 1)
                             .context("Trying to construct boolean KeyParameterValue.")
                     } else {
                         Err(anyhow::anyhow!("Invalid."))
@@ -599,7 +602,7 @@ impl LegacyBlobLoader {
                     let indirect_offset = read_ne_u32(&mut element_stream)
                         .context("While reading indirect offset.")?
                         as usize;
-                    KeyParameterValue::new_from_tag_primitive_pair(
+                    KeyParameterValue::new_from_tag_primitive_pair_without_range_check(
                         tag,
                         indirect_buffer
                             .get(indirect_offset..indirect_offset + blob_size)
@@ -614,8 +617,21 @@ impl LegacyBlobLoader {
                         .context(ks_err!("Encountered bogus tag type."));
                 }
             };
-            if let Ok(p) = param {
-                params.push(p);
+            match param {
+                Ok(p) => params.push(p),
+                // An oversized blob is not one of the malformed parameters that older versions
+                // of Keystore skipped, so it fails the whole key instead of dropping the
+                // parameter silently.
+                Err(e)
+                    if matches!(
+                        e.downcast_ref::<KeyParameterError>(),
+                        Some(KeyParameterError::BlobTooLarge(_))
+                    ) =>
+                {
+                    log::error!("Legacy key parameter exceeds the maximum size. {:?}", e);
+                    return Err(e).context(ks_err!("While reading blob parameter."));
+                }
+                Err(_) => {}
             }
         }
 
@@ -674,3 +674,33 @@ fn test_move_keystore_entry() {
     // Check that some_file no longer exists.
     assert!(!temp_dir.build().push("user_0").push(SOME_FILENAME).exists());
 }
+
+/// Encodes a key parameter list holding a single APPLICATION_ID blob of the given size in the
+/// format read by `LegacyBlobLoader::read_key_parameters`.
+fn encode_application_id_list(size: usize) -> Vec<u8> {
+    let mut list = Vec::new();
+    list.extend_from_slice(&(size as u32).to_ne_bytes());
+    list.extend_from_slice(&vec![0xaa; size]);
+    list.extend_from_slice(&1u32.to_ne_bytes());
+    list.extend_from_slice(&12u32.to_ne_bytes());
+    list.extend_from_slice(&Tag::APPLICATION_ID.0.to_ne_bytes());
+    list.extend_from_slice(&(size as u32).to_ne_bytes());
+    list.extend_from_slice(&0u32.to_ne_bytes());
+    list
+}
+
+#[test]
+fn test_read_key_parameters_rejects_oversized_blob() {
+    let max = KeyParameterValue::APPLICATION_ID_MAX_SIZE;
+
+    let list = encode_application_id_list(max);
+    let params = LegacyBlobLoader::read_key_parameters(&mut &list[..]).unwrap();
+    assert_eq!(params, vec![KeyParameterValue::ApplicationID(vec![0xaa; max])]);
+
+    let list = encode_application_id_list(max + 1);
+    let error = LegacyBlobLoader::read_key_parameters(&mut &list[..]).unwrap_err();
+    assert!(matches!(
+        error.root_cause().downcast_ref::<KeyParameterError>(),
+        Some(KeyParameterError::BlobTooLarge(_))
+    ));
+}
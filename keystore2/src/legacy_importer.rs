@@ -19,7 +19,7 @@ use crate::database::{
     KeyMetaEntry, KeyType, KeystoreDB, Uuid, KEYSTORE_UUID,
 };
 use crate::error::{map_km_error, Error};
-use crate::key_parameter::{KeyParameter, KeyParameterValue};
+use crate::key_parameter::{retain_storable, KeyParameter, KeyParameterValue};
 use crate::ks_err;
 use crate::legacy_blob::{self, Blob, BlobValue, LegacyKeyCharacteristics};
 use crate::super_key::USER_AFTER_FIRST_UNLOCK_SUPER_KEY;
@@ -634,6 +634,7 @@ impl LegacyImporterState {
 
         let result = match km_blob_params {
             Some((km_blob, params)) => {
+                let params = retain_storable(params);
                 let is_strongbox = km_blob.is_strongbox();
 
                 let (blob, mut blob_metadata) = match km_blob.take_value() {
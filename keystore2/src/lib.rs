@@ -13,10 +13,12 @@
 // limitations under the License.
 
 //! This crate implements the Android Keystore 2.0 service.
-#![recursion_limit = "256"]
+#![recursion_limit = "512"]
 
 pub mod apc;
 pub mod async_task;
+/// User authentication requirements derived from key parameters.
+pub mod auth_requirements;
 pub mod authorization;
 pub mod boot_level_keys;
 pub mod database;
@@ -26,8 +28,14 @@ pub mod entropy;
 pub mod error;
 pub mod globals;
 pub mod id_rotation;
+/// Hardware and software enforced key parameters of a key.
+pub mod key_characteristics;
 /// Internal Representation of Key Parameter and convenience functions.
 pub mod key_parameter;
+/// Validating builder for sets of key parameters.
+pub mod key_parameter_set;
+/// Validation of KeyMint key characteristics against the key generation request.
+pub mod key_validation;
 pub mod legacy_blob;
 pub mod legacy_importer;
 pub mod maintenance;
@@ -19,7 +19,7 @@
 
 use crate::error::anyhow_error_to_serialized_error;
 use crate::globals::DB;
-use crate::key_parameter::KeyParameterValue as KsKeyParamValue;
+use crate::key_parameter::KeyParameterValueRef as KsKeyParamValueRef;
 use crate::ks_err;
 use crate::operation::Outcome;
 use android_hardware_security_keymint::aidl::android::hardware::security::keymint::{
@@ -223,9 +223,9 @@ fn process_key_creation_event_stats<U>(
 
     key_creation_with_auth_info.security_level = process_security_level(sec_level);
 
-    for key_param in key_params.iter().map(KsKeyParamValue::from) {
+    for key_param in key_params.iter().map(KsKeyParamValueRef::from_km_parameter_lossy) {
         match key_param {
-            KsKeyParamValue::Algorithm(a) => {
+            KsKeyParamValueRef::Algorithm(a) => {
                 let algorithm = match a {
                     Algorithm::RSA => MetricsAlgorithm::RSA,
                     Algorithm::EC => MetricsAlgorithm::EC,
@@ -237,10 +237,10 @@ fn process_key_creation_event_stats<U>(
                 key_creation_with_general_info.algorithm = algorithm;
                 key_creation_with_purpose_and_modes_info.algorithm = algorithm;
             }
-            KsKeyParamValue::KeySize(s) => {
+            KsKeyParamValueRef::KeySize(s) => {
                 key_creation_with_general_info.key_size = s;
             }
-            KsKeyParamValue::KeyOrigin(o) => {
+            KsKeyParamValueRef::KeyOrigin(o) => {
                 key_creation_with_general_info.key_origin = match o {
                     KeyOrigin::GENERATED => MetricsKeyOrigin::GENERATED,
                     KeyOrigin::DERIVED => MetricsKeyOrigin::DERIVED,
@@ -250,7 +250,7 @@ fn process_key_creation_event_stats<U>(
                     _ => MetricsKeyOrigin::ORIGIN_UNSPECIFIED,
                 }
             }
-            KsKeyParamValue::HardwareAuthenticatorType(a) => {
+            KsKeyParamValueRef::HardwareAuthenticatorType(a) => {
                 key_creation_with_auth_info.user_auth_type = match a {
                     HardwareAuthenticatorType::NONE => MetricsHardwareAuthenticatorType::NONE,
                     HardwareAuthenticatorType::PASSWORD => {
@@ -263,36 +263,36 @@ fn process_key_creation_event_stats<U>(
                     _ => MetricsHardwareAuthenticatorType::AUTH_TYPE_UNSPECIFIED,
                 }
             }
-            KsKeyParamValue::AuthTimeout(t) => {
+            KsKeyParamValueRef::AuthTimeout(t) => {
                 key_creation_with_auth_info.log10_auth_key_timeout_seconds =
                     f32::log10(t as f32) as i32;
             }
-            KsKeyParamValue::PaddingMode(p) => {
+            KsKeyParamValueRef::PaddingMode(p) => {
                 compute_padding_mode_bitmap(
                     &mut key_creation_with_purpose_and_modes_info.padding_mode_bitmap,
                     p,
                 );
             }
-            KsKeyParamValue::Digest(d) => {
+            KsKeyParamValueRef::Digest(d) => {
                 // key_creation_with_purpose_and_modes_info.digest_bitmap =
                 compute_digest_bitmap(
                     &mut key_creation_with_purpose_and_modes_info.digest_bitmap,
                     d,
                 );
             }
-            KsKeyParamValue::BlockMode(b) => {
+            KsKeyParamValueRef::BlockMode(b) => {
                 compute_block_mode_bitmap(
                     &mut key_creation_with_purpose_and_modes_info.block_mode_bitmap,
                     b,
                 );
             }
-            KsKeyParamValue::KeyPurpose(k) => {
+            KsKeyParamValueRef::KeyPurpose(k) => {
                 compute_purpose_bitmap(
                     &mut key_creation_with_purpose_and_modes_info.purpose_bitmap,
                     k,
                 );
             }
-            KsKeyParamValue::EcCurve(e) => {
+            KsKeyParamValueRef::EcCurve(e) => {
                 key_creation_with_general_info.ec_curve = match e {
                     EcCurve::P_224 => MetricsEcCurve::P_224,
                     EcCurve::P_256 => MetricsEcCurve::P_256,
@@ -302,7 +302,7 @@ fn process_key_creation_event_stats<U>(
                     _ => MetricsEcCurve::EC_CURVE_UNSPECIFIED,
                 }
             }
-            KsKeyParamValue::AttestationChallenge(_) => {
+            KsKeyParamValueRef::AttestationChallenge(_) => {
                 key_creation_with_general_info.attestation_requested = true;
             }
             _ => {}
@@ -395,21 +395,21 @@ fn process_key_operation_event_stats(
         }
     };
 
-    for key_param in op_params.iter().map(KsKeyParamValue::from) {
+    for key_param in op_params.iter().map(KsKeyParamValueRef::from_km_parameter_lossy) {
         match key_param {
-            KsKeyParamValue::PaddingMode(p) => {
+            KsKeyParamValueRef::PaddingMode(p) => {
                 compute_padding_mode_bitmap(
                     &mut key_operation_with_purpose_and_modes_info.padding_mode_bitmap,
                     p,
                 );
             }
-            KsKeyParamValue::Digest(d) => {
+            KsKeyParamValueRef::Digest(d) => {
                 compute_digest_bitmap(
                     &mut key_operation_with_purpose_and_modes_info.digest_bitmap,
                     d,
                 );
             }
-            KsKeyParamValue::BlockMode(b) => {
+            KsKeyParamValueRef::BlockMode(b) => {
                 compute_block_mode_bitmap(
                     &mut key_operation_with_purpose_and_modes_info.block_mode_bitmap,
                     b,
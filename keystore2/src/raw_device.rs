@@ -22,6 +22,7 @@ use crate::{
     },
     error::{map_km_error, Error, ErrorCode},
     globals::get_keymint_device,
+    key_parameter::retain_storable,
     ks_err,
     super_key::KeyBlob,
     utils::{key_characteristics_to_internal, watchdog as wd, AID_KEYSTORE},
@@ -115,7 +116,8 @@ impl KeyMintDevice {
     {
         let creation_result =
             map_km_error(creator(&self.km_dev)).context(ks_err!("creator failed"))?;
-        let key_parameters = key_characteristics_to_internal(creation_result.keyCharacteristics);
+        let key_parameters =
+            retain_storable(key_characteristics_to_internal(creation_result.keyCharacteristics));
 
         let creation_date = DateTime::now().context(ks_err!("DateTime::now() failed"))?;
 
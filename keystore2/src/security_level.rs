@@ -27,6 +27,7 @@ use crate::globals::{
 };
 use crate::key_parameter::KeyParameter as KsKeyParam;
 use crate::key_parameter::KeyParameterValue as KsKeyParamValue;
+use crate::key_parameter::{check_cardinality, retain_storable};
 use crate::ks_err;
 use crate::metrics_store::log_key_creation_event_stats;
 use crate::remote_provisioning::RemProvState;
@@ -148,13 +149,17 @@ impl KeystoreSecurityLevel {
             },
         );
 
-        let mut key_parameters = key_characteristics_to_internal(key_characteristics);
+        let mut key_parameters =
+            retain_storable(key_characteristics_to_internal(key_characteristics));
 
         key_parameters.push(KsKeyParam::new(
             KsKeyParamValue::UserID(user_id as i32),
             SecurityLevel::SOFTWARE,
         ));
 
+        check_cardinality(&key_parameters)
+            .context(ks_err!("KeyMint returned conflicting key characteristics."))?;
+
         let creation_date = DateTime::now().context(ks_err!("Trying to make creation time."))?;
 
         let key = match key.domain {
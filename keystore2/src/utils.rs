@@ -17,6 +17,7 @@
 
 use crate::error::{map_binder_status, map_km_error, Error, ErrorCode};
 use crate::key_parameter::KeyParameter;
+use crate::key_parameter::KeyParameterValue as KsKeyParamValue;
 use crate::ks_err;
 use crate::permission;
 use crate::permission::{KeyPerm, KeyPermSet, KeystorePerm};
@@ -189,10 +190,9 @@ pub fn key_characteristics_to_internal(
         .into_iter()
         .flat_map(|aidl_key_char| {
             let sec_level = aidl_key_char.securityLevel;
-            aidl_key_char
-                .authorizations
-                .into_iter()
-                .map(move |aidl_kp| KeyParameter::new(aidl_kp.into(), sec_level))
+            aidl_key_char.authorizations.into_iter().map(move |aidl_kp| {
+                KeyParameter::new(KsKeyParamValue::from_km_parameter_lossy(aidl_kp), sec_level)
+            })
         })
         .collect()
 }
@@ -451,7 +451,7 @@ where
 pub fn key_parameters_to_authorizations(
     parameters: Vec<crate::key_parameter::KeyParameter>,
 ) -> Vec<Authorization> {
-    parameters.into_iter().map(|p| p.into_authorization()).collect()
+    crate::key_parameter::KeyParameter::into_authorizations(parameters)
 }
 
 #[allow(clippy::unnecessary_cast)]